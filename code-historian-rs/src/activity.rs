@@ -0,0 +1,173 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use chrono::{Datelike, Timelike, Weekday};
+use serde::{Serialize, Deserialize};
+use crate::analyzer::Change;
+use crate::config::ActivityConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ActivityFlag {
+    AfterHours,
+    Weekend,
+}
+
+/// When commits were made, in each author's local time.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActivityProfile {
+    pub commits: usize,
+    // Commits by weekday (Monday first) and hour of day
+    pub punch_card: [[usize; 24]; 7],
+    // Weekday commits outside working hours
+    pub after_hours: usize,
+    pub weekend: usize,
+}
+
+impl ActivityProfile {
+    fn record(&mut self, weekday: Weekday, hour: u32, config: &ActivityConfig) {
+        self.commits += 1;
+        self.punch_card[weekday.num_days_from_monday() as usize][hour as usize] += 1;
+
+        if matches!(weekday, Weekday::Sat | Weekday::Sun) {
+            self.weekend += 1;
+        } else if !is_working_hour(hour, config) {
+            self.after_hours += 1;
+        }
+    }
+
+    pub fn after_hours_share(&self) -> f64 {
+        share(self.after_hours, self.commits)
+    }
+
+    pub fn weekend_share(&self) -> f64 {
+        share(self.weekend, self.commits)
+    }
+
+    fn flags(&self, config: &ActivityConfig) -> Vec<ActivityFlag> {
+        let mut flags = Vec::new();
+        if self.after_hours_share() > config.flag_threshold {
+            flags.push(ActivityFlag::AfterHours);
+        }
+        if self.weekend_share() > config.flag_threshold {
+            flags.push(ActivityFlag::Weekend);
+        }
+        flags
+    }
+}
+
+fn share(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GroupActivity {
+    pub name: String,
+    pub profile: ActivityProfile,
+    pub flags: Vec<ActivityFlag>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActivityAnalysis {
+    pub overall: ActivityProfile,
+    pub authors: Vec<GroupActivity>,
+    pub teams: Vec<GroupActivity>,
+}
+
+pub fn is_working_hour(hour: u32, config: &ActivityConfig) -> bool {
+    hour >= config.work_start_hour && hour < config.work_end_hour
+}
+
+/// Build activity profiles from each commit's local time.
+///
+/// Team members are matched by author name or email.
+pub fn analyze(changes: &[Change], config: &ActivityConfig) -> ActivityAnalysis {
+    let mut analysis = ActivityAnalysis::default();
+    let mut authors: HashMap<&str, ActivityProfile> = HashMap::new();
+    let mut teams: BTreeMap<&str, ActivityProfile> = BTreeMap::new();
+    let mut seen = HashSet::new();
+
+    for change in changes {
+        // Changes are per file; activity is per commit
+        if !seen.insert(change.commit_id.as_str()) {
+            continue;
+        }
+
+        let local = change.local_timestamp();
+        let (weekday, hour) = (local.weekday(), local.hour());
+
+        analysis.overall.record(weekday, hour, config);
+        authors.entry(change.author.as_str()).or_default().record(weekday, hour, config);
+
+        for (team, members) in &config.teams {
+            let member = members.iter().any(|m| {
+                m == &change.author || m.eq_ignore_ascii_case(&change.author_email)
+            });
+            if member {
+                teams.entry(team.as_str()).or_default().record(weekday, hour, config);
+            }
+        }
+    }
+
+    let group = |name: &str, profile: ActivityProfile| GroupActivity {
+        name: name.to_string(),
+        flags: profile.flags(config),
+        profile,
+    };
+
+    analysis.authors = authors.into_iter().map(|(name, profile)| group(name, profile)).collect();
+    analysis.authors.sort_by(|a, b| b.profile.commits.cmp(&a.profile.commits).then_with(|| a.name.cmp(&b.name)));
+    analysis.teams = teams.into_iter().map(|(name, profile)| group(name, profile)).collect();
+
+    analysis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn change(commit_id: &str, author: &str, utc_hour: u32, day: u32, offset_hours: i32) -> Change {
+        Change {
+            commit_id: commit_id.to_string(),
+            author: author.to_string(),
+            author_email: format!("{}@example.com", author.to_lowercase()),
+            // 2024-01-01 is a Monday
+            timestamp: Utc.with_ymd_and_hms(2024, 1, day, utc_hour, 0, 0).unwrap(),
+            timezone_offset: offset_hours * 60,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_local_time_and_flags() {
+        let changes = vec![
+            // 08:00 UTC is 17:00 in Tokyo, 03:00 in New York
+            change("a", "Ann", 8, 1, 9),
+            change("a", "Ann", 8, 1, 9),
+            change("b", "Bob", 8, 1, -5),
+            change("c", "Bob", 12, 6, -5),
+            change("d", "Bob", 15, 2, -5),
+        ];
+        let mut config = ActivityConfig::default();
+        config.teams.insert("platform".to_string(), vec!["bob@example.com".to_string()]);
+
+        let activity = analyze(&changes, &config);
+        assert_eq!(activity.overall.commits, 4);
+        assert_eq!(activity.overall.punch_card[0][17], 1);
+        assert_eq!(activity.overall.punch_card[0][3], 1);
+        assert_eq!(activity.overall.punch_card[5][7], 1);
+        assert_eq!(activity.overall.after_hours, 1);
+        assert_eq!(activity.overall.weekend, 1);
+
+        assert_eq!(activity.authors[0].name, "Bob");
+        assert_eq!(activity.authors[0].flags, vec![ActivityFlag::AfterHours, ActivityFlag::Weekend]);
+        assert!(activity.authors[1].flags.is_empty());
+
+        assert_eq!(activity.teams.len(), 1);
+        assert_eq!(activity.teams[0].name, "platform");
+        assert_eq!(activity.teams[0].profile.commits, 3);
+    }
+}
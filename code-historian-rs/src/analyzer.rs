@@ -1,33 +1,133 @@
 use std::path::{Path, PathBuf};
-use std::collections::HashMap;
-use chrono::{DateTime, Utc};
-use git2::{Repository, Commit, DiffOptions};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use chrono::{DateTime, FixedOffset, Offset, Utc};
+use git2::{Repository, Commit, DiffOptions, Mailmap, Patch};
 use serde::{Serialize, Deserialize};
 use crate::{Result, HistorianError, Config, Category, PluginManager};
+use crate::activity::{self, ActivityAnalysis};
+use crate::attributes::{self, ExclusionSummary, FileOrigin, OriginDetector};
+use crate::blobs::{self, BlobChange, LargeFileReport};
+use crate::components::{self, ComponentCoupling, ComponentResolver, ComponentSummary};
+use crate::dependencies::{self, DependencyChange, ManifestKind};
+use crate::diff::{self, DiffLine};
+use crate::feedback::{self, Correction, FeedbackStore};
+use crate::ignore::{IgnoreMatcher, PathFilter};
+use crate::lint::{self, MessageQuality};
+use crate::mechanical::{self, MechanicalKind};
+use crate::ml::{self, CategoryScore, ChangeClassifier, NaiveBayesModel};
+use crate::query::Query;
+use crate::releases::{self, ReleaseInterval};
+use crate::search::{self, SearchIndex};
+use crate::signatures::{self, CommitSignature, SignatureVerifier, SigningCoverage};
+use crate::szz::{DefectReport, SzzAnalyzer};
+use crate::testing::{self, CodeKind, TestEvolution};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Analysis {
     pub changes: Vec<Change>,
     pub metrics: Metrics,
     pub patterns: Vec<Pattern>,
     pub cache_info: Option<CacheInfo>,
+    #[serde(default)]
+    pub defects: Option<DefectReport>,
+    #[serde(default)]
+    pub dependency_timeline: Vec<DependencyChange>,
+    #[serde(default)]
+    pub test_evolution: TestEvolution,
+    #[serde(default)]
+    pub large_files: LargeFileReport,
+    #[serde(default)]
+    pub exclusions: ExclusionSummary,
+    #[serde(default)]
+    pub components: Vec<ComponentSummary>,
+    #[serde(default)]
+    pub component_coupling: Vec<ComponentCoupling>,
+    #[serde(default)]
+    pub releases: Vec<ReleaseInterval>,
+    #[serde(default)]
+    pub message_quality: MessageQuality,
+    #[serde(default)]
+    pub activity: ActivityAnalysis,
+    #[serde(default)]
+    pub signing: SigningCoverage,
+    // Filter expression the changes were narrowed down with
+    #[serde(default)]
+    pub filter: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Change {
     pub commit_id: String,
     pub author: String,
+    #[serde(default)]
+    pub author_email: String,
     pub timestamp: DateTime<Utc>,
+    // Minutes east of UTC where the commit was made
+    #[serde(default)]
+    pub timezone_offset: i32,
     pub message: String,
     pub file_path: PathBuf,
     pub diff: String,
     pub categories: Vec<Category>,
+    // Confidence and evidence behind each category, when classified
+    #[serde(default)]
+    pub category_scores: Vec<CategoryScore>,
     pub impact_score: f64,
     pub metrics: HashMap<String, f64>,
     pub annotations: Vec<String>,
+    #[serde(default)]
+    pub fix_inducing: bool,
+    #[serde(default)]
+    pub code_kind: CodeKind,
+    #[serde(default)]
+    pub blob: Option<BlobChange>,
+    #[serde(default)]
+    pub mechanical: Option<MechanicalKind>,
+    #[serde(default)]
+    pub origin: FileOrigin,
+    #[serde(default)]
+    pub component: Option<String>,
+    #[serde(default)]
+    pub signature: Option<CommitSignature>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Change {
+    /// The commit time in the author's own timezone.
+    pub fn local_timestamp(&self) -> DateTime<FixedOffset> {
+        let offset = FixedOffset::east_opt(self.timezone_offset * 60).unwrap_or_else(|| Utc.fix());
+        self.timestamp.with_timezone(&offset)
+    }
+
+    /// Adds a category with its score, replacing any earlier score for it.
+    pub fn add_category(&mut self, score: CategoryScore) {
+        if !self.categories.contains(&score.category) {
+            self.categories.push(score.category.clone());
+        }
+        self.category_scores.retain(|s| s.category != score.category);
+        self.category_scores.push(score);
+    }
+
+    pub fn category_score(&self, category: &Category) -> Option<&CategoryScore> {
+        self.category_scores.iter().find(|s| s.category == *category)
+    }
+
+    pub fn line_counts(&self) -> (usize, usize) {
+        let mut added = 0;
+        let mut removed = 0;
+
+        for line in diff::lines(&self.diff) {
+            match line {
+                DiffLine::Added(_) => added += 1,
+                DiffLine::Removed(_) => removed += 1,
+                _ => {}
+            }
+        }
+
+        (added, removed)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Metrics {
     pub total_commits: usize,
     pub total_changes: usize,
@@ -35,6 +135,45 @@ pub struct Metrics {
     pub lines_removed: usize,
     pub category_distribution: HashMap<Category, usize>,
     pub impact_distribution: HashMap<String, f64>,
+    #[serde(default)]
+    pub mechanical_changes: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Day,
+    Week,
+    #[default]
+    Month,
+}
+
+impl Period {
+    pub fn key(&self, timestamp: &DateTime<Utc>) -> String {
+        match self {
+            Period::Day => timestamp.format("%Y-%m-%d").to_string(),
+            Period::Week => timestamp.format("%G-W%V").to_string(),
+            Period::Month => timestamp.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// Which uncommitted changes `Analyzer::analyze_uncommitted` looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UncommittedScope {
+    // The index compared with HEAD, i.e. what the next commit will contain
+    Staged,
+    // Everything in the working tree, including untracked files
+    Worktree,
+}
+
+impl UncommittedScope {
+    fn label(&self) -> &'static str {
+        match self {
+            UncommittedScope::Staged => "STAGED",
+            UncommittedScope::Worktree => "WORKTREE",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,16 +195,43 @@ pub struct CacheInfo {
 pub struct Analyzer {
     config: Config,
     plugin_manager: PluginManager,
+    classifier: ChangeClassifier,
+    // Repository the classifier's model was loaded for
+    classifier_repo: Option<PathBuf>,
+    filter: PathFilter,
+    // Tree the ignore files in `filter` were read from
+    ignore_tree: Option<git2::Oid>,
+    components: ComponentResolver,
+    query: Option<Query>,
     cache: Option<Analysis>,
 }
 
 impl Analyzer {
-    pub fn new(config: Config, plugin_manager: PluginManager) -> Self {
-        Self {
+    pub fn new(config: Config, plugin_manager: PluginManager) -> Result<Self> {
+        let filter = PathFilter::new(
+            &config.analysis.include_patterns,
+            &config.analysis.exclude_patterns,
+        )?;
+        // Explicit definitions are validated here; discovery needs the repository
+        let components = ComponentResolver::new(&config.components, None)?;
+
+        Ok(Self {
             config,
             plugin_manager,
+            classifier: ChangeClassifier::default(),
+            classifier_repo: None,
+            filter,
+            ignore_tree: None,
+            components,
+            query: None,
             cache: None,
-        }
+        })
+    }
+
+    /// Only keep changes matching `query`; metrics and reports cover just those.
+    pub fn with_query(mut self, query: Query) -> Self {
+        self.query = Some(query);
+        self
     }
 
     pub fn analyze(&mut self, repo_path: &Path, paths: Option<Vec<PathBuf>>) -> Result<Analysis> {
@@ -73,11 +239,16 @@ impl Analyzer {
         let head = repo.head()?;
         let head_commit = head.peel_to_commit()?;
 
-        // Try to load cache
-        if self.config.cache.enabled {
+        // Try to load cache; it always holds the unfiltered analysis
+        let use_cache = self.config.cache.enabled && self.query.is_none();
+        let full_history = paths.is_none();
+        if use_cache {
             if let Some(cached) = self.load_cache(repo_path)? {
                 if let Some(cache_info) = &cached.cache_info {
-                    if cache_info.last_commit == head_commit.id().to_string() {
+                    // Corrections recorded since the cache was written need a fresh analysis
+                    if cache_info.last_commit == head_commit.id().to_string()
+                        && !feedback::is_pending(&cached.changes, &self.corrections(repo_path)?)
+                    {
                         // Cache is still valid
                         if let Some(paths) = &paths {
                             // Check if all requested paths are in cache
@@ -92,19 +263,8 @@ impl Analyzer {
             }
         }
 
-        let mut analysis = Analysis {
-            changes: Vec::new(),
-            metrics: Metrics {
-                total_commits: 0,
-                total_changes: 0,
-                lines_added: 0,
-                lines_removed: 0,
-                category_distribution: HashMap::new(),
-                impact_distribution: HashMap::new(),
-            },
-            patterns: Vec::new(),
-            cache_info: None,
-        };
+        let mut analysis = Analysis::default();
+        self.load_repository_settings(&repo, repo_path)?;
 
         // Set up diff options
         let mut diff_opts = DiffOptions::new();
@@ -119,6 +279,13 @@ impl Analyzer {
         // Analyze commits
         let mut revwalk = repo.revwalk()?;
         revwalk.push_head()?;
+        let mut blob_stats = Vec::new();
+        // Merged branches are sized, and their dependency changes dated, through their merge's
+        // first-parent diff, so only the mainline counts
+        let mainline = blobs::first_parent_chain(&repo, head_commit.id())?;
+        let origins = OriginDetector::new(&repo, &self.config.generated)?;
+        let mailmap = repo.mailmap()?;
+        let verifier = SignatureVerifier::new(&self.config.signatures, repo.workdir().unwrap_or(repo_path));
 
         for oid in revwalk {
             let commit_id = oid?;
@@ -131,21 +298,101 @@ impl Analyzer {
                     Some(&mut diff_opts),
                 )?;
 
-                let changes = self.analyze_commit(&commit, &diff)?;
+                let mut changes = self.analyze_commit(&repo, &origins, &mailmap, &commit, &diff)?;
+                if self.config.signatures.enabled {
+                    let signature = verifier.verify(&repo, commit_id)?;
+                    for change in &mut changes {
+                        change.signature = signature.clone();
+                    }
+                }
                 analysis.changes.extend(changes);
+
+                if mainline.contains(&commit_id) {
+                    analysis.dependency_timeline
+                        .extend(dependencies::commit_dependency_changes(&repo, &mailmap, &commit, &diff)?);
+                    blob_stats.push(self.scan_blobs(&repo, &commit, &diff)?);
+                }
+            } else if mainline.contains(&commit_id) {
+                // The root commit establishes the baseline repository size
+                let diff = repo.diff_tree_to_tree(None, Some(&commit.tree()?), Some(&mut diff_opts))?;
+                blob_stats.push(self.scan_blobs(&repo, &commit, &diff)?);
             }
 
             analysis.metrics.total_commits += 1;
         }
 
+        analysis.dependency_timeline.sort_by_key(|c| c.timestamp);
+        analysis.large_files = blobs::build_report(self.config.large_files.threshold_bytes, blob_stats);
+
+        // Tag formatting runs, moves and explicitly ignored revisions
+        if self.config.mechanical.enabled {
+            let ignore_revs = mechanical::load_ignore_revs(&repo, self.config.mechanical.ignore_revs_file.as_deref())?;
+            for change in &mut analysis.changes {
+                if ignore_revs.contains(&change.commit_id) {
+                    change.mechanical = Some(MechanicalKind::IgnoredRevision);
+                }
+            }
+            mechanical::detect_moves(&mut analysis.changes);
+        }
+
+        // Learn from this history's labeled commits before classifying it
+        if self.config.core.ml_enabled && self.config.ml.auto_train {
+            self.train_classifier(repo_path, &analysis.changes, &[])?;
+        }
+
+        // Categorize changes, letting people's corrections override the classifier
+        self.categorize(&mut analysis.changes)?;
+        feedback::apply(&mut analysis.changes, &self.corrections(repo_path)?);
+
+        // Trace fixes back to the commits that introduced them
+        if self.config.defects.enabled {
+            let szz = SzzAnalyzer::new(&repo, &self.config.defects);
+            analysis.defects = Some(szz.analyze(&mut analysis.changes)?);
+        }
+
+        // Score impact
+        for change in &mut analysis.changes {
+            change.impact_score = self.calculate_impact(change);
+        }
+
+        if let Some(query) = &self.query {
+            analysis.changes.retain(|change| query.matches(change));
+            analysis.filter = Some(query.source().to_string());
+        }
+
         // Update metrics
         self.update_metrics(&mut analysis)?;
+        analysis.exclusions = attributes::summarize(&analysis.changes, &self.config.generated);
+        let counted: Vec<Change> = analysis.changes.iter()
+            .filter(|c| !self.is_excluded_mechanical(c) && !self.is_excluded_origin(c))
+            .cloned()
+            .collect();
+        analysis.test_evolution = testing::compute_test_evolution(
+            &counted,
+            self.config.testing.period,
+            self.config.testing.hotspot_min_changes,
+        );
+
+        if self.config.message_lint.enabled {
+            analysis.message_quality = lint::assess(&analysis.changes, &self.config.message_lint);
+        }
+        if self.config.activity.enabled {
+            analysis.activity = activity::analyze(&analysis.changes, &self.config.activity);
+        }
+        if self.config.signatures.enabled {
+            analysis.signing = signatures::coverage(&analysis.changes, &self.config.signatures);
+        }
 
         // Detect patterns
         self.detect_patterns(&mut analysis)?;
 
+        // Split history at release tags
+        if self.config.releases.enabled {
+            analysis.releases = self.partition_releases(&repo, &analysis.changes)?;
+        }
+
         // Update cache info
-        if self.config.cache.enabled {
+        if use_cache {
             analysis.cache_info = Some(CacheInfo {
                 last_commit: head_commit.id().to_string(),
                 timestamp: Utc::now(),
@@ -154,142 +401,475 @@ impl Analyzer {
 
             // Save to cache
             self.save_cache(repo_path, &analysis)?;
+
+            if self.config.search.enabled && full_history {
+                self.update_search_index(repo_path, &head_commit.id().to_string(), &analysis.changes)?;
+            }
+        }
+
+        Ok(analysis)
+    }
+
+    /// Trains the classifier on labeled changes, saves it to `model_dir` and classifies with it from now on.
+    ///
+    /// Recorded corrections come first, then `labels`, then Conventional Commit types; None when
+    /// nothing is labeled.
+    pub fn train_classifier(
+        &mut self,
+        repo_path: &Path,
+        changes: &[Change],
+        labels: &[(String, Vec<Category>)],
+    ) -> Result<Option<NaiveBayesModel>> {
+        let max_examples = self.config.ml.max_training_size;
+        let usable = changes.iter()
+            .filter(|c| c.blob.is_none() && !self.is_excluded_origin(c));
+        let (mut examples, unlabeled) = feedback::training_examples(usable, &self.corrections(repo_path)?);
+        examples.truncate(max_examples);
+        examples.extend(ml::training_examples(unlabeled, labels, max_examples - examples.len()));
+        if examples.is_empty() {
+            return Ok(None);
+        }
+
+        let model = NaiveBayesModel::train(&examples);
+        model.save(&self.config.model_dir(repo_path)?.join(ml::MODEL_FILE))?;
+        self.classifier = ChangeClassifier::from_config(&self.config, repo_path)?
+            .with_model(model.clone(), self.config.ml.mode, self.config.ml.min_confidence);
+        self.classifier_repo = Some(repo_path.to_path_buf());
+        Ok(Some(model))
+    }
+
+    /// The full-text search index, brought up to date with HEAD.
+    ///
+    /// Normally maintained alongside the analysis cache; built here on demand when missing or stale.
+    pub fn search_index(&mut self, repo_path: &Path) -> Result<SearchIndex> {
+        let head = Repository::open(repo_path)?.head()?.peel_to_commit()?.id().to_string();
+        let index = SearchIndex::load(&self.cache_dir(repo_path)?.join(search::INDEX_FILE))?;
+        if index.head() == Some(head.as_str()) {
+            return Ok(index);
+        }
+
+        // The index covers every change, whatever this analyzer's query
+        let query = self.query.take();
+        let analysis = self.analyze(repo_path, None);
+        self.query = query;
+        self.update_search_index(repo_path, &head, &analysis?.changes)
+    }
+
+    /// Categorize and score changes that have not been committed yet.
+    ///
+    /// Runs the same classifier, plugins and impact model as `analyze`; history-based
+    /// passes such as SZZ and release partitioning are skipped.
+    pub fn analyze_uncommitted(&mut self, repo_path: &Path, scope: UncommittedScope) -> Result<Analysis> {
+        let repo = Repository::open(repo_path)?;
+        self.load_repository_settings(&repo, repo_path)?;
+
+        // A repository without commits compares against an empty tree
+        let head_tree = match repo.head() {
+            Ok(head) => Some(head.peel_to_tree()?),
+            Err(_) => None,
+        };
+
+        let mut diff_opts = DiffOptions::new();
+        let diff = match scope {
+            UncommittedScope::Staged => repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut diff_opts))?,
+            UncommittedScope::Worktree => {
+                diff_opts.include_untracked(true)
+                    .recurse_untracked_dirs(true)
+                    .show_untracked_content(true);
+                repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut diff_opts))?
+            }
+        };
+
+        let signature = repo.signature().ok();
+        let base = Change {
+            commit_id: scope.label().to_string(),
+            author: signature.as_ref().and_then(|s| s.name()).unwrap_or("unknown").to_string(),
+            author_email: signature.as_ref().and_then(|s| s.email()).unwrap_or("").to_string(),
+            timestamp: Utc::now(),
+            timezone_offset: chrono::Local::now().offset().local_minus_utc() / 60,
+            ..Change::default()
+        };
+
+        let origins = OriginDetector::new(&repo, &self.config.generated)?;
+        let workdir = match scope {
+            UncommittedScope::Worktree => repo.workdir(),
+            UncommittedScope::Staged => None,
+        };
+
+        let mut analysis = Analysis {
+            changes: self.diff_changes(&repo, &origins, &diff, &base, workdir)?,
+            ..Default::default()
+        };
+
+        if self.config.mechanical.enabled {
+            mechanical::detect_moves(&mut analysis.changes);
+        }
+        self.categorize(&mut analysis.changes)?;
+        for change in &mut analysis.changes {
+            change.impact_score = self.calculate_impact(change);
         }
 
+        self.update_metrics(&mut analysis)?;
+        analysis.exclusions = attributes::summarize(&analysis.changes, &self.config.generated);
+        self.detect_patterns(&mut analysis)?;
+
         Ok(analysis)
     }
 
-    fn analyze_commit(&self, commit: &Commit, diff: &git2::Diff) -> Result<Vec<Change>> {
+    fn analyze_commit(
+        &self,
+        repo: &Repository,
+        origins: &OriginDetector,
+        mailmap: &Mailmap,
+        commit: &Commit,
+        diff: &git2::Diff,
+    ) -> Result<Vec<Change>> {
+        // .mailmap folds an author's old names and addresses into one identity
+        let author = commit.author_with_mailmap(mailmap)?;
+        // Rebases and amends move the commit time; the author time is when the work was done
+        let when = author.when();
+        let base = Change {
+            commit_id: commit.id().to_string(),
+            author: author.name().unwrap_or("unknown").to_string(),
+            author_email: author.email().unwrap_or("").to_string(),
+            timestamp: DateTime::from_timestamp(when.seconds(), 0)
+                .unwrap_or_else(Utc::now),
+            timezone_offset: when.offset_minutes(),
+            message: commit.message().unwrap_or("").to_string(),
+            ..Change::default()
+        };
+
+        self.diff_changes(repo, origins, diff, &base, None)
+    }
+
+    /// Turn each file in `diff` into a `Change` carrying the commit-level fields of `base`.
+    ///
+    /// With `workdir` set, new file contents are read from disk instead of the object database.
+    fn diff_changes(
+        &self,
+        repo: &Repository,
+        origins: &OriginDetector,
+        diff: &git2::Diff,
+        base: &Change,
+        workdir: Option<&Path>,
+    ) -> Result<Vec<Change>> {
         let mut changes = Vec::new();
 
-        diff.foreach(
-            &mut |delta, _| {
-                if let Some(path) = delta.new_file().path() {
-                    let file_path = PathBuf::from(path);
-                    
-                    // Check if file matches include/exclude patterns
-                    if self.should_analyze_file(&file_path) {
-                        // Get diff as text
-                        if let Ok(diff_text) = diff.to_string() {
-                            // Create change record
-                            let change = Change {
-                                commit_id: commit.id().to_string(),
-                                author: commit.author().name().unwrap_or("unknown").to_string(),
-                                timestamp: DateTime::from_timestamp(commit.time().seconds(), 0)
-                                    .unwrap_or_else(|| Utc::now()),
-                                message: commit.message().unwrap_or("").to_string(),
-                                file_path,
-                                diff: diff_text,
-                                categories: Vec::new(),
-                                impact_score: 0.0,
-                                metrics: HashMap::new(),
-                                annotations: Vec::new(),
-                            };
-                            changes.push(change);
-                        }
-                    }
+        for (idx, delta) in diff.deltas().enumerate() {
+            let Some(path) = delta.new_file().path() else {
+                continue;
+            };
+            let file_path = PathBuf::from(path);
+
+            // Check if file matches include/exclude patterns; submodules are other repositories
+            if !self.should_analyze_file(&file_path) || blobs::is_gitlink(&delta) {
+                continue;
+            }
+
+            let mut change = Change {
+                file_path,
+                ..base.clone()
+            };
+            change.component = self.components.resolve(&change.file_path);
+
+            let old_content = blobs::read_blob(repo, delta.old_file().id())?;
+            let new_content = match (delta.status(), workdir) {
+                (git2::Delta::Deleted, _) => None,
+                (_, Some(workdir)) => match read_worktree_file(&workdir.join(&change.file_path))? {
+                    Some(content) => Some(content),
+                    // Nested repositories and other directories have no content of their own
+                    None => continue,
+                },
+                (_, None) => blobs::read_blob(repo, delta.new_file().id())?,
+            };
+
+            // Binaries and LFS pointers are recorded by size only
+            change.blob = blobs::inspect_contents(&delta, old_content.as_deref(), new_content.as_deref());
+            if change.blob.is_some() {
+                change.origin = origins.detect(&change.file_path, None)?;
+                changes.push(change);
+                continue;
+            }
+
+            if let Some(mut patch) = Patch::from_diff(diff, idx)? {
+                change.diff = String::from_utf8_lossy(&patch.to_buf()?).into_owned();
+            }
+
+            let new_text = new_content.as_deref().and_then(|c| std::str::from_utf8(c).ok());
+            change.origin = origins.detect(&change.file_path, new_text)?;
+
+            if self.config.mechanical.enabled && delta.status() == git2::Delta::Modified {
+                let old_text = old_content.as_deref().and_then(|c| std::str::from_utf8(c).ok());
+                if let (Some(old), Some(new)) = (old_text, new_text) {
+                    change.mechanical = mechanical::classify(&change.file_path, old, new);
                 }
-                true
-            },
-            None,
-            None,
-            None,
-        )?;
+            }
+
+            changes.push(change);
+        }
 
         Ok(changes)
     }
 
-    fn should_analyze_file(&self, path: &Path) -> bool {
-        let path_str = path.to_string_lossy();
+    fn categorize(&self, changes: &mut [Change]) -> Result<()> {
+        for change in changes.iter_mut() {
+            // Generated and vendored code says nothing about the team's own work
+            if attributes::is_excluded(change.origin, &self.config.generated) {
+                continue;
+            }
 
-        // Check exclude patterns
-        for pattern in &self.config.analysis.exclude_patterns {
-            if glob::Pattern::new(pattern).unwrap().matches(&path_str) {
-                return false;
+            // Binary content tells the classifier nothing
+            if change.blob.is_some() {
+                if testing::is_test_path(&change.file_path) {
+                    change.code_kind = CodeKind::Test;
+                    change.add_category(CategoryScore::certain(Category::Testing, "test path"));
+                }
+                continue;
             }
-        }
 
-        // Check include patterns
-        for pattern in &self.config.analysis.include_patterns {
-            if glob::Pattern::new(pattern).unwrap().matches(&path_str) {
-                return true;
+            // Mechanical changes carry no intent of their own beyond moving code around
+            match change.mechanical {
+                Some(MechanicalKind::Move) => {
+                    change.add_category(CategoryScore::certain(Category::Refactoring, "moved code"));
+                    continue;
+                }
+                Some(_) => continue,
+                None => {}
             }
+
+            categorize_change(&self.classifier, change)?;
+        }
+
+        Ok(())
+    }
+
+    // Ignore files are read as committed at HEAD, again only when its tree changes; component
+    // discovery needs a working tree, and the trained model lives in the repository's history directory
+    fn load_repository_settings(&mut self, repo: &Repository, repo_path: &Path) -> Result<()> {
+        if self.classifier_repo.as_deref() != Some(repo_path) {
+            self.classifier = ChangeClassifier::from_config(&self.config, repo_path)?;
+            self.classifier_repo = Some(repo_path.to_path_buf());
         }
 
-        false
+        let head_tree = repo.head().and_then(|head| head.peel_to_tree()).ok();
+        let tree_id = head_tree.as_ref().map(|tree| tree.id());
+        if self.ignore_tree != tree_id {
+            let ignore = match &head_tree {
+                Some(tree) => IgnoreMatcher::from_tree(repo, tree)?,
+                None => IgnoreMatcher::default(),
+            };
+            self.filter.set_ignore(ignore);
+            self.ignore_tree = tree_id;
+        }
+
+        if let Some(workdir) = repo.workdir() {
+            self.components = ComponentResolver::new(&self.config.components, Some(workdir))?;
+        }
+        Ok(())
+    }
+
+    fn scan_blobs(&self, repo: &Repository, commit: &Commit, diff: &git2::Diff) -> Result<(String, DateTime<Utc>, blobs::CommitBlobStats)> {
+        let stats = blobs::scan_commit(repo, commit, diff, self.config.large_files.threshold_bytes)?;
+        let timestamp = DateTime::from_timestamp(commit.author().when().seconds(), 0).unwrap_or_else(Utc::now);
+        Ok((commit.id().to_string(), timestamp, stats))
+    }
+
+    fn should_analyze_file(&self, path: &Path) -> bool {
+        self.filter.matches(path)
+    }
+
+    fn is_excluded_mechanical(&self, change: &Change) -> bool {
+        self.config.mechanical.exclude_from_metrics && change.mechanical.is_some()
+    }
+
+    fn is_excluded_origin(&self, change: &Change) -> bool {
+        attributes::is_excluded(change.origin, &self.config.generated)
+    }
+
+    /// Narrows an analysis to some of its changes, with metrics covering just those.
+    pub fn retain_changes(&self, analysis: &mut Analysis, keep: impl Fn(&Change) -> bool) {
+        analysis.changes.retain(|change| keep(change));
+        analysis.metrics = self.compute_metrics(analysis.changes.iter());
     }
 
     fn update_metrics(&self, analysis: &mut Analysis) -> Result<()> {
-        for change in &analysis.changes {
-            analysis.metrics.total_changes += 1;
+        let metrics = self.compute_metrics(analysis.changes.iter());
+        // Unfiltered, commits without file changes (such as the root commit) still count
+        let total_commits = match self.query {
+            Some(_) => metrics.total_commits,
+            None => analysis.metrics.total_commits,
+        };
+        analysis.metrics = Metrics { total_commits, ..metrics };
+        Ok(())
+    }
 
-            // Count lines added/removed
-            for line in change.diff.lines() {
-                if line.starts_with('+') {
-                    analysis.metrics.lines_added += 1;
-                } else if line.starts_with('-') {
-                    analysis.metrics.lines_removed += 1;
-                }
+    fn compute_metrics<'c>(&self, changes: impl Iterator<Item = &'c Change>) -> Metrics {
+        let mut metrics = Metrics::default();
+        let mut commits = HashSet::new();
+
+        for change in changes {
+            if change.mechanical.is_some() {
+                metrics.mechanical_changes += 1;
             }
+            if self.is_excluded_mechanical(change) {
+                continue;
+            }
+
+            commits.insert(change.commit_id.as_str());
+            metrics.total_changes += 1;
+
+            // Count lines added/removed
+            let (added, removed) = change.line_counts();
+            metrics.lines_added += added;
+            metrics.lines_removed += removed;
 
             // Update category distribution
             for category in &change.categories {
-                *analysis.metrics.category_distribution
+                *metrics.category_distribution
                     .entry(category.clone())
                     .or_insert(0) += 1;
             }
 
             // Update impact distribution
-            analysis.metrics.impact_distribution
+            if self.is_excluded_origin(change) {
+                continue;
+            }
+            metrics.impact_distribution
                 .insert(change.file_path.to_string_lossy().into_owned(), change.impact_score);
         }
 
-        Ok(())
+        metrics.total_commits = commits.len();
+        metrics
+    }
+
+    fn calculate_impact(&self, change: &Change) -> f64 {
+        if self.is_excluded_mechanical(change) || self.is_excluded_origin(change) {
+            return 0.0;
+        }
+
+        let (added, removed) = change.line_counts();
+        let size_score = match &change.blob {
+            // Binary changes are sized by kilobytes changed instead of lines
+            Some(blob) => (blob.size_delta.unsigned_abs() as f64 / 1024.0).ln_1p(),
+            None => ((added + removed) as f64).ln_1p(),
+        };
+
+        let category_score = change.categories.iter()
+            .map(category_weight)
+            .fold(0.0, f64::max);
+
+        let mut impact = size_score + category_score * 3.0;
+
+        // Changes that later needed a fix weigh more, scaled by how defect-prone the file is
+        if change.fix_inducing {
+            let density = change.metrics.get("defect_density").copied().unwrap_or(0.0);
+            impact += self.config.defects.impact_weight * (1.0 + density);
+        }
+
+        impact.min(10.0)
+    }
+
+    fn match_patterns(&self, change: &Change) -> Vec<String> {
+        if change.blob.is_some() {
+            return Vec::new();
+        }
+
+        // Analyze with plugins
+        let context = crate::plugin::AnalysisContext {
+            file_path: &change.file_path,
+            content: &change.diff,
+            diff: Some(&change.diff),
+            language: None, // TODO: Detect language
+            config: None,
+        };
+
+        let mut matched = Vec::new();
+        for plugin in self.plugin_manager.get_plugins() {
+            if let Ok(result) = plugin.analyze(&context) {
+                matched.extend(result.patterns);
+            }
+        }
+        matched
     }
 
     fn detect_patterns(&self, analysis: &mut Analysis) -> Result<()> {
-        let mut patterns = HashMap::new();
-
-        for change in &analysis.changes {
-            // Analyze with plugins
-            let context = crate::plugin::AnalysisContext {
-                file_path: &change.file_path,
-                content: &change.diff,
-                diff: Some(&change.diff),
-                language: None, // TODO: Detect language
-                config: None,
-            };
+        let matches: Vec<Vec<String>> = analysis.changes.iter()
+            .map(|change| self.match_patterns(change))
+            .collect();
 
-            for plugin in self.plugin_manager.get_plugins() {
-                if let Ok(result) = plugin.analyze(&context) {
-                    for pattern in result.patterns {
-                        let entry = patterns.entry(pattern).or_insert_with(|| Pattern {
-                            name: pattern.clone(),
-                            description: String::new(),
-                            occurrences: 0,
-                            impact: 0.0,
-                            examples: Vec::new(),
-                        });
-
-                        entry.occurrences += 1;
-                        entry.impact += change.impact_score;
-                        entry.examples.push(format!(
-                            "{}:{} - {}",
-                            change.file_path.display(),
-                            change.commit_id,
-                            change.message
-                        ));
-                    }
+        analysis.patterns = collect_patterns(analysis.changes.iter().zip(&matches));
+
+        // Break the analysis down per component, reusing the plugin results
+        if !self.components.is_empty() {
+            let mut by_component: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+            for (idx, change) in analysis.changes.iter().enumerate() {
+                if let Some(component) = &change.component {
+                    by_component.entry(component).or_default().push(idx);
                 }
             }
+
+            analysis.components = by_component.into_iter()
+                .map(|(name, indices)| ComponentSummary {
+                    name: name.to_string(),
+                    metrics: self.compute_metrics(indices.iter().map(|&i| &analysis.changes[i])),
+                    patterns: collect_patterns(indices.iter().map(|&i| (&analysis.changes[i], &matches[i]))),
+                })
+                .collect();
+            analysis.component_coupling = components::compute_coupling(
+                &analysis.changes,
+                self.config.components.min_co_changes,
+            );
         }
 
-        analysis.patterns = patterns.into_values().collect();
         Ok(())
     }
 
+    fn partition_releases(&self, repo: &Repository, changes: &[Change]) -> Result<Vec<ReleaseInterval>> {
+        let found = releases::find_releases(repo, &self.config.releases)?;
+        if found.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let assigned = releases::assign_commits(repo, &found)?;
+        Ok(releases::partition(changes, &assigned)
+            .into_iter()
+            .map(|(release, changes)| {
+                let metrics = self.compute_metrics(changes.iter().copied());
+                releases::build_interval(release, &found, &changes, metrics, self.config.releases.top_changes)
+            })
+            .collect())
+    }
+
+    fn update_search_index(&self, repo_path: &Path, head: &str, changes: &[Change]) -> Result<SearchIndex> {
+        let index_path = self.cache_dir(repo_path)?.join(search::INDEX_FILE);
+        let mut index = SearchIndex::load(&index_path)?;
+        if index.head() != Some(head) {
+            let repo = Repository::open(repo_path)?;
+            let mut revwalk = repo.revwalk()?;
+            revwalk.push_head()?;
+            let history = revwalk
+                .map(|oid| oid.map(|oid| oid.to_string()))
+                .collect::<std::result::Result<HashSet<_>, _>>()?;
+            index.update(changes, &history, head, self.config.search.snippet_lines);
+            index.save(&index_path)?;
+        }
+        Ok(index)
+    }
+
+    fn corrections(&self, repo_path: &Path) -> Result<Vec<Correction>> {
+        FeedbackStore::new(&self.config.history_dir(repo_path)).load()
+    }
+
+    // Repositories sharing a configuration, as in a workspace, each get their own cache
+    fn cache_dir(&self, repo_path: &Path) -> Result<PathBuf> {
+        let canonical = repo_path.canonicalize()?;
+        let name = canonical.file_name().map_or_else(|| "root".into(), |name| name.to_string_lossy());
+        let hash = git2::Oid::hash_object(git2::ObjectType::Blob, canonical.to_string_lossy().as_bytes())?;
+        Ok(self.config.cache_dir()?.join(format!("{}-{}", name, &hash.to_string()[..8])))
+    }
+
     fn load_cache(&self, repo_path: &Path) -> Result<Option<Analysis>> {
-        let cache_path = self.config.cache_dir()?.join("analysis.json");
+        let cache_path = self.cache_dir(repo_path)?.join("analysis.json");
         if cache_path.exists() {
             let content = std::fs::read_to_string(cache_path)?;
             Ok(Some(serde_json::from_str(&content)?))
@@ -299,7 +879,7 @@ impl Analyzer {
     }
 
     fn save_cache(&self, repo_path: &Path, analysis: &Analysis) -> Result<()> {
-        let cache_path = self.config.cache_dir()?;
+        let cache_path = self.cache_dir(repo_path)?;
         std::fs::create_dir_all(&cache_path)?;
         
         let content = serde_json::to_string_pretty(analysis)?;
@@ -309,6 +889,78 @@ impl Analyzer {
     }
 }
 
+/// Categorizes a text change from its path, message and diff, as an analysis does; also used for
+/// commits looked at outside any analysis.
+pub fn categorize_change(classifier: &ChangeClassifier, change: &mut Change) -> Result<()> {
+    change.category_scores = classifier.score_change(Some(&change.file_path), &change.message, &change.diff)?;
+    change.categories = change.category_scores.iter().map(|s| s.category.clone()).collect();
+
+    // Test code is recognized from paths and test markers rather than assertion keywords
+    change.code_kind = testing::classify_change(&change.file_path, &change.diff);
+    if change.code_kind == CodeKind::Test {
+        change.add_category(CategoryScore::certain(Category::Testing, "test code"));
+    }
+
+    // Dependency changes are recognized from manifests rather than source keywords
+    if ManifestKind::from_path(&change.file_path).is_some() {
+        change.add_category(CategoryScore::certain(Category::Dependencies, "dependency manifest"));
+    }
+    Ok(())
+}
+
+fn collect_patterns<'c>(matches: impl Iterator<Item = (&'c Change, &'c Vec<String>)>) -> Vec<Pattern> {
+    let mut patterns: HashMap<String, Pattern> = HashMap::new();
+
+    for (change, matched) in matches {
+        for pattern in matched {
+            let entry = patterns.entry(pattern.clone()).or_insert_with(|| Pattern {
+                name: pattern.clone(),
+                description: String::new(),
+                occurrences: 0,
+                impact: 0.0,
+                examples: Vec::new(),
+            });
+
+            entry.occurrences += 1;
+            entry.impact += change.impact_score;
+            entry.examples.push(format!(
+                "{}:{} - {}",
+                change.file_path.display(),
+                change.commit_id,
+                change.message
+            ));
+        }
+    }
+
+    patterns.into_values().collect()
+}
+
+fn category_weight(category: &Category) -> f64 {
+    match category {
+        Category::Architecture | Category::Api | Category::Security => 1.0,
+        Category::Logic
+        | Category::Data
+        | Category::ErrorHandling
+        | Category::Performance
+        | Category::Dependencies => 0.7,
+        Category::Refactoring | Category::Configuration | Category::UiUx | Category::Accessibility => 0.5,
+        Category::Logging | Category::Documentation | Category::Testing => 0.3,
+    }
+}
+
+// A symlink is stored as its target, as git does, so broken links read fine; None for directories
+fn read_worktree_file(path: &Path) -> Result<Option<Vec<u8>>> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if metadata.file_type().is_symlink() {
+        let target = std::fs::read_link(path)?;
+        return Ok(Some(target.to_string_lossy().into_owned().into_bytes()));
+    }
+    if !metadata.is_file() {
+        return Ok(None);
+    }
+    Ok(Some(std::fs::read(path)?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -319,7 +971,7 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let config = Config::default();
         let plugin_manager = PluginManager::new(temp_dir.path().to_path_buf());
-        let analyzer = Analyzer::new(config, plugin_manager);
+        let analyzer = Analyzer::new(config, plugin_manager).unwrap();
 
         assert!(analyzer.should_analyze_file(Path::new("src/main.rs")));
         assert!(analyzer.should_analyze_file(Path::new("Cargo.toml")));
@@ -333,25 +985,16 @@ mod tests {
         config.cache.enabled = true;
         
         let plugin_manager = PluginManager::new(temp_dir.path().to_path_buf());
-        let analyzer = Analyzer::new(config, plugin_manager);
+        let analyzer = Analyzer::new(config, plugin_manager).unwrap();
 
         // Test cache save/load
         let analysis = Analysis {
-            changes: Vec::new(),
-            metrics: Metrics {
-                total_commits: 0,
-                total_changes: 0,
-                lines_added: 0,
-                lines_removed: 0,
-                category_distribution: HashMap::new(),
-                impact_distribution: HashMap::new(),
-            },
-            patterns: Vec::new(),
             cache_info: Some(CacheInfo {
                 last_commit: "test".to_string(),
                 timestamp: Utc::now(),
                 paths_analyzed: vec![PathBuf::from("test.rs")],
             }),
+            ..Default::default()
         };
 
         analyzer.save_cache(temp_dir.path(), &analysis).unwrap();
@@ -361,5 +1004,308 @@ mod tests {
             loaded.cache_info.unwrap().last_commit,
             analysis.cache_info.unwrap().last_commit
         );
+
+        // Another repository analyzed with the same configuration has its own cache
+        let other = TempDir::new().unwrap();
+        assert!(analyzer.load_cache(other.path()).unwrap().is_none());
+        assert_ne!(analyzer.cache_dir(other.path()).unwrap(), analyzer.cache_dir(temp_dir.path()).unwrap());
+        assert_eq!(analyzer.cache_dir(&temp_dir.path().join(".")).unwrap(), analyzer.cache_dir(temp_dir.path()).unwrap());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_impact_includes_defects() {
+        let temp_dir = TempDir::new().unwrap();
+        let plugin_manager = PluginManager::new(temp_dir.path().to_path_buf());
+        let analyzer = Analyzer::new(Config::default(), plugin_manager).unwrap();
+
+        let mut change = Change {
+            commit_id: "test".to_string(),
+            author: "Test Author".to_string(),
+            author_email: String::new(),
+            timestamp: Utc::now(),
+            timezone_offset: 0,
+            message: "feat: add parser".to_string(),
+            file_path: PathBuf::from("src/parser.rs"),
+            diff: "+fn parse() {}\n-fn old() {}\n".to_string(),
+            categories: vec![Category::Api],
+            category_scores: Vec::new(),
+            impact_score: 0.0,
+            metrics: HashMap::new(),
+            annotations: Vec::new(),
+            fix_inducing: false,
+            code_kind: CodeKind::Production,
+            blob: None,
+            mechanical: None,
+            origin: FileOrigin::Authored,
+            component: None,
+            signature: None,
+        };
+        assert_eq!(change.line_counts(), (1, 1));
+
+        let base = analyzer.calculate_impact(&change);
+        change.fix_inducing = true;
+        change.metrics.insert("defect_density".to_string(), 0.5);
+
+        assert!(analyzer.calculate_impact(&change) > base);
+        assert!(analyzer.calculate_impact(&change) <= 10.0);
+    }
+
+    #[test]
+    fn test_analyze_uncommitted() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+
+        std::fs::write(temp_dir.path().join("lib.rs"), "fn a() {}\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("lib.rs")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "initial", &tree, &[]).unwrap();
+
+        std::fs::write(temp_dir.path().join("lib.rs"), "fn a() {}\nfn b() -> Result<(), Error> { Ok(()) }\n").unwrap();
+        index.add_path(Path::new("lib.rs")).unwrap();
+        index.write().unwrap();
+        std::fs::write(temp_dir.path().join("notes.rs"), "fn c() {}\n").unwrap();
+
+        let mut config = Config::default();
+        config.cache.enabled = false;
+        let plugin_manager = PluginManager::new(temp_dir.path().to_path_buf());
+        let mut analyzer = Analyzer::new(config, plugin_manager).unwrap();
+
+        let staged = analyzer.analyze_uncommitted(temp_dir.path(), UncommittedScope::Staged).unwrap();
+        assert_eq!(staged.changes.len(), 1);
+        assert_eq!(staged.changes[0].commit_id, "STAGED");
+        assert_eq!(staged.changes[0].line_counts(), (1, 0));
+        assert!(staged.changes[0].impact_score > 0.0);
+
+        let worktree = analyzer.analyze_uncommitted(temp_dir.path(), UncommittedScope::Worktree).unwrap();
+        let mut paths: Vec<&Path> = worktree.changes.iter().map(|c| c.file_path.as_path()).collect();
+        paths.sort();
+        assert_eq!(paths, vec![Path::new("lib.rs"), Path::new("notes.rs")]);
+        assert_eq!(worktree.metrics.total_changes, 2);
+
+        // Nested repositories and broken symlinks do not stop the check
+        let nested = temp_dir.path().join("nested");
+        Repository::init(&nested).unwrap();
+        std::fs::write(nested.join("file.rs"), "fn d() {}\n").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("missing.rs", temp_dir.path().join("link.rs")).unwrap();
+        let worktree = analyzer.analyze_uncommitted(temp_dir.path(), UncommittedScope::Worktree).unwrap();
+        assert!(!worktree.changes.iter().any(|c| c.file_path.starts_with("nested")));
+        #[cfg(unix)]
+        {
+            let link = worktree.changes.iter().find(|c| c.file_path == Path::new("link.rs")).unwrap();
+            assert!(link.diff.contains("+missing.rs"));
+        }
+    }
+
+    #[test]
+    fn test_search_index_follows_head() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let commit = |path: &str, content: &str, message: &str| {
+            std::fs::write(temp_dir.path().join(path), content).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new(path)).unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let parents: Vec<Commit> = repo.head().ok()
+                .map(|head| head.peel_to_commit().unwrap())
+                .into_iter()
+                .collect();
+            let parents: Vec<&Commit> = parents.iter().collect();
+            repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap()
+        };
+
+        commit("lib.rs", "fn a() {}\n", "initial");
+        commit("lib.rs", "fn a() {}\nfn load_settings() {}\n", "Read settings from disk");
+
+        let mut config = Config::default();
+        config.core.history_dir = temp_dir.path().join(".code-historian");
+        let plugin_manager = PluginManager::new(temp_dir.path().to_path_buf());
+        let mut analyzer = Analyzer::new(config, plugin_manager).unwrap();
+
+        let index = analyzer.search_index(temp_dir.path()).unwrap();
+        assert_eq!(index.search("settings", 10).len(), 1);
+
+        let head = commit("lib.rs", "fn a() {}\nfn load_settings() {}\nfn retry() {}\n", "Retry failed reads");
+        let index = analyzer.search_index(temp_dir.path()).unwrap();
+        assert_eq!(index.head(), Some(head.to_string().as_str()));
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.search("retry", 10)[0].commit_id, head.to_string());
+    }
+
+    #[test]
+    fn test_author_time() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let author = git2::Signature::new("Ann", "ann@example.com", &git2::Time::new(1_700_000_000, 120)).unwrap();
+        // Rebased a day later from another timezone
+        let committer = git2::Signature::new("Bob", "bob@example.com", &git2::Time::new(1_700_086_400, -300)).unwrap();
+        let commit = |path: &str, content: &str, message: &str| {
+            std::fs::write(temp_dir.path().join(path), content).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new(path)).unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let parents: Vec<Commit> = repo.head().ok()
+                .map(|head| head.peel_to_commit().unwrap())
+                .into_iter()
+                .collect();
+            let parents: Vec<&Commit> = parents.iter().collect();
+            repo.commit(Some("HEAD"), &author, &committer, message, &tree, &parents).unwrap()
+        };
+        commit("lib.rs", "fn a() {}\n", "initial");
+        commit("lib.rs", "fn a() {}\nfn b() {}\n", "Add b");
+        commit("Cargo.toml", "[dependencies]\nserde = \"1\"\n", "Add serde");
+        std::fs::write(temp_dir.path().join(".mailmap"), "Ann Example <ann@example.com>\n").unwrap();
+
+        let mut config = Config::default();
+        config.core.history_dir = temp_dir.path().join(".code-historian");
+        config.cache.enabled = false;
+        let plugin_manager = PluginManager::new(temp_dir.path().to_path_buf());
+        let mut analyzer = Analyzer::new(config, plugin_manager).unwrap();
+
+        let analysis = analyzer.analyze(temp_dir.path(), None).unwrap();
+        assert_eq!(analysis.changes[0].timestamp.timestamp(), 1_700_000_000);
+        assert_eq!(analysis.changes[0].timezone_offset, 120);
+
+        let dependency = &analysis.dependency_timeline[0];
+        assert_eq!(dependency.timestamp.timestamp(), 1_700_000_000);
+        assert_eq!(dependency.author, "Ann Example");
+
+        let growth = &analysis.large_files.blob_growth;
+        assert_eq!(growth.len(), 3);
+        assert!(growth.iter().all(|point| point.timestamp.timestamp() == 1_700_000_000));
+    }
+
+    #[test]
+    fn test_feedback_overrides_categories() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let commit = |content: &str, message: &str| {
+            std::fs::write(temp_dir.path().join("lib.rs"), content).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new("lib.rs")).unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let parents: Vec<Commit> = repo.head().ok()
+                .map(|head| head.peel_to_commit().unwrap())
+                .into_iter()
+                .collect();
+            let parents: Vec<&Commit> = parents.iter().collect();
+            repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap()
+        };
+        commit("fn a() {}\n", "initial");
+        let head = commit("fn a() {}\nfn check_password() {}\n", "Check passwords");
+
+        let mut config = Config::default();
+        config.core.history_dir = temp_dir.path().join(".code-historian");
+        let plugin_manager = PluginManager::new(temp_dir.path().to_path_buf());
+        let mut analyzer = Analyzer::new(config.clone(), plugin_manager).unwrap();
+
+        let analysis = analyzer.analyze(temp_dir.path(), None).unwrap();
+        assert!(analysis.changes[0].categories.contains(&Category::Security));
+
+        // A correction made after the cache was written still applies
+        let correction = Correction::new(&head.to_string()[..7], vec![Category::Documentation])
+            .with_author(Some("Ann".to_string()));
+        FeedbackStore::new(&config.core.history_dir).record(&correction).unwrap();
+        let analysis = analyzer.analyze(temp_dir.path(), None).unwrap();
+        assert_eq!(analysis.changes[0].categories, vec![Category::Documentation]);
+        assert_eq!(analysis.changes[0].category_scores[0].evidence, vec!["corrected by Ann".to_string()]);
+        assert_eq!(analysis.metrics.category_distribution.get(&Category::Documentation), Some(&1));
+    }
+
+    #[test]
+    fn test_train_classifier() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let mut parent: Option<git2::Oid> = None;
+        for (path, content, message) in [
+            ("README.md", "Install it\n", "Initial"),
+            ("README.md", "Install it with cargo\n", "docs: explain installation"),
+            ("tests/cli.rs", "#[test]\nfn runs() {}\n", "test: cover the CLI"),
+            ("src/lib.rs", "pub fn run() {}\n", "Add run"),
+        ] {
+            std::fs::create_dir_all(temp_dir.path().join(path).parent().unwrap()).unwrap();
+            std::fs::write(temp_dir.path().join(path), content).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new(path)).unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let parents: Vec<Commit> = parent.map(|id| repo.find_commit(id).unwrap()).into_iter().collect();
+            let parents: Vec<&Commit> = parents.iter().collect();
+            parent = Some(repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap());
+        }
+
+        // The default history directory, which is relative to the repository
+        let mut config = Config::default();
+        config.cache.enabled = false;
+        config.classification.disabled_rules = vec!["logging".to_string()];
+        let plugin_manager = PluginManager::new(temp_dir.path().to_path_buf());
+        let mut analyzer = Analyzer::new(config.clone(), plugin_manager).unwrap();
+        assert!(analyzer.classifier.model().is_none());
+
+        // Narrowed analyses count only what they kept
+        let mut narrowed = analyzer.analyze(temp_dir.path(), None).unwrap();
+        let last = parent.unwrap().to_string();
+        analyzer.retain_changes(&mut narrowed, |c| c.commit_id == last);
+        assert_eq!(narrowed.changes.len(), 1);
+        assert_eq!((narrowed.metrics.total_commits, narrowed.metrics.lines_added), (1, 1));
+
+        let analysis = analyzer.analyze(temp_dir.path(), None).unwrap();
+        let model = analyzer.train_classifier(temp_dir.path(), &analysis.changes, &[]).unwrap().unwrap();
+        assert_eq!(model.documents(), 2);
+        assert!(analyzer.classifier.model().is_some());
+        // The configured rules stay in use alongside the model
+        assert!(analyzer.classifier.rules().iter().all(|r| r.category != Category::Logging));
+
+        // Corrections are training data too
+        let head = parent.unwrap().to_string();
+        FeedbackStore::new(&config.history_dir(temp_dir.path()))
+            .record(&Correction::new(head, vec![Category::Api]))
+            .unwrap();
+        let model = analyzer.train_classifier(temp_dir.path(), &analysis.changes, &[]).unwrap().unwrap();
+        assert_eq!(model.documents(), 3);
+
+        // Saved to the model directory and picked up by later runs
+        assert!(temp_dir.path().join(".code-historian/models").join(ml::MODEL_FILE).exists());
+        let plugin_manager = PluginManager::new(temp_dir.path().to_path_buf());
+        let mut analyzer = Analyzer::new(config, plugin_manager).unwrap();
+        analyzer.analyze(temp_dir.path(), None).unwrap();
+        assert_eq!(analyzer.classifier.model().unwrap().documents(), 3);
+
+        assert!(matches!(
+            Analyzer::new(Config::default(), PluginManager::new(temp_dir.path().to_path_buf()))
+                .unwrap()
+                .train_classifier(temp_dir.path(), &[], &[]),
+            Ok(None)
+        ));
+    }
+
+    #[test]
+    fn test_submodules_are_skipped() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let commit = |content: &[u8], submodule: Option<git2::Oid>, parents: &[&Commit]| {
+            let mut builder = repo.treebuilder(None).unwrap();
+            builder.insert("lib.rs", repo.blob(content).unwrap(), 0o100644).unwrap();
+            if let Some(submodule) = submodule {
+                builder.insert("vendor", submodule, 0o160000).unwrap();
+            }
+            let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+            repo.commit(Some("HEAD"), &sig, &sig, "commit", &tree, parents).unwrap()
+        };
+        let root = commit(b"fn a() {}\n", None, &[]);
+        commit(b"fn a() {}\nfn b() {}\n", Some(root), &[&repo.find_commit(root).unwrap()]);
+
+        let mut config = Config::default();
+        config.cache.enabled = false;
+        let plugin_manager = PluginManager::new(temp_dir.path().to_path_buf());
+        let mut analyzer = Analyzer::new(config, plugin_manager).unwrap();
+        let analysis = analyzer.analyze(temp_dir.path(), None).unwrap();
+        let paths: Vec<&Path> = analysis.changes.iter().map(|c| c.file_path.as_path()).collect();
+        assert_eq!(paths, vec![Path::new("lib.rs")]);
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use git2::{AttrCheckFlags, AttrValue, Index, Repository};
+use serde::{Serialize, Deserialize};
+use crate::Result;
+use crate::analyzer::Change;
+use crate::config::GeneratedConfig;
+
+// Generators only announce themselves near the top of the file
+const HEADER_LINES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum FileOrigin {
+    #[default]
+    Authored,
+    Generated,
+    Vendored,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExclusionSummary {
+    pub generated_changes: usize,
+    pub vendored_changes: usize,
+    pub files: Vec<ExcludedFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcludedFile {
+    pub file_path: PathBuf,
+    pub origin: FileOrigin,
+    pub changes: usize,
+}
+
+pub struct OriginDetector<'a> {
+    // A second handle on the repository, whose index is the HEAD tree
+    attributes: Repository,
+    config: &'a GeneratedConfig,
+}
+
+impl<'a> OriginDetector<'a> {
+    /// Reads `.gitattributes` as committed at HEAD, like the ignore files, so neither uncommitted
+    /// edits nor the staging area change how history is classified.
+    pub fn new(repo: &Repository, config: &'a GeneratedConfig) -> Result<Self> {
+        let attributes = Repository::open(repo.path())?;
+        let mut index = Index::new()?;
+        if let Ok(tree) = repo.head().and_then(|head| head.peel_to_tree()) {
+            index.read_tree(&tree)?;
+        }
+        attributes.set_index(&mut index)?;
+        Ok(Self { attributes, config })
+    }
+
+    /// Decide whether a file is authored, generated or vendored.
+    ///
+    /// Explicit `.gitattributes` settings win over path and header heuristics,
+    /// including `linguist-generated=false` to opt a file back in.
+    pub fn detect(&self, path: &Path, content: Option<&str>) -> Result<FileOrigin> {
+        match self.attribute(path, "linguist-vendored")? {
+            Some(true) => return Ok(FileOrigin::Vendored),
+            Some(false) => {}
+            None if self.is_vendor_path(path) => return Ok(FileOrigin::Vendored),
+            None => {}
+        }
+
+        match self.attribute(path, "linguist-generated")? {
+            Some(true) => return Ok(FileOrigin::Generated),
+            Some(false) => return Ok(FileOrigin::Authored),
+            None => {}
+        }
+
+        // `-diff` marks files whose diffs are not meant to be read
+        if self.attribute(path, "diff")? == Some(false) {
+            return Ok(FileOrigin::Generated);
+        }
+
+        if content.is_some_and(|content| self.has_generated_header(content)) {
+            return Ok(FileOrigin::Generated);
+        }
+
+        Ok(FileOrigin::Authored)
+    }
+
+    fn attribute(&self, path: &Path, name: &str) -> Result<Option<bool>> {
+        let value = self.attributes.get_attr(path, name, AttrCheckFlags::INDEX_ONLY)?;
+        Ok(match AttrValue::from_string(value) {
+            AttrValue::True => Some(true),
+            AttrValue::False => Some(false),
+            AttrValue::String(value) => Some(value != "false"),
+            AttrValue::Bytes(_) => Some(true),
+            AttrValue::Unspecified => None,
+        })
+    }
+
+    fn is_vendor_path(&self, path: &Path) -> bool {
+        path.components().any(|c| {
+            let component = c.as_os_str().to_string_lossy();
+            self.config.vendor_dirs.iter().any(|dir| *dir == component)
+        })
+    }
+
+    fn has_generated_header(&self, content: &str) -> bool {
+        content.lines()
+            .take(HEADER_LINES)
+            .any(|line| self.config.header_markers.iter().any(|marker| line.contains(marker.as_str())))
+    }
+}
+
+pub fn summarize(changes: &[Change], config: &GeneratedConfig) -> ExclusionSummary {
+    let mut summary = ExclusionSummary::default();
+    let mut files: BTreeMap<&Path, (FileOrigin, usize)> = BTreeMap::new();
+
+    for change in changes {
+        match change.origin {
+            FileOrigin::Generated => summary.generated_changes += 1,
+            FileOrigin::Vendored => summary.vendored_changes += 1,
+            FileOrigin::Authored => {}
+        }
+
+        if is_excluded(change.origin, config) {
+            files.entry(change.file_path.as_path()).or_insert((change.origin, 0)).1 += 1;
+        }
+    }
+
+    summary.files = files.into_iter()
+        .map(|(path, (origin, changes))| ExcludedFile {
+            file_path: path.to_path_buf(),
+            origin,
+            changes,
+        })
+        .collect();
+    summary
+}
+
+pub fn is_excluded(origin: FileOrigin, config: &GeneratedConfig) -> bool {
+    match origin {
+        FileOrigin::Generated => !config.include_generated,
+        FileOrigin::Vendored => !config.include_vendored,
+        FileOrigin::Authored => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detect_origin() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let attributes = temp_dir.path().join(".gitattributes");
+        std::fs::write(
+            &attributes,
+            "*.pb.go linguist-generated\nlib/** linguist-vendored\nCargo.lock -diff\nvendor/ours/** linguist-vendored=false\n",
+        ).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(".gitattributes")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "Add attributes", &tree, &[]).unwrap();
+
+        // Attributes are read as committed, whatever the working tree and index say
+        std::fs::write(&attributes, "src/** linguist-generated\n").unwrap();
+        index.add_path(Path::new(".gitattributes")).unwrap();
+        index.write().unwrap();
+
+        let config = GeneratedConfig::default();
+        let detector = OriginDetector::new(&repo, &config).unwrap();
+
+        assert_eq!(detector.detect(Path::new("api/service.pb.go"), None).unwrap(), FileOrigin::Generated);
+        assert_eq!(detector.detect(Path::new("lib/jquery.js"), None).unwrap(), FileOrigin::Vendored);
+        assert_eq!(detector.detect(Path::new("Cargo.lock"), None).unwrap(), FileOrigin::Generated);
+        assert_eq!(detector.detect(Path::new("vendor/github.com/x/y.go"), None).unwrap(), FileOrigin::Vendored);
+        assert_eq!(detector.detect(Path::new("vendor/ours/main.go"), None).unwrap(), FileOrigin::Authored);
+        assert_eq!(
+            detector.detect(Path::new("src/schema.rs"), Some("// @generated by diesel\nfn a() {}\n")).unwrap(),
+            FileOrigin::Generated,
+        );
+        assert_eq!(detector.detect(Path::new("src/main.rs"), Some("fn main() {}\n")).unwrap(), FileOrigin::Authored);
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        let mut config = GeneratedConfig::default();
+        assert!(is_excluded(FileOrigin::Generated, &config));
+        assert!(!is_excluded(FileOrigin::Authored, &config));
+
+        config.include_generated = true;
+        assert!(!is_excluded(FileOrigin::Generated, &config));
+        assert!(is_excluded(FileOrigin::Vendored, &config));
+    }
+}
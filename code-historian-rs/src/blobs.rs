@@ -0,0 +1,344 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use git2::{Commit, Diff, DiffDelta, FileMode, Oid, Repository};
+use serde::{Serialize, Deserialize};
+use crate::Result;
+
+const LFS_SPEC: &str = "version https://git-lfs.github.com/spec/v1";
+const LFS_POINTER_MAX_SIZE: usize = 1024;
+const BINARY_SNIFF_LEN: usize = 8000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlobKind {
+    Binary,
+    Lfs,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobChange {
+    pub kind: BlobKind,
+    pub old_size: u64,
+    pub new_size: u64,
+    pub size_delta: i64,
+    pub lfs_oid: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LfsPointer {
+    pub oid: String,
+    pub size: u64,
+}
+
+impl LfsPointer {
+    pub fn parse(content: &[u8]) -> Option<Self> {
+        if content.len() > LFS_POINTER_MAX_SIZE {
+            return None;
+        }
+
+        let text = std::str::from_utf8(content).ok()?;
+        let mut lines = text.lines();
+        if lines.next()?.trim() != LFS_SPEC {
+            return None;
+        }
+
+        let mut oid = None;
+        let mut size = None;
+        for line in lines {
+            if let Some(value) = line.strip_prefix("oid ") {
+                oid = Some(value.trim().to_string());
+            } else if let Some(value) = line.strip_prefix("size ") {
+                size = value.trim().parse().ok();
+            }
+        }
+
+        Some(Self { oid: oid?, size: size? })
+    }
+}
+
+pub fn is_binary(content: &[u8]) -> bool {
+    content[..content.len().min(BINARY_SNIFF_LEN)].contains(&0)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LargeFileReport {
+    pub threshold: u64,
+    pub large_files: Vec<LargeFile>,
+    pub blob_growth: Vec<BlobGrowthPoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LargeFile {
+    pub file_path: PathBuf,
+    pub size: u64,
+    pub lfs: bool,
+    pub commit_id: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlobGrowthPoint {
+    pub commit_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub size_delta: i64,
+    pub total_bytes: i64,
+    pub lfs_bytes: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CommitBlobStats {
+    pub size_delta: i64,
+    pub lfs_delta: i64,
+    pub large_files: Vec<LargeFile>,
+}
+
+/// Classify a delta as binary or LFS-tracked, returning `None` for text changes.
+pub fn inspect_delta(repo: &Repository, delta: &DiffDelta) -> Result<Option<BlobChange>> {
+    let old = read_blob(repo, delta.old_file().id())?;
+    let new = read_blob(repo, delta.new_file().id())?;
+    Ok(inspect_contents(delta, old.as_deref(), new.as_deref()))
+}
+
+/// Like `inspect_delta`, for contents that are already loaded, e.g. from the working tree.
+pub fn inspect_contents(delta: &DiffDelta, old: Option<&[u8]>, new: Option<&[u8]>) -> Option<BlobChange> {
+    let old_pointer = old.and_then(LfsPointer::parse);
+    let new_pointer = new.and_then(LfsPointer::parse);
+
+    if old_pointer.is_some() || new_pointer.is_some() {
+        let old_size = old_pointer.as_ref().map_or(0, |p| p.size);
+        let new_size = new_pointer.as_ref().map_or(0, |p| p.size);
+        return Some(BlobChange {
+            kind: BlobKind::Lfs,
+            old_size,
+            new_size,
+            size_delta: new_size as i64 - old_size as i64,
+            lfs_oid: new_pointer.or(old_pointer).map(|p| p.oid),
+        });
+    }
+
+    let binary = delta.flags().is_binary()
+        || old.is_some_and(is_binary)
+        || new.is_some_and(is_binary);
+    if !binary {
+        return None;
+    }
+
+    let old_size = old.map_or(0, |b| b.len() as u64);
+    let new_size = new.map_or(0, |b| b.len() as u64);
+    Some(BlobChange {
+        kind: BlobKind::Binary,
+        old_size,
+        new_size,
+        size_delta: new_size as i64 - old_size as i64,
+        lfs_oid: None,
+    })
+}
+
+/// Whether a delta is a submodule, whose ids name commits of another repository rather than blobs.
+pub fn is_gitlink(delta: &DiffDelta) -> bool {
+    delta.old_file().mode() == FileMode::Commit || delta.new_file().mode() == FileMode::Commit
+}
+
+/// The commits on `head`'s first-parent line; their diffs add up every blob exactly once.
+pub fn first_parent_chain(repo: &Repository, head: Oid) -> Result<HashSet<Oid>> {
+    let mut chain = HashSet::new();
+    let mut commit = Some(repo.find_commit(head)?);
+    while let Some(current) = commit {
+        chain.insert(current.id());
+        commit = current.parent(0).ok();
+    }
+    Ok(chain)
+}
+
+pub fn read_blob(repo: &Repository, id: Oid) -> Result<Option<Vec<u8>>> {
+    if id.is_zero() {
+        return Ok(None);
+    }
+    Ok(Some(repo.find_blob(id)?.content().to_vec()))
+}
+
+fn blob_size(repo: &Repository, id: Oid) -> Result<u64> {
+    if id.is_zero() {
+        return Ok(0);
+    }
+    let (size, _) = repo.odb()?.read_header(id)?;
+    Ok(size as u64)
+}
+
+/// Measure how a commit changes the repository's blob size and which large files it touches.
+pub fn scan_commit(repo: &Repository, commit: &Commit, diff: &Diff, threshold: u64) -> Result<CommitBlobStats> {
+    let mut stats = CommitBlobStats::default();
+    let timestamp = DateTime::from_timestamp(commit.author().when().seconds(), 0).unwrap_or_else(Utc::now);
+
+    for delta in diff.deltas().filter(|d| !is_gitlink(d)) {
+        let old_size = blob_size(repo, delta.old_file().id())?;
+        let new_size = blob_size(repo, delta.new_file().id())?;
+        stats.size_delta += new_size as i64 - old_size as i64;
+
+        // LFS pointers are tiny; the object they stand for is what matters
+        let mut size = new_size;
+        let mut lfs = false;
+        if new_size as usize <= LFS_POINTER_MAX_SIZE || old_size as usize <= LFS_POINTER_MAX_SIZE {
+            if let Some(change) = inspect_delta(repo, &delta)?.filter(|c| c.kind == BlobKind::Lfs) {
+                stats.lfs_delta += change.size_delta;
+                size = change.new_size;
+                lfs = true;
+            }
+        }
+
+        if size >= threshold && !delta.new_file().id().is_zero() {
+            if let Some(path) = delta.new_file().path() {
+                stats.large_files.push(LargeFile {
+                    file_path: path.to_path_buf(),
+                    size,
+                    lfs,
+                    commit_id: commit.id().to_string(),
+                    timestamp,
+                });
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Accumulate per-commit statistics into a large-file report.
+pub fn build_report(threshold: u64, mut commits: Vec<(String, DateTime<Utc>, CommitBlobStats)>) -> LargeFileReport {
+    commits.sort_by_key(|(_, timestamp, _)| *timestamp);
+
+    let mut largest: HashMap<PathBuf, LargeFile> = HashMap::new();
+    let mut blob_growth = Vec::new();
+    let mut total_bytes = 0;
+    let mut lfs_bytes = 0;
+
+    for (commit_id, timestamp, stats) in commits {
+        total_bytes += stats.size_delta;
+        lfs_bytes += stats.lfs_delta;
+        blob_growth.push(BlobGrowthPoint {
+            commit_id,
+            timestamp,
+            size_delta: stats.size_delta,
+            total_bytes,
+            lfs_bytes,
+        });
+
+        for file in stats.large_files {
+            match largest.get(&file.file_path) {
+                Some(existing) if existing.size >= file.size => {}
+                _ => {
+                    largest.insert(file.file_path.clone(), file);
+                }
+            }
+        }
+    }
+
+    let mut large_files: Vec<LargeFile> = largest.into_values().collect();
+    large_files.sort_by_key(|f| Reverse(f.size));
+
+    LargeFileReport {
+        threshold,
+        large_files,
+        blob_growth,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lfs_pointer() {
+        let pointer = LfsPointer::parse(
+            b"version https://git-lfs.github.com/spec/v1\noid sha256:4d7a2146\nsize 12345\n",
+        ).unwrap();
+        assert_eq!(pointer.oid, "sha256:4d7a2146");
+        assert_eq!(pointer.size, 12345);
+
+        assert!(LfsPointer::parse(b"fn main() {}\n").is_none());
+    }
+
+    #[test]
+    fn test_binary_detection() {
+        assert!(is_binary(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert!(!is_binary(b"plain text\n"));
+    }
+
+    #[test]
+    fn test_scan_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+
+        std::fs::write(temp_dir.path().join("image.png"), [0u8; 64]).unwrap();
+        std::fs::write(
+            temp_dir.path().join("model.bin"),
+            "version https://git-lfs.github.com/spec/v1\noid sha256:abc\nsize 5000000\n",
+        ).unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("image.png")).unwrap();
+        index.add_path(Path::new("model.bin")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let oid = repo.commit(Some("HEAD"), &sig, &sig, "add assets", &tree, &[]).unwrap();
+        let commit = repo.find_commit(oid).unwrap();
+
+        let diff = repo.diff_tree_to_tree(None, Some(&tree), None).unwrap();
+        let stats = scan_commit(&repo, &commit, &diff, 1024 * 1024).unwrap();
+
+        assert_eq!(stats.lfs_delta, 5_000_000);
+        assert_eq!(stats.large_files.len(), 1);
+        assert!(stats.large_files[0].lfs);
+
+        let kinds: Vec<BlobKind> = diff.deltas()
+            .filter_map(|delta| inspect_delta(&repo, &delta).unwrap())
+            .map(|change| change.kind)
+            .collect();
+        assert_eq!(kinds, vec![BlobKind::Binary, BlobKind::Lfs]);
+
+        let report = build_report(1024 * 1024, vec![(oid.to_string(), Utc::now(), stats)]);
+        assert_eq!(report.blob_growth.len(), 1);
+        assert_eq!(report.blob_growth[0].lfs_bytes, 5_000_000);
+        assert_eq!(report.large_files[0].file_path, PathBuf::from("model.bin"));
+    }
+
+    #[test]
+    fn test_submodules_and_merges() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let commit = |files: &[(&str, &[u8])], parents: &[Oid]| {
+            let mut builder = repo.treebuilder(None).unwrap();
+            for (name, content) in files {
+                builder.insert(name, repo.blob(content).unwrap(), 0o100644).unwrap();
+            }
+            let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+            let parents: Vec<Commit> = parents.iter().map(|id| repo.find_commit(*id).unwrap()).collect();
+            let parents: Vec<&Commit> = parents.iter().collect();
+            repo.commit(None, &sig, &sig, "commit", &tree, &parents).unwrap()
+        };
+
+        let root = commit(&[("a.txt", b"root")], &[]);
+        let main = commit(&[("a.txt", b"main")], &[root]);
+        let branch = commit(&[("a.txt", b"root"), ("b.txt", b"branch")], &[root]);
+        let merge = commit(&[("a.txt", b"main"), ("b.txt", b"branch")], &[main, branch]);
+        let chain = first_parent_chain(&repo, merge).unwrap();
+        assert_eq!(chain, HashSet::from([merge, main, root]));
+
+        // A submodule entry names a commit, not a blob
+        let mut builder = repo.treebuilder(None).unwrap();
+        builder.insert("a.txt", repo.blob(b"root").unwrap(), 0o100644).unwrap();
+        builder.insert("vendor", root, 0o160000).unwrap();
+        let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+        let with_submodule = repo.find_commit(
+            repo.commit(None, &sig, &sig, "add submodule", &tree, &[&repo.find_commit(root).unwrap()]).unwrap(),
+        ).unwrap();
+        let diff = repo.diff_tree_to_tree(None, Some(&tree), None).unwrap();
+        assert_eq!(diff.deltas().filter(|d| is_gitlink(d)).count(), 1);
+        let stats = scan_commit(&repo, &with_submodule, &diff, 1).unwrap();
+        assert_eq!(stats.size_delta, 4);
+        assert_eq!(stats.large_files.len(), 1);
+    }
+}
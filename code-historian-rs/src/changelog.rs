@@ -0,0 +1,396 @@
+use std::collections::{BTreeMap, HashMap};
+use chrono::{DateTime, TimeZone, Utc};
+use git2::Repository;
+use handlebars::Handlebars;
+use serde::{Serialize, Deserialize};
+use crate::{Result, HistorianError, Category, Change};
+use crate::config::ChangelogConfig;
+use crate::message::{self, ConventionalCommit};
+
+const UNRELEASED: &str = "Unreleased";
+const OTHER: &str = "Other";
+
+// Keep a Changelog sections in the order they are rendered
+const SECTION_ORDER: [&str; 7] = ["Added", "Changed", "Deprecated", "Removed", "Fixed", "Security", "Documentation"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangelogGrouping {
+    #[default]
+    Type,
+    Category,
+}
+
+impl std::str::FromStr for ChangelogGrouping {
+    type Err = HistorianError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "type" => Ok(Self::Type),
+            "category" => Ok(Self::Category),
+            _ => Err(HistorianError::InvalidArgument(format!(
+                "Unknown changelog grouping '{}', expected 'type' or 'category'",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Changelog {
+    pub version: String,
+    pub from: Option<String>,
+    pub to: String,
+    pub date: String,
+    pub breaking: Vec<ChangelogEntry>,
+    pub sections: Vec<ChangelogSection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangelogSection {
+    pub title: String,
+    pub entries: Vec<ChangelogEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangelogEntry {
+    pub commit_id: String,
+    pub short_id: String,
+    pub author: String,
+    pub timestamp: DateTime<Utc>,
+    pub kind: Option<String>,
+    pub scope: Option<String>,
+    pub summary: String,
+    pub breaking: bool,
+    pub breaking_note: Option<String>,
+    pub categories: Vec<Category>,
+    pub issues: Vec<IssueLink>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssueLink {
+    pub id: String,
+    pub url: Option<String>,
+}
+
+impl IssueLink {
+    /// Links `id` through a template such as `https://github.com/acme/app/issues/{id}`.
+    pub fn new(id: String, url_template: Option<&str>) -> Self {
+        let url = url_template.map(|template| template.replace("{id}", id.trim_start_matches('#')));
+        Self { id, url }
+    }
+}
+
+pub struct ChangelogGenerator<'a> {
+    config: &'a ChangelogConfig,
+    group_by: ChangelogGrouping,
+}
+
+impl<'a> ChangelogGenerator<'a> {
+    pub fn new(config: &'a ChangelogConfig) -> Self {
+        Self {
+            config,
+            group_by: config.group_by,
+        }
+    }
+
+    pub fn with_grouping(mut self, group_by: ChangelogGrouping) -> Self {
+        self.group_by = group_by;
+        self
+    }
+
+    /// Collect the commits reachable from `to` but not from `from`.
+    ///
+    /// Categories come from the analyzed `changes`; merge commits are skipped.
+    pub fn build(&self, repo: &Repository, from: Option<&str>, to: &str, changes: &[Change]) -> Result<Changelog> {
+        let mut categories: HashMap<&str, Vec<Category>> = HashMap::new();
+        for change in changes {
+            let entry = categories.entry(change.commit_id.as_str()).or_default();
+            for category in &change.categories {
+                if !entry.contains(category) {
+                    entry.push(category.clone());
+                }
+            }
+        }
+
+        let to_commit = repo.revparse_single(to)?.peel_to_commit()?;
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(to_commit.id())?;
+        if let Some(from) = from {
+            revwalk.hide(repo.revparse_single(from)?.peel_to_commit()?.id())?;
+        }
+
+        let mut entries = Vec::new();
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+            if commit.parent_count() > 1 {
+                continue;
+            }
+
+            let commit_id = commit.id().to_string();
+            let commit_categories = categories.get(commit_id.as_str()).cloned().unwrap_or_default();
+            let author = commit.author();
+            if let Some(entry) = self.entry(
+                &commit_id,
+                author.name().unwrap_or("Unknown"),
+                Utc.timestamp_opt(commit.time().seconds(), 0).single().unwrap_or_default(),
+                commit.message().unwrap_or_default(),
+                commit_categories,
+            ) {
+                entries.push(entry);
+            }
+        }
+
+        // Tags name the version; anything else is still unreleased
+        let version = if repo.find_reference(&format!("refs/tags/{}", to)).is_ok() {
+            to.to_string()
+        } else {
+            UNRELEASED.to_string()
+        };
+
+        let date = Utc.timestamp_opt(to_commit.time().seconds(), 0)
+            .single()
+            .unwrap_or_default()
+            .format("%Y-%m-%d")
+            .to_string();
+
+        Ok(self.assemble(version, from.map(str::to_string), to.to_string(), date, entries))
+    }
+
+    fn entry(
+        &self,
+        commit_id: &str,
+        author: &str,
+        timestamp: DateTime<Utc>,
+        message: &str,
+        categories: Vec<Category>,
+    ) -> Option<ChangelogEntry> {
+        let conventional = ConventionalCommit::parse(message);
+
+        if let Some(commit) = &conventional {
+            if !commit.breaking && self.config.hidden_types.contains(&commit.kind) {
+                return None;
+            }
+        }
+
+        let summary = match &conventional {
+            Some(commit) => commit.description.clone(),
+            None => message.lines().next().unwrap_or_default().trim().to_string(),
+        };
+        if summary.is_empty() {
+            return None;
+        }
+
+        let breaking_note = conventional.as_ref().and_then(|c| {
+            c.footers.iter()
+                .find(|(token, _)| token == "BREAKING CHANGE" || token == "BREAKING-CHANGE")
+                .map(|(_, value)| value.clone())
+        });
+
+        Some(ChangelogEntry {
+            commit_id: commit_id.to_string(),
+            short_id: commit_id.chars().take(7).collect(),
+            author: author.to_string(),
+            timestamp,
+            kind: conventional.as_ref().map(|c| c.kind.clone()),
+            scope: conventional.as_ref().and_then(|c| c.scope.clone()),
+            summary,
+            breaking: conventional.as_ref().is_some_and(|c| c.breaking),
+            breaking_note,
+            categories,
+            issues: message::issue_references(message).into_iter()
+                .map(|id| IssueLink::new(id, self.config.issue_url_template.as_deref()))
+                .collect(),
+        })
+    }
+
+    fn assemble(
+        &self,
+        version: String,
+        from: Option<String>,
+        to: String,
+        date: String,
+        entries: Vec<ChangelogEntry>,
+    ) -> Changelog {
+        let (breaking, entries): (Vec<_>, Vec<_>) = entries.into_iter()
+            .partition(|e| e.breaking);
+
+        let mut sections: BTreeMap<(usize, String), Vec<ChangelogEntry>> = BTreeMap::new();
+        for entry in entries {
+            let title = match self.group_by {
+                ChangelogGrouping::Type => type_section(&entry),
+                ChangelogGrouping::Category => entry.categories.first()
+                    .map(|c| format!("{:?}", c))
+                    .unwrap_or_else(|| OTHER.to_string()),
+            };
+            let order = SECTION_ORDER.iter()
+                .position(|s| *s == title)
+                .unwrap_or(if title == OTHER { usize::MAX } else { SECTION_ORDER.len() });
+            sections.entry((order, title)).or_default().push(entry);
+        }
+
+        Changelog {
+            version,
+            from,
+            to,
+            date,
+            breaking,
+            sections: sections.into_iter()
+                .map(|((_, title), entries)| ChangelogSection { title, entries })
+                .collect(),
+        }
+    }
+}
+
+// Conventional Commit types map onto Keep a Changelog sections;
+// other commits fall back on their categories
+fn type_section(entry: &ChangelogEntry) -> String {
+    let section = match entry.kind.as_deref() {
+        Some("feat") => "Added",
+        Some("fix") => "Fixed",
+        Some("perf" | "refactor") => "Changed",
+        Some("deprecate") => "Deprecated",
+        Some("remove" | "revert") => "Removed",
+        Some("security") => "Security",
+        Some("docs") => "Documentation",
+        Some(_) => OTHER,
+        None if entry.categories.contains(&Category::Security) => "Security",
+        None if entry.categories.contains(&Category::ErrorHandling) => "Fixed",
+        None if entry.categories.contains(&Category::Documentation) => "Documentation",
+        None => "Changed",
+    };
+    section.to_string()
+}
+
+/// Render Keep a Changelog Markdown, optionally with a custom Handlebars template.
+pub fn render_markdown(changelog: &Changelog, template: Option<&str>) -> Result<String> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars.register_template_string(
+        "changelog",
+        template.unwrap_or(include_str!("../templates/changelog.md")),
+    )?;
+
+    handlebars.render("changelog", changelog)
+        .map_err(|e| HistorianError::Visualization(format!("Failed to render changelog: {}", e)))
+}
+
+pub fn render_json(changelog: &Changelog) -> Result<String> {
+    serde_json::to_string_pretty(changelog)
+        .map_err(|e| HistorianError::Analysis(format!("Failed to serialize changelog: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use git2::Oid;
+    use tempfile::TempDir;
+
+    fn commit_file(repo: &Repository, path: &str, content: &str, message: &str) -> Oid {
+        std::fs::write(repo.workdir().unwrap().join(path), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let parents: Vec<git2::Commit> = repo.head().ok()
+            .and_then(|h| h.peel_to_commit().ok())
+            .into_iter()
+            .collect();
+        let parents: Vec<&git2::Commit> = parents.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap()
+    }
+
+    fn config() -> ChangelogConfig {
+        ChangelogConfig {
+            issue_url_template: Some("https://github.com/acme/app/issues/{id}".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_changelog() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+
+        let base = commit_file(&repo, "a.txt", "1", "chore: initial commit");
+        repo.tag_lightweight("v1.0.0", &repo.find_object(base, None).unwrap(), false).unwrap();
+        commit_file(&repo, "a.txt", "2", "feat(api): add export endpoint (#12)");
+        commit_file(&repo, "a.txt", "3", "fix: handle empty input\n\nCloses #15");
+        commit_file(&repo, "a.txt", "4", "chore: bump lockfile");
+        commit_file(&repo, "a.txt", "5", "feat!: drop v1 routes\n\nBREAKING CHANGE: v1 clients must migrate");
+        let head = commit_file(&repo, "a.txt", "6", "Tighten token validation");
+        repo.tag_lightweight("v2.0.0", &repo.find_object(head, None).unwrap(), false).unwrap();
+
+        let mut change = crate::analyzer::Change {
+            commit_id: head.to_string(),
+            author: "Test".to_string(),
+            author_email: String::new(),
+            timestamp: Utc::now(),
+            timezone_offset: 0,
+            message: String::new(),
+            file_path: "src/auth.rs".into(),
+            diff: String::new(),
+            categories: vec![Category::Security],
+            category_scores: Vec::new(),
+            impact_score: 0.0,
+            metrics: HashMap::new(),
+            annotations: Vec::new(),
+            fix_inducing: false,
+            code_kind: Default::default(),
+            blob: None,
+            mechanical: None,
+            origin: Default::default(),
+            component: None,
+            signature: None,
+        };
+
+        let config = config();
+        let generator = ChangelogGenerator::new(&config);
+        let changelog = generator.build(&repo, Some("v1.0.0"), "v2.0.0", std::slice::from_ref(&change)).unwrap();
+
+        assert_eq!(changelog.version, "v2.0.0");
+        assert_eq!(changelog.breaking.len(), 1);
+        assert_eq!(changelog.breaking[0].breaking_note.as_deref(), Some("v1 clients must migrate"));
+
+        let titles: Vec<&str> = changelog.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["Added", "Fixed", "Security"]);
+
+        let added = &changelog.sections[0].entries[0];
+        assert_eq!(added.scope.as_deref(), Some("api"));
+        assert_eq!(added.issues[0].url.as_deref(), Some("https://github.com/acme/app/issues/12"));
+
+        change.categories = vec![Category::Api];
+        let changelog = ChangelogGenerator::new(&config)
+            .with_grouping(ChangelogGrouping::Category)
+            .build(&repo, Some("v1.0.0"), "HEAD", &[change])
+            .unwrap();
+        assert_eq!(changelog.version, UNRELEASED);
+        let titles: Vec<&str> = changelog.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["Api", OTHER]);
+    }
+
+    #[test]
+    fn test_render() {
+        let config = config();
+        let generator = ChangelogGenerator::new(&config);
+        let entries = vec![
+            generator.entry("abcdef123", "Ann", Utc::now(), "feat!: new config format\n\nBREAKING CHANGE: rename keys", vec![]).unwrap(),
+            generator.entry("1234567ab", "Bob", Utc::now(), "fix(cli): quote <paths> PROJ-9", vec![]).unwrap(),
+        ];
+        let changelog = generator.assemble("v2.0.0".to_string(), None, "v2.0.0".to_string(), "2024-05-01".to_string(), entries);
+
+        let markdown = render_markdown(&changelog, None).unwrap();
+        assert!(markdown.contains("## [v2.0.0] - 2024-05-01"));
+        assert!(markdown.find("### BREAKING CHANGES").unwrap() < markdown.find("### Fixed").unwrap());
+        assert!(markdown.contains("rename keys"));
+        assert!(markdown.contains("- **cli:** quote <paths> PROJ-9 (1234567) [PROJ-9](https://github.com/acme/app/issues/PROJ-9)"));
+
+        let custom = render_markdown(&changelog, Some("{{version}}: {{#each sections}}{{title}}{{/each}}")).unwrap();
+        assert_eq!(custom, "v2.0.0: Fixed");
+
+        let json: serde_json::Value = serde_json::from_str(&render_json(&changelog).unwrap()).unwrap();
+        assert_eq!(json["breaking"][0]["short_id"], "abcdef1");
+    }
+}
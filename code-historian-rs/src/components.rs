@@ -0,0 +1,346 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use glob::Pattern;
+use serde::{Serialize, Deserialize};
+use crate::{Result, HistorianError};
+use crate::analyzer::{Change, Metrics, Pattern as ChangePattern};
+use crate::config::{ComponentDefinition, ComponentsConfig};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentSummary {
+    pub name: String,
+    pub metrics: Metrics,
+    pub patterns: Vec<ChangePattern>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentCoupling {
+    pub first: String,
+    pub second: String,
+    pub co_changes: usize,
+    // Shared commits relative to all commits touching either component
+    pub strength: f64,
+}
+
+#[derive(Debug, Clone)]
+struct ComponentMatcher {
+    name: String,
+    patterns: Vec<Pattern>,
+    // Depth of the discovered root; deeper roots win over enclosing ones
+    depth: usize,
+}
+
+/// Maps file paths to the component that owns them.
+#[derive(Debug, Clone, Default)]
+pub struct ComponentResolver {
+    defined: Vec<ComponentMatcher>,
+    discovered: Vec<ComponentMatcher>,
+}
+
+impl ComponentResolver {
+    pub fn new(config: &ComponentsConfig, repo_root: Option<&Path>) -> Result<Self> {
+        let mut resolver = Self::default();
+
+        for definition in &config.definitions {
+            resolver.defined.push(compile(definition)?);
+        }
+
+        if config.auto_discover {
+            if let Some(root) = repo_root {
+                for (name, dir) in discover(root)? {
+                    if resolver.defined.iter().any(|c| c.name == name) {
+                        continue;
+                    }
+                    let pattern = format!("{}/**", dir.to_string_lossy());
+                    resolver.discovered.push(ComponentMatcher {
+                        name,
+                        patterns: vec![Pattern::new(&pattern).map_err(|e| {
+                            HistorianError::Config(format!("Invalid component path '{}': {}", pattern, e))
+                        })?],
+                        depth: dir.components().count(),
+                    });
+                }
+                resolver.discovered.sort_by_key(|c| std::cmp::Reverse(c.depth));
+            }
+        }
+
+        Ok(resolver)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.defined.is_empty() && self.discovered.is_empty()
+    }
+
+    /// Explicit definitions are checked in order before auto-discovered components.
+    pub fn resolve(&self, path: &Path) -> Option<String> {
+        let path_str = path.to_string_lossy();
+        self.defined.iter()
+            .chain(self.discovered.iter())
+            .find(|c| c.patterns.iter().any(|p| p.matches(&path_str)))
+            .map(|c| c.name.clone())
+    }
+}
+
+fn compile(definition: &ComponentDefinition) -> Result<ComponentMatcher> {
+    let patterns = definition.paths.iter()
+        .map(|path| Pattern::new(path).map_err(|e| {
+            HistorianError::Config(format!("Invalid path '{}' for component '{}': {}", path, definition.name, e))
+        }))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(ComponentMatcher {
+        name: definition.name.clone(),
+        patterns,
+        depth: 0,
+    })
+}
+
+/// Find Cargo workspace members, npm workspaces and Go modules below the repository root.
+pub fn discover(root: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut found = BTreeMap::new();
+
+    for dir in cargo_members(root)? {
+        let name = read_cargo_name(&root.join(&dir)).unwrap_or_else(|| dir_name(&dir));
+        found.entry(dir).or_insert(name);
+    }
+
+    for dir in npm_workspaces(root)? {
+        let name = read_package_name(&root.join(&dir)).unwrap_or_else(|| dir_name(&dir));
+        found.entry(dir).or_insert(name);
+    }
+
+    for dir in go_modules(root)? {
+        found.entry(dir.clone()).or_insert_with(|| dir_name(&dir));
+    }
+
+    Ok(found.into_iter().map(|(dir, name)| (name, dir)).collect())
+}
+
+fn dir_name(dir: &Path) -> String {
+    dir.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| dir.to_string_lossy().into_owned())
+}
+
+fn expand_members(root: &Path, members: &[String], excluded: &[String], marker: &str) -> Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+
+    for member in members {
+        let pattern = root.join(member).to_string_lossy().into_owned();
+        let entries = glob::glob(&pattern)
+            .map_err(|e| HistorianError::Config(format!("Invalid workspace member '{}': {}", member, e)))?;
+
+        for entry in entries.flatten() {
+            let Ok(relative) = entry.strip_prefix(root) else {
+                continue;
+            };
+            let excluded = excluded.iter().any(|e| Path::new(e) == relative);
+            if !excluded && entry.join(marker).is_file() && !relative.as_os_str().is_empty() {
+                dirs.push(relative.to_path_buf());
+            }
+        }
+    }
+
+    Ok(dirs)
+}
+
+fn cargo_members(root: &Path) -> Result<Vec<PathBuf>> {
+    let manifest = root.join("Cargo.toml");
+    if !manifest.is_file() {
+        return Ok(Vec::new());
+    }
+
+    let value: toml::Value = toml::from_str(&std::fs::read_to_string(manifest)?)
+        .map_err(|e| HistorianError::Config(format!("Invalid workspace Cargo.toml: {}", e)))?;
+    let Some(workspace) = value.get("workspace") else {
+        return Ok(Vec::new());
+    };
+
+    let strings = |key: &str| -> Vec<String> {
+        workspace.get(key)
+            .and_then(|v| v.as_array())
+            .map(|a| a.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+            .unwrap_or_default()
+    };
+
+    expand_members(root, &strings("members"), &strings("exclude"), "Cargo.toml")
+}
+
+fn read_cargo_name(dir: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+    let value: toml::Value = toml::from_str(&content).ok()?;
+    value.get("package")?.get("name")?.as_str().map(String::from)
+}
+
+fn npm_workspaces(root: &Path) -> Result<Vec<PathBuf>> {
+    let manifest = root.join("package.json");
+    if !manifest.is_file() {
+        return Ok(Vec::new());
+    }
+
+    let value: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(manifest)?)
+        .map_err(|e| HistorianError::Config(format!("Invalid workspace package.json: {}", e)))?;
+    // Either an array or, with yarn, `{ "packages": [...] }`
+    let workspaces = match value.get("workspaces") {
+        Some(serde_json::Value::Array(items)) => items.clone(),
+        Some(serde_json::Value::Object(map)) => map.get("packages")
+            .and_then(|p| p.as_array())
+            .cloned()
+            .unwrap_or_default(),
+        _ => return Ok(Vec::new()),
+    };
+
+    let members: Vec<String> = workspaces.iter()
+        .filter_map(|v| v.as_str().map(String::from))
+        .collect();
+    expand_members(root, &members, &[], "package.json")
+}
+
+fn read_package_name(dir: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(dir.join("package.json")).ok()?;
+    let value: serde_json::Value = serde_json::from_str(&content).ok()?;
+    value.get("name")?.as_str().map(String::from)
+}
+
+fn go_modules(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut modules = Vec::new();
+    collect_go_modules(root, Path::new(""), &mut modules)?;
+    Ok(modules)
+}
+
+fn collect_go_modules(root: &Path, relative: &Path, modules: &mut Vec<PathBuf>) -> Result<()> {
+    let dir = root.join(relative);
+    if !relative.as_os_str().is_empty() && dir.join("go.mod").is_file() {
+        modules.push(relative.to_path_buf());
+    }
+
+    for entry in std::fs::read_dir(&dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if entry.file_type()?.is_dir() && !name.starts_with('.') && name != "vendor" && name != "node_modules" {
+            collect_go_modules(root, &relative.join(entry.file_name()), modules)?;
+        }
+    }
+    Ok(())
+}
+
+/// Count how often pairs of components change in the same commit.
+pub fn compute_coupling(changes: &[Change], min_co_changes: usize) -> Vec<ComponentCoupling> {
+    let mut commits: HashMap<&str, BTreeSet<&str>> = HashMap::new();
+    for change in changes {
+        if let Some(component) = &change.component {
+            commits.entry(change.commit_id.as_str()).or_default().insert(component);
+        }
+    }
+
+    let mut touched: HashMap<&str, usize> = HashMap::new();
+    let mut pairs: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for components in commits.values() {
+        let components: Vec<&str> = components.iter().copied().collect();
+        for (i, first) in components.iter().enumerate() {
+            *touched.entry(first).or_insert(0) += 1;
+            for second in &components[i + 1..] {
+                *pairs.entry((first, second)).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut coupling: Vec<ComponentCoupling> = pairs.into_iter()
+        .filter(|(_, count)| *count >= min_co_changes)
+        .map(|((first, second), co_changes)| {
+            let union = touched[first] + touched[second] - co_changes;
+            ComponentCoupling {
+                first: first.to_string(),
+                second: second.to_string(),
+                co_changes,
+                strength: co_changes as f64 / union as f64,
+            }
+        })
+        .collect();
+    coupling.sort_by(|a, b| b.co_changes.cmp(&a.co_changes).then(a.first.cmp(&b.first)));
+    coupling
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn change(commit_id: &str, component: &str) -> Change {
+        Change {
+            commit_id: commit_id.to_string(),
+            component: Some(component.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_discover() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+
+        std::fs::write(root.join("Cargo.toml"), "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/old\"]\n").unwrap();
+        for (dir, name) in [("crates/core", "acme-core"), ("crates/old", "acme-old")] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            std::fs::write(root.join(dir).join("Cargo.toml"), format!("[package]\nname = \"{}\"\n", name)).unwrap();
+        }
+
+        std::fs::write(root.join("package.json"), r#"{"workspaces": ["web/*"]}"#).unwrap();
+        std::fs::create_dir_all(root.join("web/dashboard")).unwrap();
+        std::fs::write(root.join("web/dashboard/package.json"), r#"{"name": "@acme/dashboard"}"#).unwrap();
+
+        std::fs::create_dir_all(root.join("services/billing")).unwrap();
+        std::fs::write(root.join("services/billing/go.mod"), "module acme/billing\n").unwrap();
+
+        let found = discover(root).unwrap();
+        assert_eq!(found, vec![
+            ("acme-core".to_string(), PathBuf::from("crates/core")),
+            ("billing".to_string(), PathBuf::from("services/billing")),
+            ("@acme/dashboard".to_string(), PathBuf::from("web/dashboard")),
+        ]);
+
+        let config = ComponentsConfig {
+            auto_discover: true,
+            definitions: vec![ComponentDefinition {
+                name: "docs".to_string(),
+                paths: vec!["docs/**".to_string(), "*.md".to_string()],
+            }],
+            ..Default::default()
+        };
+        let resolver = ComponentResolver::new(&config, Some(root)).unwrap();
+        assert_eq!(resolver.resolve(Path::new("crates/core/src/lib.rs")).as_deref(), Some("acme-core"));
+        assert_eq!(resolver.resolve(Path::new("services/billing/main.go")).as_deref(), Some("billing"));
+        assert_eq!(resolver.resolve(Path::new("README.md")).as_deref(), Some("docs"));
+        assert_eq!(resolver.resolve(Path::new("scripts/deploy.sh")), None);
+    }
+
+    #[test]
+    fn test_invalid_definition() {
+        let config = ComponentsConfig {
+            definitions: vec![ComponentDefinition {
+                name: "broken".to_string(),
+                paths: vec!["src/[".to_string()],
+            }],
+            ..Default::default()
+        };
+        assert!(ComponentResolver::new(&config, None).is_err());
+    }
+
+    #[test]
+    fn test_coupling() {
+        let changes = vec![
+            change("a", "api"), change("a", "web"),
+            change("b", "api"), change("b", "web"),
+            change("c", "api"), change("c", "billing"),
+            change("d", "web"),
+        ];
+
+        let coupling = compute_coupling(&changes, 2);
+        assert_eq!(coupling.len(), 1);
+        assert_eq!((coupling[0].first.as_str(), coupling[0].second.as_str()), ("api", "web"));
+        assert_eq!(coupling[0].co_changes, 2);
+        assert!((coupling[0].strength - 0.5).abs() < f64::EPSILON);
+    }
+}
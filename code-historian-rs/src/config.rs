@@ -1,7 +1,15 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use serde::{Serialize, Deserialize};
 use directories::ProjectDirs;
-use crate::{Result, HistorianError};
+use crate::{Result, HistorianError, Category};
+use crate::analyzer::Period;
+use crate::changelog::ChangelogGrouping;
+use crate::ignore::compile_patterns;
+use crate::ml::ClassifierMode;
+use crate::releases::parse_tag_pattern;
+use crate::lexer::TokenContext;
+use crate::rules::{self, PatternKind, RuleTarget};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -16,6 +24,36 @@ pub struct Config {
     pub documentation: DocumentationConfig,
     pub ml: MlConfig,
     pub reporting: ReportingConfig,
+    #[serde(default)]
+    pub defects: DefectConfig,
+    #[serde(default)]
+    pub testing: TestingConfig,
+    #[serde(default)]
+    pub large_files: LargeFileConfig,
+    #[serde(default)]
+    pub mechanical: MechanicalConfig,
+    #[serde(default)]
+    pub generated: GeneratedConfig,
+    #[serde(default)]
+    pub components: ComponentsConfig,
+    #[serde(default)]
+    pub releases: ReleasesConfig,
+    #[serde(default)]
+    pub changelog: ChangelogConfig,
+    #[serde(default)]
+    pub precommit: PrecommitConfig,
+    #[serde(default)]
+    pub message_lint: MessageLintConfig,
+    #[serde(default)]
+    pub activity: ActivityConfig,
+    #[serde(default)]
+    pub signatures: SignaturesConfig,
+    #[serde(default)]
+    pub search: SearchConfig,
+    #[serde(default)]
+    pub classification: ClassificationConfig,
+    #[serde(default)]
+    pub server: ServerConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -95,8 +133,11 @@ pub struct DocumentationConfig {
 pub struct MlConfig {
     pub model_dir: PathBuf,
     pub min_confidence: f64,
+    // Retrain from labeled commits on every fresh analysis
     pub auto_train: bool,
     pub max_training_size: usize,
+    #[serde(default)]
+    pub mode: ClassifierMode,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -107,13 +148,417 @@ pub struct ReportingConfig {
     pub max_size: usize,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DefectConfig {
+    pub enabled: bool,
+    pub use_categories: bool,
+    pub fix_types: Vec<String>,
+    pub fix_labels: Vec<String>,
+    pub impact_weight: f64,
+}
+
+impl Default for DefectConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            use_categories: true,
+            fix_types: vec!["fix".to_string()],
+            fix_labels: vec![
+                "bug".to_string(),
+                "bugfix".to_string(),
+                "hotfix".to_string(),
+                "defect".to_string(),
+            ],
+            impact_weight: 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TestingConfig {
+    pub period: Period,
+    pub hotspot_min_changes: usize,
+}
+
+impl Default for TestingConfig {
+    fn default() -> Self {
+        Self {
+            period: Period::Month,
+            hotspot_min_changes: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LargeFileConfig {
+    pub threshold_bytes: u64,
+}
+
+impl Default for LargeFileConfig {
+    fn default() -> Self {
+        Self {
+            threshold_bytes: 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MechanicalConfig {
+    pub enabled: bool,
+    pub exclude_from_metrics: bool,
+    pub ignore_revs_file: Option<PathBuf>,
+}
+
+impl Default for MechanicalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            exclude_from_metrics: true,
+            ignore_revs_file: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneratedConfig {
+    pub include_generated: bool,
+    pub include_vendored: bool,
+    pub header_markers: Vec<String>,
+    pub vendor_dirs: Vec<String>,
+}
+
+impl Default for GeneratedConfig {
+    fn default() -> Self {
+        Self {
+            include_generated: false,
+            include_vendored: false,
+            header_markers: vec![
+                "@generated".to_string(),
+                "DO NOT EDIT".to_string(),
+                "Code generated".to_string(),
+                "auto-generated".to_string(),
+                "autogenerated".to_string(),
+            ],
+            vendor_dirs: vec![
+                "vendor".to_string(),
+                "third_party".to_string(),
+                "third-party".to_string(),
+                "node_modules".to_string(),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ComponentsConfig {
+    pub auto_discover: bool,
+    pub min_co_changes: usize,
+    pub definitions: Vec<ComponentDefinition>,
+}
+
+impl Default for ComponentsConfig {
+    fn default() -> Self {
+        Self {
+            auto_discover: true,
+            min_co_changes: 2,
+            definitions: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentDefinition {
+    pub name: String,
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReleasesConfig {
+    pub enabled: bool,
+    // Semver requirement such as ">=1.0.0"; tags that don't satisfy it are not releases
+    pub tag_pattern: Option<String>,
+    pub top_changes: usize,
+}
+
+impl Default for ReleasesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            tag_pattern: None,
+            top_changes: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChangelogConfig {
+    pub group_by: ChangelogGrouping,
+    // Link for issue references, with `{id}` replaced by the issue number or key
+    pub issue_url_template: Option<String>,
+    pub template: Option<PathBuf>,
+    // Conventional Commit types left out unless they are breaking
+    pub hidden_types: Vec<String>,
+}
+
+impl Default for ChangelogConfig {
+    fn default() -> Self {
+        Self {
+            group_by: ChangelogGrouping::Type,
+            issue_url_template: None,
+            template: None,
+            hidden_types: vec![
+                "chore".to_string(),
+                "ci".to_string(),
+                "build".to_string(),
+                "test".to_string(),
+                "style".to_string(),
+            ],
+        }
+    }
+}
+
+// Thresholds for `analyze --staged` / `--worktree`; unset limits are not checked
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrecommitConfig {
+    pub max_impact: Option<f64>,
+    pub max_total_impact: Option<f64>,
+    pub max_lines_changed: Option<usize>,
+    pub fail_on_categories: Vec<Category>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MessageLintConfig {
+    pub enabled: bool,
+    pub max_subject_length: usize,
+    pub imperative_mood: bool,
+    // Commits changing at least this many lines need a message body
+    pub body_required_lines: Option<usize>,
+    pub require_issue_reference: bool,
+    pub require_conventional: bool,
+    // Allowed Conventional Commit types; empty allows any
+    pub conventional_types: Vec<String>,
+    pub period: Period,
+}
+
+impl Default for MessageLintConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_subject_length: 72,
+            imperative_mood: true,
+            body_required_lines: Some(100),
+            require_issue_reference: false,
+            require_conventional: false,
+            conventional_types: vec![
+                "feat".to_string(),
+                "fix".to_string(),
+                "docs".to_string(),
+                "style".to_string(),
+                "refactor".to_string(),
+                "perf".to_string(),
+                "test".to_string(),
+                "build".to_string(),
+                "ci".to_string(),
+                "chore".to_string(),
+                "revert".to_string(),
+            ],
+            period: Period::Month,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActivityConfig {
+    pub enabled: bool,
+    // Local working hours, from the start hour up to but excluding the end hour
+    pub work_start_hour: u32,
+    pub work_end_hour: u32,
+    // Authors and teams above this share of after-hours or weekend commits are flagged
+    pub flag_threshold: f64,
+    // Team name to member names or emails
+    pub teams: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for ActivityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            work_start_hour: 9,
+            work_end_hour: 18,
+            flag_threshold: 0.25,
+            teams: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignaturesConfig {
+    pub enabled: bool,
+    // Keyring for OpenPGP signatures; without one they are recorded as signed but unchecked
+    pub gpg_keyring: Option<PathBuf>,
+    // ssh-keygen allowed signers file for SSH signatures
+    pub allowed_signers: Option<PathBuf>,
+    pub gpg_program: String,
+    pub ssh_program: String,
+    // Fail the pre-push hook on unsigned commits
+    pub require_signed: bool,
+    // Also fail on signatures that do not verify
+    pub require_verified: bool,
+    pub period: Period,
+}
+
+impl Default for SignaturesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            gpg_keyring: None,
+            allowed_signers: None,
+            gpg_program: "gpg".to_string(),
+            ssh_program: "ssh-keygen".to_string(),
+            require_signed: false,
+            require_verified: false,
+            period: Period::Month,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    pub enabled: bool,
+    // Changed lines kept per change for snippets; all of them are indexed
+    pub snippet_lines: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            snippet_lines: 200,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    // Browser origins such as "http://localhost:63342" that may read the API; none by default
+    pub allowed_origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClassificationConfig {
+    pub builtin_rules: bool,
+    // Built-in rules to drop, by category ("logging") or category and keyword ("configuration:env")
+    pub disabled_rules: Vec<String>,
+    // TOML file of further [[rules]], relative to the history directory
+    pub rules_file: Option<PathBuf>,
+    pub rules: Vec<RuleDefinition>,
+}
+
+impl Default for ClassificationConfig {
+    fn default() -> Self {
+        Self {
+            builtin_rules: true,
+            disabled_rules: Vec::new(),
+            rules_file: None,
+            rules: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleDefinition {
+    // Defaults to the pattern
+    #[serde(default)]
+    pub name: Option<String>,
+    pub category: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub kind: PatternKind,
+    #[serde(default)]
+    pub target: RuleTarget,
+    // Parts of changed lines to match: code, comments and strings
+    #[serde(default = "default_rule_contexts")]
+    pub contexts: Vec<TokenContext>,
+    // Empty applies to every language and path
+    #[serde(default)]
+    pub languages: Vec<String>,
+    #[serde(default)]
+    pub paths: Vec<String>,
+    #[serde(default = "default_rule_weight")]
+    pub weight: f64,
+}
+
+fn default_rule_weight() -> f64 {
+    1.0
+}
+
+fn default_rule_contexts() -> Vec<TokenContext> {
+    vec![TokenContext::Code]
+}
+
 impl Config {
     pub fn new(repo_path: &Path) -> Result<Self> {
         let mut config = Self::default();
         config.load_all(repo_path)?;
+        config.validate()?;
+        rules::load(&config, Some(repo_path))?;
         Ok(config)
     }
 
+    pub fn validate(&self) -> Result<()> {
+        compile_patterns(&self.analysis.include_patterns)?;
+        compile_patterns(&self.analysis.exclude_patterns)?;
+        for component in &self.components.definitions {
+            compile_patterns(&component.paths)?;
+        }
+        if let Some(pattern) = &self.releases.tag_pattern {
+            parse_tag_pattern(pattern)?;
+        }
+
+        if self.large_files.threshold_bytes == 0 {
+            return Err(HistorianError::Config("large_files.threshold_bytes must be greater than zero".to_string()));
+        }
+
+        if !(0.0..=1.0).contains(&self.ml.min_confidence) {
+            return Err(HistorianError::Config("ml.min_confidence must be between 0 and 1".to_string()));
+        }
+
+        // The rules file belongs to a repository, so only the inline rules are checked here
+        rules::load(self, None)?;
+
+        for origin in &self.server.allowed_origins {
+            let host = origin.strip_prefix("http://").or_else(|| origin.strip_prefix("https://"));
+            if !host.is_some_and(|h| !h.is_empty() && !h.contains(['/', ' '])) {
+                return Err(HistorianError::Config(format!(
+                    "server.allowed_origins: '{}' is not an origin such as http://localhost:8080", origin,
+                )));
+            }
+        }
+
+        if self.activity.work_start_hour >= self.activity.work_end_hour || self.activity.work_end_hour > 24 {
+            return Err(HistorianError::Config(
+                "activity working hours must satisfy work_start_hour < work_end_hour <= 24".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
     pub fn load_all(&mut self, repo_path: &Path) -> Result<()> {
         // Load system config
         if let Some(system_config) = self.load_system_config()? {
@@ -196,12 +641,40 @@ impl Config {
         self.documentation = other.documentation;
         self.ml = other.ml;
         self.reporting = other.reporting;
+        self.defects = other.defects;
+        self.testing = other.testing;
+        self.large_files = other.large_files;
+        self.mechanical = other.mechanical;
+        self.generated = other.generated;
+        self.components = other.components;
+        self.releases = other.releases;
+        self.changelog = other.changelog;
+        self.precommit = other.precommit;
+        self.message_lint = other.message_lint;
+        self.activity = other.activity;
+        self.signatures = other.signatures;
+        self.search = other.search;
+        self.classification = other.classification;
+        self.server = other.server;
+    }
+
+    /// The history directory of the repository at `repo_path`; a relative one lives in the repository.
+    pub fn history_dir(&self, repo_path: &Path) -> PathBuf {
+        repo_path.join(&self.core.history_dir)
     }
 
     pub fn plugins_dir(&self) -> Result<PathBuf> {
         Ok(self.core.history_dir.join(&self.plugins.directory))
     }
 
+    pub fn model_dir(&self, repo_path: &Path) -> Result<PathBuf> {
+        Ok(self.history_dir(repo_path).join(&self.ml.model_dir))
+    }
+
+    pub fn rules_file(&self, repo_path: &Path) -> Option<PathBuf> {
+        self.classification.rules_file.as_ref().map(|file| self.history_dir(repo_path).join(file))
+    }
+
     pub fn cache_dir(&self) -> Result<PathBuf> {
         Ok(self.core.history_dir.join(&self.cache.directory))
     }
@@ -258,4 +731,37 @@ mod tests {
         assert_eq!(base_config.core.output_dir, PathBuf::from("custom/output"));
         assert!(!base_config.core.ml_enabled);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_validate_patterns() {
+        let mut config = Config::default();
+        assert!(config.validate().is_ok());
+
+        config.analysis.exclude_patterns.push("src/[".to_string());
+        let err = config.validate().unwrap_err();
+        assert!(matches!(err, HistorianError::Config(_)));
+
+        let mut config = Config::default();
+        config.releases.tag_pattern = Some("latest".to_string());
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.ml.min_confidence = 1.5;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.activity.work_start_hour = 18;
+        config.activity.work_end_hour = 9;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.classification.disabled_rules.push("logging:println".to_string());
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.server.allowed_origins = vec!["http://localhost:63342".to_string()];
+        assert!(config.validate().is_ok());
+        config.server.allowed_origins.push("*".to_string());
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,657 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use git2::{Commit, Diff, Mailmap, Oid, Repository};
+use serde::{Serialize, Deserialize};
+use tracing::warn;
+use crate::{Result, HistorianError};
+
+pub type DependencySet = BTreeMap<String, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+    Python,
+    Go,
+    Maven,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ManifestKind {
+    CargoToml,
+    CargoLock,
+    PackageJson,
+    PackageLock,
+    YarnLock,
+    Requirements,
+    PyProject,
+    GoMod,
+    Pom,
+}
+
+impl ManifestKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        match name {
+            "Cargo.toml" => Some(Self::CargoToml),
+            "Cargo.lock" => Some(Self::CargoLock),
+            "package.json" => Some(Self::PackageJson),
+            "package-lock.json" | "npm-shrinkwrap.json" => Some(Self::PackageLock),
+            "yarn.lock" => Some(Self::YarnLock),
+            "pyproject.toml" => Some(Self::PyProject),
+            "go.mod" => Some(Self::GoMod),
+            "pom.xml" => Some(Self::Pom),
+            _ if name.starts_with("requirements") && name.ends_with(".txt") => Some(Self::Requirements),
+            _ => None,
+        }
+    }
+
+    pub fn ecosystem(&self) -> Ecosystem {
+        match self {
+            Self::CargoToml | Self::CargoLock => Ecosystem::Cargo,
+            Self::PackageJson | Self::PackageLock | Self::YarnLock => Ecosystem::Npm,
+            Self::Requirements | Self::PyProject => Ecosystem::Python,
+            Self::GoMod => Ecosystem::Go,
+            Self::Pom => Ecosystem::Maven,
+        }
+    }
+
+    pub fn is_lockfile(&self) -> bool {
+        matches!(self, Self::CargoLock | Self::PackageLock | Self::YarnLock)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VersionBump {
+    Major,
+    Minor,
+    Patch,
+    Downgrade,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DependencyChangeKind {
+    Added { version: String },
+    Removed { version: String },
+    Updated { from: String, to: String, bump: VersionBump },
+}
+
+impl DependencyChangeKind {
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Added { version } => format!("added {}", version),
+            Self::Removed { version } => format!("removed {}", version),
+            Self::Updated { from, to, bump } => format!("{} -> {} ({:?})", from, to, bump),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyChange {
+    pub commit_id: String,
+    pub author: String,
+    pub timestamp: DateTime<Utc>,
+    pub manifest: PathBuf,
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub kind: DependencyChangeKind,
+}
+
+/// Collect the dependency additions, removals and version bumps made by a commit.
+pub fn commit_dependency_changes(
+    repo: &Repository,
+    mailmap: &Mailmap,
+    commit: &Commit,
+    diff: &Diff,
+) -> Result<Vec<DependencyChange>> {
+    let mut changes = Vec::new();
+    let author = commit.author_with_mailmap(mailmap)?;
+    let timestamp = DateTime::from_timestamp(author.when().seconds(), 0).unwrap_or_else(Utc::now);
+
+    for delta in diff.deltas() {
+        let Some(path) = delta.new_file().path().or_else(|| delta.old_file().path()) else {
+            continue;
+        };
+        let Some(kind) = ManifestKind::from_path(path) else {
+            continue;
+        };
+
+        let old = read_blob(repo, delta.old_file().id())?;
+        let new = read_blob(repo, delta.new_file().id())?;
+
+        let (old_deps, new_deps) = match (parse_manifest(kind, &old), parse_manifest(kind, &new)) {
+            (Ok(old_deps), Ok(new_deps)) => (old_deps, new_deps),
+            (Err(e), _) | (_, Err(e)) => {
+                warn!("Skipping {} in {}: {}", path.display(), commit.id(), e);
+                continue;
+            }
+        };
+
+        for (name, kind_of_change) in diff_dependencies(&old_deps, &new_deps) {
+            changes.push(DependencyChange {
+                commit_id: commit.id().to_string(),
+                author: author.name().unwrap_or("unknown").to_string(),
+                timestamp,
+                manifest: path.to_path_buf(),
+                ecosystem: kind.ecosystem(),
+                name,
+                kind: kind_of_change,
+            });
+        }
+    }
+
+    Ok(changes)
+}
+
+fn read_blob(repo: &Repository, id: Oid) -> Result<String> {
+    if id.is_zero() {
+        return Ok(String::new());
+    }
+    let blob = repo.find_blob(id)?;
+    Ok(String::from_utf8_lossy(blob.content()).into_owned())
+}
+
+pub fn diff_dependencies(old: &DependencySet, new: &DependencySet) -> Vec<(String, DependencyChangeKind)> {
+    let mut changes = Vec::new();
+
+    for (name, version) in new {
+        match old.get(name) {
+            None => changes.push((name.clone(), DependencyChangeKind::Added {
+                version: version.clone(),
+            })),
+            Some(previous) if previous != version => changes.push((name.clone(), DependencyChangeKind::Updated {
+                from: previous.clone(),
+                to: version.clone(),
+                bump: classify_bump(previous, version),
+            })),
+            Some(_) => {}
+        }
+    }
+
+    for (name, version) in old {
+        if !new.contains_key(name) {
+            changes.push((name.clone(), DependencyChangeKind::Removed {
+                version: version.clone(),
+            }));
+        }
+    }
+
+    changes
+}
+
+pub fn classify_bump(from: &str, to: &str) -> VersionBump {
+    let (Some(from), Some(to)) = (parse_version(from), parse_version(to)) else {
+        return VersionBump::Other;
+    };
+
+    if to < from {
+        VersionBump::Downgrade
+    } else if to.0 != from.0 {
+        VersionBump::Major
+    } else if to.1 != from.1 {
+        VersionBump::Minor
+    } else if to.2 != from.2 {
+        VersionBump::Patch
+    } else {
+        VersionBump::Other
+    }
+}
+
+// Lenient version parsing: requirement operators and a leading `v` are ignored,
+// missing components count as zero.
+fn parse_version(spec: &str) -> Option<(u64, u64, u64)> {
+    let version = spec
+        .split([',', ' ', '|'])
+        .find(|part| part.chars().any(|c| c.is_ascii_digit()))?
+        .trim_start_matches(|c: char| "^~=<>!v".contains(c));
+
+    let mut parts = version.split('.').map(|part| {
+        let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+        digits.parse::<u64>().ok()
+    });
+
+    let major = parts.next()??;
+    let minor = parts.next().flatten().unwrap_or(0);
+    let patch = parts.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+pub fn parse_manifest(kind: ManifestKind, content: &str) -> Result<DependencySet> {
+    if content.trim().is_empty() {
+        return Ok(DependencySet::new());
+    }
+
+    match kind {
+        ManifestKind::CargoToml => parse_cargo_toml(content),
+        ManifestKind::CargoLock => parse_cargo_lock(content),
+        ManifestKind::PackageJson => parse_package_json(content),
+        ManifestKind::PackageLock => parse_package_lock(content),
+        ManifestKind::YarnLock => Ok(parse_yarn_lock(content)),
+        ManifestKind::Requirements => Ok(parse_requirements(content)),
+        ManifestKind::PyProject => parse_pyproject(content),
+        ManifestKind::GoMod => Ok(parse_go_mod(content)),
+        ManifestKind::Pom => Ok(parse_pom(content)),
+    }
+}
+
+fn parse_toml(content: &str) -> Result<toml::Value> {
+    toml::from_str(content)
+        .map_err(|e| HistorianError::Analysis(format!("Invalid TOML manifest: {}", e)))
+}
+
+fn parse_json(content: &str) -> Result<serde_json::Value> {
+    serde_json::from_str(content)
+        .map_err(|e| HistorianError::Analysis(format!("Invalid JSON manifest: {}", e)))
+}
+
+fn parse_cargo_toml(content: &str) -> Result<DependencySet> {
+    let value = parse_toml(content)?;
+    let mut deps = DependencySet::new();
+
+    let mut tables = vec![&value];
+    if let Some(targets) = value.get("target").and_then(|t| t.as_table()) {
+        tables.extend(targets.values());
+    }
+    if let Some(workspace) = value.get("workspace") {
+        tables.push(workspace);
+    }
+
+    for table in tables {
+        for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
+            let Some(entries) = table.get(section).and_then(|s| s.as_table()) else {
+                continue;
+            };
+            for (name, spec) in entries {
+                let version = match spec {
+                    toml::Value::String(version) => version.clone(),
+                    toml::Value::Table(table) => {
+                        if let Some(version) = table.get("version").and_then(|v| v.as_str()) {
+                            version.to_string()
+                        } else if table.get("workspace").and_then(|v| v.as_bool()) == Some(true) {
+                            "workspace".to_string()
+                        } else if table.contains_key("git") {
+                            "git".to_string()
+                        } else if table.contains_key("path") {
+                            "path".to_string()
+                        } else {
+                            "*".to_string()
+                        }
+                    }
+                    _ => continue,
+                };
+                deps.insert(name.clone(), version);
+            }
+        }
+    }
+
+    Ok(deps)
+}
+
+fn insert_locked(deps: &mut DependencySet, name: &str, version: &str) {
+    // Lockfiles may carry several versions of one package
+    deps.entry(name.to_string())
+        .and_modify(|existing| {
+            let mut versions: Vec<&str> = existing.split(", ").collect();
+            if !versions.contains(&version) {
+                versions.push(version);
+                versions.sort();
+                *existing = versions.join(", ");
+            }
+        })
+        .or_insert_with(|| version.to_string());
+}
+
+fn parse_cargo_lock(content: &str) -> Result<DependencySet> {
+    let value = parse_toml(content)?;
+    let mut deps = DependencySet::new();
+
+    if let Some(packages) = value.get("package").and_then(|p| p.as_array()) {
+        for package in packages {
+            let name = package.get("name").and_then(|n| n.as_str());
+            let version = package.get("version").and_then(|v| v.as_str());
+            if let (Some(name), Some(version)) = (name, version) {
+                insert_locked(&mut deps, name, version);
+            }
+        }
+    }
+
+    Ok(deps)
+}
+
+fn parse_package_json(content: &str) -> Result<DependencySet> {
+    let value = parse_json(content)?;
+    let mut deps = DependencySet::new();
+
+    for section in ["dependencies", "devDependencies", "peerDependencies", "optionalDependencies"] {
+        if let Some(entries) = value.get(section).and_then(|s| s.as_object()) {
+            for (name, version) in entries {
+                if let Some(version) = version.as_str() {
+                    deps.insert(name.clone(), version.to_string());
+                }
+            }
+        }
+    }
+
+    Ok(deps)
+}
+
+fn parse_package_lock(content: &str) -> Result<DependencySet> {
+    let value = parse_json(content)?;
+    let mut deps = DependencySet::new();
+
+    if let Some(packages) = value.get("packages").and_then(|p| p.as_object()) {
+        // lockfileVersion 2 and 3
+        for (key, package) in packages {
+            let Some((_, name)) = key.rsplit_once("node_modules/") else {
+                continue;
+            };
+            if let Some(version) = package.get("version").and_then(|v| v.as_str()) {
+                insert_locked(&mut deps, name, version);
+            }
+        }
+    } else if let Some(dependencies) = value.get("dependencies").and_then(|d| d.as_object()) {
+        // lockfileVersion 1
+        for (name, package) in dependencies {
+            if let Some(version) = package.get("version").and_then(|v| v.as_str()) {
+                insert_locked(&mut deps, name, version);
+            }
+        }
+    }
+
+    Ok(deps)
+}
+
+fn parse_yarn_lock(content: &str) -> DependencySet {
+    let mut deps = DependencySet::new();
+    let mut current: Vec<String> = Vec::new();
+
+    for line in content.lines() {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+
+        if !line.starts_with(' ') {
+            // Entry header, e.g. `"@scope/pkg@^1.0.0", "@scope/pkg@^1.1.0":`
+            current = line.trim_end_matches(':')
+                .split(',')
+                .filter_map(|spec| {
+                    let spec = spec.trim().trim_matches('"');
+                    // A leading `@` belongs to the scope
+                    let (at, _) = spec.char_indices().skip(1).find(|&(_, c)| c == '@')?;
+                    Some(spec[..at].to_string())
+                })
+                .collect();
+            current.dedup();
+        } else if let Some(version) = line.trim().strip_prefix("version") {
+            let version = version.trim_start_matches(':').trim().trim_matches('"');
+            for name in &current {
+                insert_locked(&mut deps, name, version);
+            }
+        }
+    }
+
+    deps
+}
+
+// Split a PEP 508 requirement such as `requests[socks]>=2.0; python_version>"3"`
+fn parse_requirement(requirement: &str) -> Option<(String, String)> {
+    let requirement = requirement.split(';').next()?.trim();
+    let end = requirement
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'))
+        .unwrap_or(requirement.len());
+    let name = &requirement[..end];
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut spec = requirement[end..].trim();
+    if spec.starts_with('[') {
+        spec = spec.split_once(']').map(|(_, rest)| rest.trim()).unwrap_or("");
+    }
+    let version = spec.strip_prefix("==").unwrap_or(spec).trim();
+
+    Some((
+        name.to_lowercase().replace('_', "-"),
+        if version.is_empty() { "*".to_string() } else { version.to_string() },
+    ))
+}
+
+fn parse_requirements(content: &str) -> DependencySet {
+    content.lines()
+        .map(|line| line.split(" #").next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('-'))
+        .filter_map(parse_requirement)
+        .collect()
+}
+
+fn parse_pyproject(content: &str) -> Result<DependencySet> {
+    let value = parse_toml(content)?;
+    let mut deps = DependencySet::new();
+
+    // PEP 621
+    if let Some(project) = value.get("project") {
+        let mut lists: Vec<&toml::Value> = project.get("dependencies").into_iter().collect();
+        if let Some(optional) = project.get("optional-dependencies").and_then(|o| o.as_table()) {
+            lists.extend(optional.values());
+        }
+        for list in lists {
+            for requirement in list.as_array().into_iter().flatten().filter_map(|r| r.as_str()) {
+                if let Some((name, version)) = parse_requirement(requirement) {
+                    deps.insert(name, version);
+                }
+            }
+        }
+    }
+
+    // Poetry
+    if let Some(poetry) = value.get("tool").and_then(|t| t.get("poetry")) {
+        let mut tables: Vec<&toml::Value> = Vec::new();
+        tables.extend(poetry.get("dependencies"));
+        tables.extend(poetry.get("dev-dependencies"));
+        if let Some(groups) = poetry.get("group").and_then(|g| g.as_table()) {
+            tables.extend(groups.values().filter_map(|g| g.get("dependencies")));
+        }
+
+        for table in tables.into_iter().filter_map(|t| t.as_table()) {
+            for (name, spec) in table {
+                if name == "python" {
+                    continue;
+                }
+                let version = match spec {
+                    toml::Value::String(version) => version.clone(),
+                    toml::Value::Table(table) => table.get("version")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("*")
+                        .to_string(),
+                    _ => continue,
+                };
+                deps.insert(name.to_lowercase().replace('_', "-"), version);
+            }
+        }
+    }
+
+    Ok(deps)
+}
+
+fn parse_go_mod(content: &str) -> DependencySet {
+    let mut deps = DependencySet::new();
+    let mut in_block = false;
+
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap_or("").trim();
+
+        let requirement = if in_block {
+            if line == ")" {
+                in_block = false;
+                continue;
+            }
+            line
+        } else if let Some(rest) = line.strip_prefix("require") {
+            let rest = rest.trim();
+            if rest == "(" {
+                in_block = true;
+                continue;
+            }
+            rest
+        } else {
+            continue;
+        };
+
+        let mut parts = requirement.split_whitespace();
+        if let (Some(module), Some(version)) = (parts.next(), parts.next()) {
+            deps.insert(module.to_string(), version.to_string());
+        }
+    }
+
+    deps
+}
+
+fn xml_tag<'a>(content: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = content.find(&open)? + open.len();
+    let end = content[start..].find(&close)? + start;
+    Some(content[start..end].trim())
+}
+
+fn parse_pom(content: &str) -> DependencySet {
+    let mut deps = DependencySet::new();
+
+    // Simple `<properties>` entries are used to resolve `${...}` versions
+    let mut properties = HashMap::new();
+    if let Some(block) = xml_tag(content, "properties") {
+        let mut rest = block;
+        while let Some(start) = rest.find('<') {
+            let Some(end) = rest[start..].find('>') else { break };
+            let tag = &rest[start + 1..start + end];
+            rest = &rest[start + end + 1..];
+            if tag.starts_with('/') || tag.starts_with('!') {
+                continue;
+            }
+            if let Some(value) = rest.find(&format!("</{}>", tag)).map(|i| &rest[..i]) {
+                properties.insert(tag.to_string(), value.trim().to_string());
+            }
+        }
+    }
+
+    for block in content.split("<dependency>").skip(1) {
+        let Some(block) = block.split("</dependency>").next() else {
+            continue;
+        };
+        let (Some(group), Some(artifact)) = (xml_tag(block, "groupId"), xml_tag(block, "artifactId")) else {
+            continue;
+        };
+
+        let mut version = xml_tag(block, "version").unwrap_or("*").to_string();
+        if let Some(property) = version.strip_prefix("${").and_then(|v| v.strip_suffix('}')) {
+            if let Some(resolved) = properties.get(property) {
+                version = resolved.clone();
+            }
+        }
+
+        deps.insert(format!("{}:{}", group, artifact), version);
+    }
+
+    deps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_detection() {
+        assert_eq!(ManifestKind::from_path(Path::new("crates/a/Cargo.toml")), Some(ManifestKind::CargoToml));
+        assert_eq!(ManifestKind::from_path(Path::new("requirements-dev.txt")), Some(ManifestKind::Requirements));
+        assert_eq!(ManifestKind::from_path(Path::new("src/main.rs")), None);
+    }
+
+    #[test]
+    fn test_version_bumps() {
+        assert_eq!(classify_bump("1.2.3", "2.0.0"), VersionBump::Major);
+        assert_eq!(classify_bump("^1.2", "^1.3"), VersionBump::Minor);
+        assert_eq!(classify_bump("v1.2.3", "v1.2.4"), VersionBump::Patch);
+        assert_eq!(classify_bump("1.2.3", "1.0.0"), VersionBump::Downgrade);
+        assert_eq!(classify_bump("git", "path"), VersionBump::Other);
+    }
+
+    #[test]
+    fn test_cargo_manifest_diff() {
+        let old = parse_manifest(ManifestKind::CargoToml, r#"
+            [dependencies]
+            serde = "1.0"
+            git2 = { version = "0.17", features = ["vendored"] }
+            glob = "0.3"
+        "#).unwrap();
+        let new = parse_manifest(ManifestKind::CargoToml, r#"
+            [dependencies]
+            serde = "1.0"
+            git2 = { version = "0.18" }
+
+            [dev-dependencies]
+            tempfile = "3.8"
+        "#).unwrap();
+
+        let changes = diff_dependencies(&old, &new);
+        assert!(changes.contains(&("git2".to_string(), DependencyChangeKind::Updated {
+            from: "0.17".to_string(),
+            to: "0.18".to_string(),
+            bump: VersionBump::Minor,
+        })));
+        assert!(changes.contains(&("tempfile".to_string(), DependencyChangeKind::Added {
+            version: "3.8".to_string(),
+        })));
+        assert!(changes.contains(&("glob".to_string(), DependencyChangeKind::Removed {
+            version: "0.3".to_string(),
+        })));
+        assert_eq!(changes.len(), 3);
+    }
+
+    #[test]
+    fn test_other_ecosystems() {
+        let deps = parse_manifest(ManifestKind::Requirements, "requests[socks]==2.31.0\n# comment\nDjango_Filter>=23.1 ; python_version > '3.8'\n-r base.txt\n").unwrap();
+        assert_eq!(deps.get("requests").map(String::as_str), Some("2.31.0"));
+        assert_eq!(deps.get("django-filter").map(String::as_str), Some(">=23.1"));
+
+        let deps = parse_manifest(ManifestKind::GoMod, "module x\n\nrequire github.com/a/b v1.2.3\nrequire (\n\tgolang.org/x/text v0.14.0 // indirect\n)\n").unwrap();
+        assert_eq!(deps.get("github.com/a/b").map(String::as_str), Some("v1.2.3"));
+        assert_eq!(deps.get("golang.org/x/text").map(String::as_str), Some("v0.14.0"));
+
+        let deps = parse_manifest(ManifestKind::YarnLock, "\"@babel/core@^7.0.0\", \"@babel/core@^7.1.0\":\n  version \"7.2.0\"\n\nleft-pad@^1.0.0:\n  version \"1.3.0\"\n").unwrap();
+        assert_eq!(deps.get("@babel/core").map(String::as_str), Some("7.2.0"));
+        assert_eq!(deps.get("left-pad").map(String::as_str), Some("1.3.0"));
+        let deps = parse_manifest(ManifestKind::YarnLock, "\"\", é@^1.0.0:\n  version \"1.0.0\"\n").unwrap();
+        assert_eq!(deps.get("é").map(String::as_str), Some("1.0.0"));
+
+        let deps = parse_manifest(ManifestKind::PackageLock, r#"{"packages": {"": {}, "node_modules/a": {"version": "1.0.0"}, "node_modules/a/node_modules/b": {"version": "2.0.0"}}}"#).unwrap();
+        assert_eq!(deps.get("b").map(String::as_str), Some("2.0.0"));
+
+        let deps = parse_manifest(ManifestKind::Pom, r#"
+            <project>
+              <properties><junit.version>5.10.0</junit.version></properties>
+              <dependencies>
+                <dependency>
+                  <groupId>org.junit.jupiter</groupId>
+                  <artifactId>junit-jupiter</artifactId>
+                  <version>${junit.version}</version>
+                </dependency>
+              </dependencies>
+            </project>
+        "#).unwrap();
+        assert_eq!(deps.get("org.junit.jupiter:junit-jupiter").map(String::as_str), Some("5.10.0"));
+
+        let deps = parse_manifest(ManifestKind::PyProject, r#"
+            [project]
+            dependencies = ["httpx>=0.25"]
+
+            [tool.poetry.dependencies]
+            python = "^3.11"
+            rich = { version = "^13.0" }
+        "#).unwrap();
+        assert_eq!(deps.get("httpx").map(String::as_str), Some(">=0.25"));
+        assert_eq!(deps.get("rich").map(String::as_str), Some("^13.0"));
+        assert!(!deps.contains_key("python"));
+    }
+}
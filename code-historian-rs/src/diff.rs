@@ -0,0 +1,153 @@
+/// One line of a unified diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    /// `diff --git`, `index`, `---`, `+++` and other lines outside any hunk
+    Header(&'a str),
+    /// A hunk header, with the enclosing function or section git shows after the second `@@`
+    Hunk { context: &'a str },
+    Added(&'a str),
+    Removed(&'a str),
+    Context(&'a str),
+}
+
+impl<'a> DiffLine<'a> {
+    /// The content of an added or removed line.
+    pub fn changed(&self) -> Option<&'a str> {
+        match *self {
+            DiffLine::Added(content) | DiffLine::Removed(content) => Some(content),
+            _ => None,
+        }
+    }
+}
+
+/// The lines of `diff`, told apart by where they sit rather than by their first characters.
+///
+/// Hunks are followed using the line counts in their `@@` headers, so a removed `-- comment` or
+/// an added `++i;` is content, and only outside a hunk is `---` or `+++` a file header. Diffs
+/// without hunk headers are taken to be the body of one hunk.
+pub fn lines(diff: &str) -> DiffLines<'_> {
+    DiffLines {
+        lines: diff.lines(),
+        region: Region::Body { started: false },
+    }
+}
+
+pub struct DiffLines<'a> {
+    lines: std::str::Lines<'a>,
+    region: Region,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Region {
+    // Lines left on each side of a hunk, from its header
+    Hunk { old: usize, new: usize },
+    // A hunk of unknown length, which runs until a line that is not part of a diff body
+    Body { started: bool },
+    Headers,
+}
+
+impl<'a> Iterator for DiffLines<'a> {
+    type Item = DiffLine<'a>;
+
+    fn next(&mut self) -> Option<DiffLine<'a>> {
+        loop {
+            let line = self.lines.next()?;
+            if let Some(header) = line.strip_prefix("@@") {
+                self.region = hunk_lengths(line)
+                    .map_or(Region::Body { started: true }, |(old, new)| Region::Hunk { old, new });
+                let context = header.split_once("@@").map_or("", |(_, context)| context.trim_start());
+                return Some(DiffLine::Hunk { context });
+            }
+
+            // `\ No newline at end of file` follows the last line of a side
+            if line.starts_with('\\') {
+                continue;
+            }
+
+            let in_hunk = match self.region {
+                Region::Hunk { old, new } => old > 0 || new > 0,
+                Region::Body { started } => started || !(line.starts_with("+++ ") || line.starts_with("--- ")),
+                Region::Headers => false,
+            };
+            if !in_hunk {
+                return Some(DiffLine::Header(line));
+            }
+
+            let (marker, content) = match line.char_indices().nth(1) {
+                Some((index, _)) => line.split_at(index),
+                None => (line, ""),
+            };
+            let (diff_line, old_lines, new_lines) = match marker {
+                "+" => (DiffLine::Added(content), 0, 1),
+                "-" => (DiffLine::Removed(content), 1, 0),
+                " " | "" => (DiffLine::Context(content), 1, 1),
+                // `diff --git`, `index` and the like start the next file's headers
+                _ => {
+                    self.region = Region::Headers;
+                    return Some(DiffLine::Header(line));
+                }
+            };
+            self.region = match self.region {
+                Region::Hunk { old, new } => Region::Hunk {
+                    old: old.saturating_sub(old_lines),
+                    new: new.saturating_sub(new_lines),
+                },
+                _ => Region::Body { started: true },
+            };
+            return Some(diff_line);
+        }
+    }
+}
+
+// The line counts of `@@ -1,3 +1,4 @@`; a missing count is one line
+fn hunk_lengths(header: &str) -> Option<(usize, usize)> {
+    let mut ranges = header.strip_prefix("@@ ")?.split(' ');
+    let old = ranges.next()?.strip_prefix('-')?;
+    let new = ranges.next()?.strip_prefix('+')?;
+    let length = |range: &str| match range.split_once(',') {
+        Some((_, count)) => count.parse().ok(),
+        None => Some(1),
+    };
+    Some((length(old)?, length(new)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hunk_lengths_decide_headers() {
+        let diff = "diff --git a/q.sql b/q.sql\n\
+                    --- a/q.sql\n\
+                    +++ b/q.sql\n\
+                    @@ -1,2 +1,2 @@ create table\n\
+                    --- old note\n\
+                    +++i;\n \
+                    select 1;\n\
+                    \\ No newline at end of file\n\
+                    --- a/next.sql\n\
+                    +++ b/next.sql\n\
+                    @@ -1 +1 @@\n\
+                    -a\n\
+                    +b\n";
+        let lines: Vec<DiffLine> = lines(diff).collect();
+        assert_eq!(lines, vec![
+            DiffLine::Header("diff --git a/q.sql b/q.sql"),
+            DiffLine::Header("--- a/q.sql"),
+            DiffLine::Header("+++ b/q.sql"),
+            DiffLine::Hunk { context: "create table" },
+            DiffLine::Removed("-- old note"),
+            DiffLine::Added("++i;"),
+            DiffLine::Context("select 1;"),
+            DiffLine::Header("--- a/next.sql"),
+            DiffLine::Header("+++ b/next.sql"),
+            DiffLine::Hunk { context: "" },
+            DiffLine::Removed("a"),
+            DiffLine::Added("b"),
+        ]);
+
+        // A bare hunk body, as in hand-written diffs
+        let changed: Vec<&str> = super::lines("+added\n context\n-removed").filter_map(|l| l.changed()).collect();
+        assert_eq!(changed, vec!["added", "removed"]);
+    }
+}
@@ -0,0 +1,254 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use git2::Repository;
+use serde::{Serialize, Deserialize};
+use crate::{Category, HistorianError, Result};
+use crate::analyzer::Change;
+use crate::ml::{CategoryScore, TrainingExample};
+
+pub const FEEDBACK_FILE: &str = "feedback.jsonl";
+
+/// Someone's correction of the categories of a commit, or of one file in it.
+///
+/// Stored one per line, so the feedback file also reads as `train --labels` input.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Correction {
+    // Full or abbreviated commit id
+    pub commit: String,
+    // None corrects every file in the commit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<PathBuf>,
+    // Empty marks the change as belonging to no category
+    pub categories: Vec<Category>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl Correction {
+    pub fn new(commit: impl Into<String>, categories: Vec<Category>) -> Self {
+        Self {
+            commit: commit.into(),
+            file_path: None,
+            categories,
+            author: None,
+            recorded_at: Utc::now(),
+        }
+    }
+
+    pub fn with_file_path(mut self, file_path: Option<PathBuf>) -> Self {
+        self.file_path = file_path;
+        self
+    }
+
+    pub fn with_author(mut self, author: Option<String>) -> Self {
+        self.author = author;
+        self
+    }
+
+    pub fn matches(&self, change: &Change) -> bool {
+        !self.commit.is_empty()
+            && change.commit_id.starts_with(self.commit.as_str())
+            && self.file_path.as_ref().is_none_or(|path| *path == change.file_path)
+    }
+}
+
+/// The corrections kept in the history directory, oldest first.
+pub struct FeedbackStore {
+    path: PathBuf,
+}
+
+impl FeedbackStore {
+    pub fn new(history_dir: &Path) -> Self {
+        Self {
+            path: history_dir.join(FEEDBACK_FILE),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn load(&self) -> Result<Vec<Correction>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = std::fs::read_to_string(&self.path)?;
+        content.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(number, line)| {
+                serde_json::from_str(line).map_err(|e| {
+                    HistorianError::Config(format!("{}:{}: {}", self.path.display(), number + 1, e))
+                })
+            })
+            .collect()
+    }
+
+    /// Appends a correction; later ones take precedence over earlier ones for the same change.
+    pub fn record(&self, correction: &Correction) -> Result<()> {
+        if correction.commit.trim().is_empty() {
+            return Err(HistorianError::InvalidArgument("a correction needs a commit".to_string()));
+        }
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(correction)?)?;
+        Ok(())
+    }
+}
+
+/// The full id of the commit `rev` names, so a correction never covers more commits than meant.
+pub fn resolve_commit(repo: &Repository, rev: &str) -> Result<String> {
+    repo.revparse_single(rev)
+        .and_then(|object| object.peel_to_commit())
+        .map(|commit| commit.id().to_string())
+        .map_err(|e| HistorianError::InvalidArgument(format!("unknown or ambiguous commit '{}': {}", rev, e.message())))
+}
+
+/// The correction that applies to `change`: the latest for its file, else the latest for its commit.
+pub fn correction_for<'c>(change: &Change, corrections: &'c [Correction]) -> Option<&'c Correction> {
+    let mut matching = corrections.iter().rev().filter(|c| c.matches(change));
+    let latest = matching.next()?;
+    if latest.file_path.is_some() {
+        return Some(latest);
+    }
+    Some(matching.find(|c| c.file_path.is_some()).unwrap_or(latest))
+}
+
+/// Overrides the categories of corrected changes, returning how many changed.
+pub fn apply(changes: &mut [Change], corrections: &[Correction]) -> usize {
+    let mut applied = 0;
+    for change in changes.iter_mut() {
+        let Some(correction) = correction_for(change, corrections) else {
+            continue;
+        };
+        let evidence = match &correction.author {
+            Some(author) => format!("corrected by {}", author),
+            None => "corrected".to_string(),
+        };
+        let scores: Vec<CategoryScore> = correction.categories.iter()
+            .map(|category| CategoryScore::certain(category.clone(), &evidence))
+            .collect();
+        if change.categories != correction.categories || change.category_scores != scores {
+            change.categories = correction.categories.clone();
+            change.category_scores = scores;
+            applied += 1;
+        }
+    }
+    applied
+}
+
+/// Whether any change does not yet carry the categories it was corrected to.
+pub fn is_pending(changes: &[Change], corrections: &[Correction]) -> bool {
+    changes.iter().any(|change| {
+        correction_for(change, corrections).is_some_and(|c| change.categories != c.categories)
+    })
+}
+
+/// Corrected changes as training examples, with the rest handed back unlabeled.
+pub fn training_examples<'c>(
+    changes: impl IntoIterator<Item = &'c Change>,
+    corrections: &[Correction],
+) -> (Vec<TrainingExample>, Vec<&'c Change>) {
+    let mut examples = Vec::new();
+    let mut unlabeled = Vec::new();
+    for change in changes {
+        match correction_for(change, corrections) {
+            Some(correction) => examples.push(TrainingExample {
+                message: change.message.clone(),
+                diff: change.diff.clone(),
+                categories: correction.categories.clone(),
+            }),
+            None => unlabeled.push(change),
+        }
+    }
+    (examples, unlabeled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn change(commit_id: &str, path: &str, categories: Vec<Category>) -> Change {
+        Change {
+            commit_id: commit_id.to_string(),
+            file_path: path.into(),
+            categories,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = FeedbackStore::new(&temp_dir.path().join(".code-historian"));
+        assert!(store.load().unwrap().is_empty());
+
+        let first = Correction::new("abc123", vec![Category::Security]).with_author(Some("Ann".to_string()));
+        let second = Correction::new("def456", vec![]).with_file_path(Some("src/lib.rs".into()));
+        store.record(&first).unwrap();
+        store.record(&second).unwrap();
+        assert_eq!(store.load().unwrap(), vec![first, second]);
+
+        // The feedback file doubles as training labels
+        let labels = crate::ml::load_labels(store.path()).unwrap();
+        assert_eq!(labels[0], ("abc123".to_string(), vec![Category::Security]));
+
+        assert!(store.record(&Correction::new(" ", vec![])).is_err());
+        std::fs::write(store.path(), "not json\n").unwrap();
+        assert!(matches!(store.load(), Err(HistorianError::Config(_))));
+    }
+
+    #[test]
+    fn test_resolve_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let oid = repo.commit(Some("HEAD"), &sig, &sig, "Initial", &tree, &[]).unwrap();
+
+        assert_eq!(resolve_commit(&repo, &oid.to_string()[..8]).unwrap(), oid.to_string());
+        assert_eq!(resolve_commit(&repo, "HEAD").unwrap(), oid.to_string());
+        for rev in ["a", "deadbeef", ""] {
+            assert!(matches!(resolve_commit(&repo, rev), Err(HistorianError::InvalidArgument(_))));
+        }
+    }
+
+    #[test]
+    fn test_apply_corrections() {
+        let mut changes = vec![
+            change("abc123", "src/auth.rs", vec![Category::Logic]),
+            change("abc123", "src/lib.rs", vec![Category::Logic]),
+            change("def456", "src/lib.rs", vec![Category::Api]),
+        ];
+        let corrections = vec![
+            Correction::new("abc", vec![Category::Security]).with_author(Some("Ann".to_string())),
+            Correction::new("abc", vec![Category::Documentation]).with_file_path(Some("src/lib.rs".into())),
+            // A later commit-wide correction does not undo a file's own
+            Correction::new("abc1", vec![Category::Security, Category::ErrorHandling]),
+        ];
+
+        assert!(is_pending(&changes, &corrections));
+        assert_eq!(apply(&mut changes, &corrections), 2);
+        assert_eq!(changes[0].categories, vec![Category::Security, Category::ErrorHandling]);
+        assert!(changes[0].category_scores.iter().all(|s| s.is_certain()));
+        assert_eq!(changes[0].category_scores[0].evidence, vec!["corrected".to_string()]);
+        assert_eq!(changes[1].categories, vec![Category::Documentation]);
+        assert_eq!(changes[2].categories, vec![Category::Api]);
+
+        assert!(!is_pending(&changes, &corrections));
+        assert_eq!(apply(&mut changes, &corrections), 0);
+
+        let (examples, unlabeled) = training_examples(&changes, &corrections);
+        assert_eq!(examples.len(), 2);
+        assert_eq!(examples[1].categories, vec![Category::Documentation]);
+        assert_eq!(unlabeled.len(), 1);
+        assert_eq!(unlabeled[0].commit_id, "def456");
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use git2::{Oid, Repository};
+use serde::{Serialize, Deserialize};
+use crate::{Result, HistorianError};
+
+// Identifies hook scripts we own, so foreign hooks are never overwritten or removed
+pub const HOOK_MARKER: &str = "# Installed by code-historian";
+
+// An existing hook is renamed with this suffix and called before ours
+const CHAINED_SUFFIX: &str = ".pre-historian";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookKind {
+    PostCommit,
+    PostMerge,
+    PrePush,
+    CommitMsg,
+}
+
+impl HookKind {
+    pub const ALL: [HookKind; 4] = [HookKind::PostCommit, HookKind::PostMerge, HookKind::PrePush, HookKind::CommitMsg];
+
+    // Installed when no hooks are named; message linting is opt-in
+    pub const DEFAULT: [HookKind; 3] = [HookKind::PostCommit, HookKind::PostMerge, HookKind::PrePush];
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            HookKind::PostCommit => "post-commit",
+            HookKind::PostMerge => "post-merge",
+            HookKind::PrePush => "pre-push",
+            HookKind::CommitMsg => "commit-msg",
+        }
+    }
+
+    fn script(&self) -> String {
+        let name = self.file_name();
+        let chained = format!("\"$(dirname \"$0\")/{}{}\"", name, CHAINED_SUFFIX);
+
+        // pre-push receives the pushed refs on stdin, which both hooks need to read
+        let (read_input, pipe) = match self {
+            HookKind::PrePush => ("input=$(cat)\n", "printf '%s\\n' \"$input\" | "),
+            _ => ("", ""),
+        };
+
+        format!(
+            "#!/bin/sh\n\
+             {marker}; remove with `code-historian hooks uninstall`\n\
+             {read_input}\
+             if [ -x {chained} ]; then\n    \
+                 {pipe}{chained} \"$@\" || exit $?\n\
+             fi\n\
+             command -v code-historian >/dev/null 2>&1 || exit 0\n\
+             {pipe}code-historian hooks run {name} \"$@\"\n",
+            marker = HOOK_MARKER,
+        )
+    }
+}
+
+impl std::str::FromStr for HookKind {
+    type Err = HistorianError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        HookKind::ALL.into_iter()
+            .find(|kind| kind.file_name() == s)
+            .ok_or_else(|| HistorianError::InvalidArgument(format!(
+                "Unsupported hook '{}', expected post-commit, post-merge, pre-push or commit-msg",
+                s
+            )))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HookState {
+    NotInstalled,
+    Installed { chained: bool },
+    // Some other tool's hook, which installing would chain
+    Foreign,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookStatus {
+    pub kind: HookKind,
+    pub path: PathBuf,
+    pub state: HookState,
+}
+
+pub struct HookManager {
+    hooks_dir: PathBuf,
+}
+
+impl HookManager {
+    /// Locate the hooks directory, honoring `core.hooksPath`.
+    pub fn new(repo: &Repository) -> Result<Self> {
+        let hooks_dir = match repo.config()?.get_path("core.hooksPath") {
+            Ok(path) if path.is_relative() => repo.workdir().unwrap_or_else(|| repo.path()).join(path),
+            Ok(path) => path,
+            Err(_) => repo.path().join("hooks"),
+        };
+        Ok(Self { hooks_dir })
+    }
+
+    pub fn hooks_dir(&self) -> &Path {
+        &self.hooks_dir
+    }
+
+    pub fn install(&self, kinds: &[HookKind]) -> Result<()> {
+        std::fs::create_dir_all(&self.hooks_dir)?;
+
+        for kind in kinds {
+            let path = self.hook_path(*kind);
+            if self.state(*kind)? == HookState::Foreign {
+                let chained = self.chained_path(*kind);
+                if chained.exists() {
+                    return Err(HistorianError::Config(format!(
+                        "Cannot chain {}: {} already exists",
+                        path.display(),
+                        chained.display()
+                    )));
+                }
+                std::fs::rename(&path, &chained)?;
+            }
+
+            std::fs::write(&path, kind.script())?;
+            make_executable(&path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove our hooks and put any chained hooks back in place.
+    pub fn uninstall(&self, kinds: &[HookKind]) -> Result<()> {
+        for kind in kinds {
+            if let HookState::Installed { chained } = self.state(*kind)? {
+                let path = self.hook_path(*kind);
+                std::fs::remove_file(&path)?;
+                if chained {
+                    std::fs::rename(self.chained_path(*kind), &path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn status(&self) -> Result<Vec<HookStatus>> {
+        HookKind::ALL.into_iter()
+            .map(|kind| Ok(HookStatus {
+                kind,
+                path: self.hook_path(kind),
+                state: self.state(kind)?,
+            }))
+            .collect()
+    }
+
+    fn state(&self, kind: HookKind) -> Result<HookState> {
+        let path = self.hook_path(kind);
+        if !path.exists() {
+            return Ok(HookState::NotInstalled);
+        }
+
+        let content = std::fs::read_to_string(&path).unwrap_or_default();
+        if content.contains(HOOK_MARKER) {
+            Ok(HookState::Installed { chained: self.chained_path(kind).exists() })
+        } else {
+            Ok(HookState::Foreign)
+        }
+    }
+
+    fn hook_path(&self, kind: HookKind) -> PathBuf {
+        self.hooks_dir.join(kind.file_name())
+    }
+
+    fn chained_path(&self, kind: HookKind) -> PathBuf {
+        self.hooks_dir.join(format!("{}{}", kind.file_name(), CHAINED_SUFFIX))
+    }
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// One line of pre-push input: `<local ref> <local sha> <remote ref> <remote sha>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushRef {
+    pub local_ref: String,
+    pub local_sha: String,
+    pub remote_ref: String,
+    pub remote_sha: String,
+}
+
+pub fn parse_push_refs(input: &str) -> Vec<PushRef> {
+    input.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            Some(PushRef {
+                local_ref: parts.next()?.to_string(),
+                local_sha: parts.next()?.to_string(),
+                remote_ref: parts.next()?.to_string(),
+                remote_sha: parts.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Commits a push would send that the remote does not have yet.
+///
+/// Deleted refs send nothing; new refs, and refs whose remote tip we have not fetched, send
+/// everything not already on a remote branch.
+pub fn pushed_commits(repo: &Repository, refs: &[PushRef]) -> Result<HashSet<String>> {
+    let mut commits = HashSet::new();
+
+    for push in refs {
+        let local = Oid::from_str(&push.local_sha)?;
+        if local.is_zero() {
+            continue;
+        }
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(local)?;
+        let remote = Oid::from_str(&push.remote_sha)?;
+        if !remote.is_zero() && repo.find_commit(remote).is_ok() {
+            revwalk.hide(remote)?;
+        } else {
+            revwalk.hide_glob("refs/remotes/*")?;
+        }
+
+        for oid in revwalk {
+            commits.insert(oid?.to_string());
+        }
+    }
+
+    Ok(commits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_install_chain_uninstall() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let manager = HookManager::new(&repo).unwrap();
+
+        std::fs::create_dir_all(manager.hooks_dir()).unwrap();
+        let existing = manager.hooks_dir().join("post-commit");
+        std::fs::write(&existing, "#!/bin/sh\necho existing\n").unwrap();
+
+        let status = manager.status().unwrap();
+        assert_eq!(status[0].state, HookState::Foreign);
+        assert_eq!(status[2].state, HookState::NotInstalled);
+
+        manager.install(&HookKind::ALL).unwrap();
+        manager.install(&HookKind::ALL).unwrap();
+
+        let status = manager.status().unwrap();
+        assert_eq!(status[0].state, HookState::Installed { chained: true });
+        assert_eq!(status[1].state, HookState::Installed { chained: false });
+
+        let script = std::fs::read_to_string(&existing).unwrap();
+        assert!(script.contains("post-commit.pre-historian"));
+        assert!(script.contains("code-historian hooks run post-commit"));
+        let pre_push = std::fs::read_to_string(manager.hooks_dir().join("pre-push")).unwrap();
+        assert!(pre_push.contains("input=$(cat)"));
+
+        manager.uninstall(&HookKind::ALL).unwrap();
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "#!/bin/sh\necho existing\n");
+        assert!(!manager.hooks_dir().join("pre-push").exists());
+        assert!(!manager.hooks_dir().join("post-commit.pre-historian").exists());
+    }
+
+    #[test]
+    fn test_hooks_path_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        repo.config().unwrap().set_str("core.hooksPath", ".githooks").unwrap();
+
+        let manager = HookManager::new(&repo).unwrap();
+        assert!(manager.hooks_dir().ends_with(".githooks"));
+        assert!("pre-push".parse::<HookKind>().is_ok());
+        assert_eq!("commit-msg".parse::<HookKind>().unwrap(), HookKind::CommitMsg);
+        assert!("pre-commit".parse::<HookKind>().is_err());
+    }
+
+    #[test]
+    fn test_pushed_commits() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+
+        let first = repo.commit(Some("HEAD"), &sig, &sig, "first", &tree, &[]).unwrap();
+        let parent = repo.find_commit(first).unwrap();
+        let second = repo.commit(Some("HEAD"), &sig, &sig, "second", &tree, &[&parent]).unwrap();
+
+        let zero = Oid::zero().to_string();
+        let input = format!("refs/heads/main {} refs/heads/main {}\n", second, first);
+        let refs = parse_push_refs(&input);
+        assert_eq!(refs[0].remote_ref, "refs/heads/main");
+
+        let pushed = pushed_commits(&repo, &refs).unwrap();
+        assert_eq!(pushed, HashSet::from([second.to_string()]));
+
+        let deleted = parse_push_refs(&format!("(delete) {} refs/heads/old {}\n", zero, first));
+        assert!(pushed_commits(&repo, &deleted).unwrap().is_empty());
+
+        // Someone else pushed a tip we have not fetched; what we last fetched is still on the remote
+        repo.reference("refs/remotes/origin/main", first, false, "fetch").unwrap();
+        let unknown = "1".repeat(40);
+        let refs = parse_push_refs(&format!("refs/heads/main {} refs/heads/main {}\n", second, unknown));
+        assert_eq!(pushed_commits(&repo, &refs).unwrap(), HashSet::from([second.to_string()]));
+    }
+}
@@ -0,0 +1,339 @@
+use std::path::{Component, Path, PathBuf};
+use git2::{ObjectType, Repository, Tree, TreeWalkMode, TreeWalkResult};
+use glob::{MatchOptions, Pattern};
+use crate::{Result, HistorianError};
+
+pub const IGNORE_FILE: &str = ".historianignore";
+
+const GITIGNORE_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    pattern: Pattern,
+    // Directory of the ignore file the rule came from, relative to the repository root
+    base: PathBuf,
+    negated: bool,
+    directory_only: bool,
+    anchored: bool,
+}
+
+impl IgnoreRule {
+    fn parse(line: &str, base: &Path) -> Result<Option<Self>> {
+        let line = trim_trailing_spaces(line);
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+
+        let (directory_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+
+        // A slash anywhere but the end anchors the pattern to the ignore file's directory
+        let anchored = line.contains('/');
+        let line = line.strip_prefix('/').unwrap_or(line);
+        if line.is_empty() {
+            return Ok(None);
+        }
+
+        let pattern = Pattern::new(line).map_err(|e| {
+            HistorianError::Config(format!("Invalid ignore pattern '{}': {}", line, e))
+        })?;
+
+        Ok(Some(Self {
+            pattern,
+            base: base.to_path_buf(),
+            negated,
+            directory_only,
+            anchored,
+        }))
+    }
+
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.directory_only && !is_dir {
+            return false;
+        }
+
+        let Ok(relative) = path.strip_prefix(&self.base) else {
+            return false;
+        };
+
+        if self.anchored {
+            self.pattern.matches_with(&relative.to_string_lossy(), GITIGNORE_OPTIONS)
+        } else {
+            relative.file_name()
+                .map(|name| self.pattern.matches_with(&name.to_string_lossy(), GITIGNORE_OPTIONS))
+                .unwrap_or(false)
+        }
+    }
+}
+
+// Trailing spaces are ignored unless escaped with a backslash
+fn trim_trailing_spaces(line: &str) -> &str {
+    let trimmed = line.trim_end_matches(' ');
+    if trimmed.ends_with('\\') && trimmed.len() < line.len() {
+        &line[..trimmed.len() + 1]
+    } else {
+        trimmed
+    }
+}
+
+/// Gitignore-style matcher built from `.historianignore` files.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreMatcher {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreMatcher {
+    /// Load the ignore file at the repository root and any nested ones below it.
+    pub fn load(root: &Path) -> Result<Self> {
+        let mut files = Vec::new();
+        collect_ignore_files(root, Path::new(""), &mut files)?;
+
+        // Deeper files take precedence, so their rules are evaluated last
+        files.sort_by_key(|base| base.components().count());
+
+        let mut matcher = Self::default();
+        for base in files {
+            let content = std::fs::read_to_string(root.join(&base).join(IGNORE_FILE))?;
+            matcher.add_rules(&content, &base)?;
+        }
+        Ok(matcher)
+    }
+
+    /// Load the ignore files committed in `tree`, at its root and below it.
+    ///
+    /// The object database lists them without walking build output and other untracked
+    /// directories of the working tree.
+    pub fn from_tree(repo: &Repository, tree: &Tree) -> Result<Self> {
+        let mut files = Vec::new();
+        tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+            if entry.name() == Some(IGNORE_FILE) && entry.kind() == Some(ObjectType::Blob) {
+                files.push((PathBuf::from(dir), entry.id()));
+            }
+            TreeWalkResult::Ok
+        })?;
+        files.sort_by_key(|(base, _)| base.components().count());
+
+        let mut matcher = Self::default();
+        for (base, id) in files {
+            let blob = repo.find_blob(id)?;
+            matcher.add_rules(&String::from_utf8_lossy(blob.content()), &base)?;
+        }
+        Ok(matcher)
+    }
+
+    pub fn add_rules(&mut self, content: &str, base: &Path) -> Result<()> {
+        for line in content.lines() {
+            if let Some(rule) = IgnoreRule::parse(line, base)? {
+                self.rules.push(rule);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Check a repository-relative file path against the rules.
+    ///
+    /// As in git, a file inside an ignored directory cannot be re-included.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+
+        let mut prefix = PathBuf::new();
+        let components: Vec<Component> = path.components().collect();
+        for (idx, component) in components.iter().enumerate() {
+            prefix.push(component);
+            let is_dir = idx + 1 < components.len();
+
+            if self.last_match(&prefix, is_dir) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn last_match(&self, path: &Path, is_dir: bool) -> bool {
+        self.rules.iter()
+            .rev()
+            .find(|rule| rule.matches(path, is_dir))
+            .map(|rule| !rule.negated)
+            .unwrap_or(false)
+    }
+}
+
+/// Pre-compiled include/exclude patterns combined with `.historianignore` rules.
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    ignore: IgnoreMatcher,
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(Self {
+            include: compile_patterns(include)?,
+            exclude: compile_patterns(exclude)?,
+            ignore: IgnoreMatcher::default(),
+        })
+    }
+
+    pub fn set_ignore(&mut self, ignore: IgnoreMatcher) {
+        self.ignore = ignore;
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        let path_str = path.to_string_lossy();
+
+        if self.exclude.iter().any(|p| p.matches(&path_str)) || self.ignore.is_ignored(path) {
+            return false;
+        }
+
+        self.include.is_empty() || self.include.iter().any(|p| p.matches(&path_str))
+    }
+}
+
+/// Compile glob patterns, reporting every invalid one at once.
+pub fn compile_patterns(patterns: &[String]) -> Result<Vec<Pattern>> {
+    let mut compiled = Vec::new();
+    let mut errors = Vec::new();
+
+    for pattern in patterns {
+        match Pattern::new(pattern) {
+            Ok(p) => compiled.push(p),
+            Err(e) => errors.push(format!("'{}': {}", pattern, e)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(compiled)
+    } else {
+        Err(HistorianError::Config(format!("Invalid file patterns: {}", errors.join(", "))))
+    }
+}
+
+fn collect_ignore_files(root: &Path, relative: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let dir = root.join(relative);
+    if dir.join(IGNORE_FILE).is_file() {
+        files.push(relative.to_path_buf());
+    }
+
+    for entry in std::fs::read_dir(&dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() && entry.file_name() != ".git" {
+            collect_ignore_files(root, &relative.join(entry.file_name()), files)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn matcher(content: &str) -> IgnoreMatcher {
+        let mut matcher = IgnoreMatcher::default();
+        matcher.add_rules(content, Path::new("")).unwrap();
+        matcher
+    }
+
+    #[test]
+    fn test_basic_patterns() {
+        let matcher = matcher("# comment\n*.log\n/build\ndocs/*.md\n");
+
+        assert!(matcher.is_ignored(Path::new("debug.log")));
+        assert!(matcher.is_ignored(Path::new("src/nested/trace.log")));
+        assert!(matcher.is_ignored(Path::new("build/output.rs")));
+        assert!(!matcher.is_ignored(Path::new("src/build/output.rs")));
+        assert!(matcher.is_ignored(Path::new("docs/intro.md")));
+        assert!(!matcher.is_ignored(Path::new("docs/guide/intro.md")));
+        assert!(!matcher.is_ignored(Path::new("src/main.rs")));
+    }
+
+    #[test]
+    fn test_negation_and_directories() {
+        let matcher = matcher("*.json\n!package.json\nfixtures/\n!fixtures/keep.rs\n**/gen/**\n");
+
+        assert!(matcher.is_ignored(Path::new("data/report.json")));
+        assert!(!matcher.is_ignored(Path::new("web/package.json")));
+        assert!(matcher.is_ignored(Path::new("tests/fixtures/sample.rs")));
+        // A file under an ignored directory stays ignored
+        assert!(matcher.is_ignored(Path::new("fixtures/keep.rs")));
+        assert!(!matcher.is_ignored(Path::new("src/fixtures.rs")));
+        assert!(matcher.is_ignored(Path::new("api/gen/v1/types.rs")));
+    }
+
+    #[test]
+    fn test_nested_ignore_files() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("web/src")).unwrap();
+        std::fs::write(temp_dir.path().join(IGNORE_FILE), "*.snap\n").unwrap();
+        std::fs::write(temp_dir.path().join("web").join(IGNORE_FILE), "/src/*.js\n!keep.snap\n").unwrap();
+
+        let matcher = IgnoreMatcher::load(temp_dir.path()).unwrap();
+        assert!(matcher.is_ignored(Path::new("web/src/bundle.js")));
+        assert!(!matcher.is_ignored(Path::new("src/bundle.js")));
+        assert!(matcher.is_ignored(Path::new("tests/output.snap")));
+        assert!(!matcher.is_ignored(Path::new("web/keep.snap")));
+    }
+
+    #[test]
+    fn test_ignore_files_from_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("web/src")).unwrap();
+        std::fs::write(temp_dir.path().join(IGNORE_FILE), "*.snap\n").unwrap();
+        std::fs::write(temp_dir.path().join("web").join(IGNORE_FILE), "/src/*.js\n!keep.snap\n").unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(IGNORE_FILE)).unwrap();
+        index.add_path(&Path::new("web").join(IGNORE_FILE)).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+
+        let matcher = IgnoreMatcher::from_tree(&repo, &tree).unwrap();
+        assert!(matcher.is_ignored(Path::new("web/src/bundle.js")));
+        assert!(!matcher.is_ignored(Path::new("src/bundle.js")));
+        assert!(matcher.is_ignored(Path::new("tests/output.snap")));
+        assert!(!matcher.is_ignored(Path::new("web/keep.snap")));
+    }
+
+    #[test]
+    fn test_path_filter() {
+        let mut filter = PathFilter::new(&[], &["target/**".to_string()]).unwrap();
+        filter.set_ignore(matcher("*.snap\n"));
+
+        assert!(filter.matches(Path::new("src/main.rs")));
+        assert!(!filter.matches(Path::new("target/debug/main")));
+        assert!(!filter.matches(Path::new("tests/output.snap")));
+
+        let filter = PathFilter::new(&["*.rs".to_string()], &[]).unwrap();
+        assert!(filter.matches(Path::new("src/main.rs")));
+        assert!(!filter.matches(Path::new("README.md")));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let mut matcher = IgnoreMatcher::default();
+        assert!(matcher.add_rules("src/[a-\n", Path::new("")).is_err());
+
+        let err = PathFilter::new(&["[".to_string(), "*.rs".to_string(), "a**b".to_string()], &[]).unwrap_err();
+        assert!(err.to_string().contains("'['"));
+        assert!(err.to_string().contains("'a**b'"));
+    }
+}
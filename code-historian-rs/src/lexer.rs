@@ -0,0 +1,480 @@
+use std::ops::Range;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use crate::diff::{self, DiffLine};
+use crate::rules;
+
+/// Which part of the source a piece of a line belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenContext {
+    Code,
+    Comment,
+    String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub context: TokenContext,
+    // Byte range within the line
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Added,
+    Removed,
+}
+
+/// An added or removed line of a diff, split into code, comments and strings.
+#[derive(Debug, Clone)]
+pub struct ChangedLine {
+    pub side: Side,
+    pub text: String,
+    pub tokens: Vec<Token>,
+}
+
+impl ChangedLine {
+    /// The line with everything outside `contexts` blanked, so columns and word boundaries survive.
+    pub fn text(&self, contexts: &[TokenContext]) -> String {
+        let mut text = String::with_capacity(self.text.len());
+        for token in &self.tokens {
+            let piece = &self.text[token.range.clone()];
+            if contexts.contains(&token.context) {
+                text.push_str(piece);
+            } else {
+                text.extend(piece.chars().map(|c| if c.is_whitespace() { c } else { ' ' }));
+            }
+        }
+        text
+    }
+}
+
+struct Syntax {
+    line_comments: &'static [&'static str],
+    block_comments: &'static [(&'static str, &'static str)],
+    // Delimiter, and whether the string may run on past the end of its line
+    strings: &'static [(&'static str, bool)],
+    // `'` opens a string only when it closes right away, as in Rust's `'a'` against `'a`
+    char_literals: bool,
+}
+
+const C_LIKE: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    strings: &[("\"", false), ("'", false)],
+    char_literals: false,
+};
+
+const RUST: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    strings: &[("\"", true)],
+    char_literals: true,
+};
+
+// JavaScript template literals and Go raw strings
+const BACKTICK: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    strings: &[("`", true), ("\"", false), ("'", false)],
+    char_literals: false,
+};
+
+const PYTHON: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comments: &[],
+    strings: &[("\"\"\"", true), ("'''", true), ("\"", false), ("'", false)],
+    char_literals: false,
+};
+
+const HASH: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comments: &[],
+    strings: &[("\"", false), ("'", false)],
+    char_literals: false,
+};
+
+const SQL: Syntax = Syntax {
+    line_comments: &["--"],
+    block_comments: &[("/*", "*/")],
+    strings: &[("'", false), ("\"", false)],
+    char_literals: false,
+};
+
+const CSS: Syntax = Syntax {
+    line_comments: &[],
+    block_comments: &[("/*", "*/")],
+    strings: &[("\"", false), ("'", false)],
+    char_literals: false,
+};
+
+// Quotes in markup are mostly prose, so only comments are told apart
+const MARKUP: Syntax = Syntax {
+    line_comments: &[],
+    block_comments: &[("<!--", "-->")],
+    strings: &[],
+    char_literals: false,
+};
+
+const JSON: Syntax = Syntax {
+    line_comments: &[],
+    block_comments: &[],
+    strings: &[("\"", false)],
+    char_literals: false,
+};
+
+// Files in a language we cannot lex are all code
+const PLAIN: Syntax = Syntax {
+    line_comments: &[],
+    block_comments: &[],
+    strings: &[],
+    char_literals: false,
+};
+
+fn syntax(path: Option<&Path>) -> &'static Syntax {
+    match path.and_then(rules::language) {
+        Some("rust") => &RUST,
+        Some("javascript" | "typescript" | "go") => &BACKTICK,
+        Some("java" | "kotlin" | "c" | "cpp" | "csharp" | "php" | "swift" | "scala") => &C_LIKE,
+        Some("python") => &PYTHON,
+        Some("ruby" | "shell" | "toml" | "yaml") => &HASH,
+        Some("sql") => &SQL,
+        Some("css") => &CSS,
+        Some("html" | "markdown") => &MARKUP,
+        Some("json") => &JSON,
+        _ => &PLAIN,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Code,
+    Comment(&'static str),
+    String(&'static str, bool),
+}
+
+/// Splits the lines of one version of a file, carrying open comments and strings between lines.
+pub struct Lexer {
+    syntax: &'static Syntax,
+    state: State,
+}
+
+impl Lexer {
+    pub fn for_path(path: Option<&Path>) -> Self {
+        Self {
+            syntax: syntax(path),
+            state: State::Code,
+        }
+    }
+
+    /// Forgets any open comment or string, as at the start of a hunk.
+    pub fn reset(&mut self) {
+        self.state = State::Code;
+    }
+
+    pub fn line(&mut self, line: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut push = |context, range: Range<usize>| {
+            if !range.is_empty() {
+                tokens.push(Token { context, range });
+            }
+        };
+
+        let mut start = 0;
+        while start < line.len() {
+            match self.state {
+                State::Comment(close) => {
+                    let end = match line[start..].find(close) {
+                        Some(offset) => {
+                            self.state = State::Code;
+                            start + offset + close.len()
+                        }
+                        None => line.len(),
+                    };
+                    push(TokenContext::Comment, start..end);
+                    start = end;
+                }
+                State::String(delimiter, multiline) => {
+                    let end = match closing_quote(line, start, delimiter) {
+                        Some(end) => {
+                            self.state = State::Code;
+                            end
+                        }
+                        None => {
+                            if !multiline {
+                                self.state = State::Code;
+                            }
+                            line.len()
+                        }
+                    };
+                    push(TokenContext::String, start..end);
+                    start = end;
+                }
+                State::Code => {
+                    let (end, next) = self.next_opening(line, start);
+                    push(TokenContext::Code, start..end);
+                    start = end;
+                    match next {
+                        Some(Opening::LineComment) => {
+                            push(TokenContext::Comment, start..line.len());
+                            start = line.len();
+                        }
+                        Some(Opening::Block(open, close)) => {
+                            self.state = State::Comment(close);
+                            push(TokenContext::Comment, start..start + open.len());
+                            start += open.len();
+                        }
+                        Some(Opening::String(delimiter, multiline)) => {
+                            self.state = State::String(delimiter, multiline);
+                            push(TokenContext::String, start..start + delimiter.len());
+                            start += delimiter.len();
+                        }
+                        None => {}
+                    }
+                }
+            }
+        }
+        merge(tokens)
+    }
+
+    // Where the code from `start` ends, and what opens there
+    fn next_opening(&self, line: &str, start: usize) -> (usize, Option<Opening>) {
+        for (index, c) in line[start..].char_indices() {
+            let at = start + index;
+            let rest = &line[at..];
+            if self.syntax.line_comments.iter().any(|marker| rest.starts_with(marker)) {
+                return (at, Some(Opening::LineComment));
+            }
+            if let Some((open, close)) = self.syntax.block_comments.iter().find(|(open, _)| rest.starts_with(open)) {
+                return (at, Some(Opening::Block(open, close)));
+            }
+            if let Some((delimiter, multiline)) = self.syntax.strings.iter().find(|(d, _)| rest.starts_with(d)) {
+                return (at, Some(Opening::String(delimiter, *multiline)));
+            }
+            if c == '\'' && self.syntax.char_literals && is_char_literal(rest) {
+                return (at, Some(Opening::String("'", false)));
+            }
+        }
+        (line.len(), None)
+    }
+}
+
+enum Opening {
+    LineComment,
+    Block(&'static str, &'static str),
+    String(&'static str, bool),
+}
+
+// The end of the string closed by `delimiter` at or after `start`, skipping escapes
+fn closing_quote(line: &str, start: usize, delimiter: &str) -> Option<usize> {
+    let mut chars = line[start..].char_indices();
+    while let Some((index, c)) = chars.next() {
+        if c == '\\' && delimiter != "`" {
+            chars.next();
+        } else if line[start + index..].starts_with(delimiter) {
+            return Some(start + index + delimiter.len());
+        }
+    }
+    None
+}
+
+// `'a'` and `'\n'` are characters, `'a` is a lifetime or label
+fn is_char_literal(rest: &str) -> bool {
+    let mut chars = rest.chars().skip(1);
+    match chars.next() {
+        Some('\\') => closing_quote(rest, 1, "'").is_some(),
+        Some(_) => chars.next() == Some('\''),
+        None => false,
+    }
+}
+
+// Joins neighbours in the same context, such as a string's opening quote and its body
+fn merge(tokens: Vec<Token>) -> Vec<Token> {
+    let mut merged: Vec<Token> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        match merged.last_mut() {
+            Some(last) if last.context == token.context && last.range.end == token.range.start => {
+                last.range.end = token.range.end;
+            }
+            _ => merged.push(token),
+        }
+    }
+    merged
+}
+
+/// The added and removed lines of a diff of the file at `path`.
+///
+/// Removed lines are lexed in order with the old version's context lines and added lines with
+/// the new version's, so a comment opened on one line is still a comment on the next.
+pub fn lex_diff(path: Option<&Path>, diff: &str) -> Vec<ChangedLine> {
+    let mut old = Lexer::for_path(path);
+    let mut new = Lexer::for_path(path);
+    let mut lines = Vec::new();
+
+    for line in diff::lines(diff) {
+        match line {
+            DiffLine::Hunk { .. } => {
+                old.reset();
+                new.reset();
+            }
+            DiffLine::Added(content) => lines.push(ChangedLine {
+                side: Side::Added,
+                tokens: new.line(content),
+                text: content.to_string(),
+            }),
+            DiffLine::Removed(content) => lines.push(ChangedLine {
+                side: Side::Removed,
+                tokens: old.line(content),
+                text: content.to_string(),
+            }),
+            DiffLine::Context(content) => {
+                old.line(content);
+                new.line(content);
+            }
+            DiffLine::Header(_) => {}
+        }
+    }
+    lines
+}
+
+/// Whether `word` is one of the words of `text`, or a part of one of its identifiers.
+///
+/// `env` is found in `env_var` and `getEnv` but not in `environment`. Lowercase words match
+/// regardless of case.
+pub fn contains_word(text: &str, word: &str) -> bool {
+    let ignore_case = !word.chars().any(char::is_uppercase);
+    let same = |candidate: &str| {
+        if ignore_case { candidate.eq_ignore_ascii_case(word) } else { candidate == word }
+    };
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty())
+        .any(|w| same(w) || identifier_parts(w).into_iter().any(same))
+}
+
+/// Whether a pattern is matched as a whole word rather than as a substring.
+pub fn is_word(pattern: &str) -> bool {
+    !pattern.is_empty() && pattern.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+// Splits at underscores and case changes: `HTTPServer_config` is `HTTP`, `Server`, `config`
+fn identifier_parts(word: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    for piece in word.split('_').filter(|p| !p.is_empty()) {
+        let chars: Vec<(usize, char)> = piece.char_indices().collect();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (previous, (at, current)) = (chars[i - 1].1, chars[i]);
+            let next_lower = chars.get(i + 1).is_some_and(|(_, c)| c.is_lowercase());
+            if (previous.is_lowercase() && current.is_uppercase())
+                || (previous.is_uppercase() && current.is_uppercase() && next_lower)
+            {
+                parts.push(&piece[start..at]);
+                start = at;
+            }
+        }
+        parts.push(&piece[start..]);
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contexts(path: &str, lines: &[&str]) -> Vec<Vec<(TokenContext, String)>> {
+        let mut lexer = Lexer::for_path(Some(Path::new(path)));
+        lines.iter()
+            .map(|line| {
+                lexer.line(line).into_iter()
+                    .map(|t| (t.context, line[t.range].to_string()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_lex_rust() {
+        use TokenContext::*;
+        let lines = contexts("src/lib.rs", &[
+            r#"let s = "a \" // b"; // note"#,
+            "fn f<'a>(c: char) -> bool { c == '\\'' } /* open",
+            "still comment */ x",
+        ]);
+        assert_eq!(lines[0], vec![
+            (Code, "let s = ".to_string()),
+            (String, r#""a \" // b""#.to_string()),
+            (Code, "; ".to_string()),
+            (Comment, "// note".to_string()),
+        ]);
+        assert_eq!(lines[1][1], (String, "'\\''".to_string()));
+        assert_eq!(lines[1][0].0, Code);
+        assert!(lines[1][0].1.contains("<'a>"));
+        assert_eq!(lines[1][3], (Comment, "/* open".to_string()));
+        assert_eq!(lines[2], vec![(Comment, "still comment */".to_string()), (Code, " x".to_string())]);
+    }
+
+    #[test]
+    fn test_lex_other_languages() {
+        use TokenContext::*;
+        let python = contexts("app.py", &["x = '''doc # not a comment", "end''' # comment"]);
+        assert_eq!(python[0][1], (String, "'''doc # not a comment".to_string()));
+        assert_eq!(python[1], vec![
+            (String, "end'''".to_string()),
+            (Code, " ".to_string()),
+            (Comment, "# comment".to_string()),
+        ]);
+
+        // A plain quote does not run on to the next line
+        let javascript = contexts("app.js", &["let s = 'open", "log()"]);
+        assert_eq!(javascript[1], vec![(Code, "log()".to_string())]);
+
+        let sql = contexts("schema.sql", &["select 1 -- why"]);
+        assert_eq!(sql[0][1], (Comment, "-- why".to_string()));
+
+        let unknown = contexts("Makefile", &["# all code"]);
+        assert_eq!(unknown[0], vec![(Code, "# all code".to_string())]);
+    }
+
+    #[test]
+    fn test_lex_diff() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\n\
+                    --- a/src/lib.rs\n\
+                    +++ b/src/lib.rs\n\
+                    @@ -1,2 +1,3 @@\n \
+                    /* a comment that\n\
+                    -   says log\n\
+                    +   says trace */\n\
+                    +let env = \"x\";\n";
+        let lines = lex_diff(Some(Path::new("src/lib.rs")), diff);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].side, Side::Removed);
+        assert_eq!(lines[0].tokens[0].context, TokenContext::Comment);
+        assert_eq!(lines[1].side, Side::Added);
+        assert_eq!(lines[2].text(&[TokenContext::Code]), "let env =    ;");
+        assert_eq!(lines[2].text(&[TokenContext::String]), "          \"x\" ");
+
+        // Removed SQL comments and markdown rules at the start of a hunk are not file headers
+        let diff = "--- a/schema.sql\n+++ b/schema.sql\n@@ -1,2 +1,1 @@\n--- drop later\n---\n+-- keep\n\\ No newline at end of file\n\
+                    --- a/next.sql\n+++ b/next.sql\n@@ -1 +1 @@\n-a\n+b\n";
+        let lines = lex_diff(Some(Path::new("schema.sql")), diff);
+        assert_eq!(lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>(), vec!["-- drop later", "--", "-- keep", "a", "b"]);
+        assert_eq!(lines[0].tokens[0].context, TokenContext::Comment);
+
+        // A bare hunk body, as in hand-written diffs
+        let lines = lex_diff(None, "+added\n context\n-removed");
+        assert_eq!(lines.iter().map(|l| l.side).collect::<Vec<_>>(), vec![Side::Added, Side::Removed]);
+    }
+
+    #[test]
+    fn test_contains_word() {
+        assert!(contains_word("let env_var = getEnv();", "env"));
+        assert!(contains_word("ENV", "env"));
+        assert!(!contains_word("let environment = 1;", "env"));
+        assert!(!contains_word("catalog.remove(item)", "log"));
+        assert!(!contains_word("catalog.remove(item)", "move"));
+        assert!(contains_word("fn parse() -> ParseResult", "Result"));
+        assert!(!contains_word("let result = 1;", "Result"));
+        assert!(is_word("cache") && !is_word("debug!") && !is_word("///"));
+    }
+}
@@ -3,15 +3,41 @@ use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 use thiserror::Error;
 
+pub mod activity;
 pub mod analyzer;
+pub mod attributes;
+pub mod blobs;
+pub mod changelog;
+pub mod components;
 pub mod config;
+pub mod dependencies;
+pub mod diff;
+pub mod feedback;
 pub mod git;
+pub mod hooks;
+pub mod ignore;
 pub mod interactive;
+pub mod lexer;
+pub mod lint;
+pub mod message;
+pub mod mechanical;
 pub mod ml;
 pub mod plugin;
+pub mod precommit;
+pub mod provenance;
+pub mod query;
+pub mod releases;
 pub mod report;
+pub mod rules;
+pub mod search;
+pub mod server;
+pub mod signatures;
+pub mod symbols;
+pub mod szz;
+pub mod testing;
 pub mod visualization;
 pub mod watch;
+pub mod workspace;
 
 pub use analyzer::{Analysis, Analyzer, Category, Change, Pattern};
 pub use config::{Config, load_config};
@@ -58,6 +84,9 @@ pub enum HistorianError {
 
     #[error("Watch error: {0}")]
     Watch(#[from] notify::Error),
+
+    #[error("Query error: {0}")]
+    Query(String),
 }
 
 pub type Result<T> = std::result::Result<T, HistorianError>;
@@ -191,6 +220,38 @@ pub enum Category {
     Accessibility,
 }
 
+impl Category {
+    pub const ALL: [Category; 15] = [
+        Category::Architecture,
+        Category::Api,
+        Category::Logic,
+        Category::Data,
+        Category::ErrorHandling,
+        Category::Logging,
+        Category::Documentation,
+        Category::Testing,
+        Category::Performance,
+        Category::Security,
+        Category::Refactoring,
+        Category::Dependencies,
+        Category::Configuration,
+        Category::UiUx,
+        Category::Accessibility,
+    ];
+}
+
+impl std::str::FromStr for Category {
+    type Err = HistorianError;
+
+    /// Case-insensitive, ignoring separators, so `error-handling` and `ErrorHandling` both parse.
+    fn from_str(s: &str) -> Result<Self> {
+        let wanted: String = s.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+        Category::ALL.into_iter()
+            .find(|category| format!("{:?}", category).to_lowercase() == wanted)
+            .ok_or_else(|| HistorianError::InvalidArgument(format!("Unknown category '{}'", s)))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Analysis {
     pub changes: Vec<Change>,
@@ -238,6 +299,14 @@ mod tests {
         assert_ne!(Category::Api, Category::Logic);
     }
 
+    #[test]
+    fn test_category_from_str() {
+        assert_eq!("security".parse::<Category>().unwrap(), Category::Security);
+        assert_eq!("error-handling".parse::<Category>().unwrap(), Category::ErrorHandling);
+        assert_eq!("UiUx".parse::<Category>().unwrap(), Category::UiUx);
+        assert!("bugs".parse::<Category>().is_err());
+    }
+
     #[test]
     fn test_config_creation() {
         let config = Config::new(PathBuf::from("/tmp/repo"))
@@ -0,0 +1,287 @@
+use std::collections::{BTreeMap, HashMap};
+use serde::{Serialize, Deserialize};
+use crate::analyzer::Change;
+use crate::config::MessageLintConfig;
+use crate::message::{self, ConventionalCommit};
+
+// Verbs whose third-person form ("adds", "fixes") is a common non-imperative subject
+const COMMON_VERBS: &[&str] = &[
+    "add", "allow", "bump", "change", "clean", "create", "delete", "drop", "fix", "handle",
+    "implement", "improve", "introduce", "make", "merge", "move", "refactor", "remove", "rename",
+    "replace", "revert", "set", "support", "update", "upgrade", "use",
+];
+
+// Imperative verbs that happen to end in "ed" or "ing"
+const IMPERATIVE_EXCEPTIONS: &[&str] = &["bring", "embed", "feed", "seed", "shed", "speed", "string", "need", "ping"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LintIssue {
+    pub rule: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LintResult {
+    pub issues: Vec<LintIssue>,
+    // Number of rules that applied to the message
+    pub checked: usize,
+}
+
+impl LintResult {
+    /// Share of applicable rules the message passed, from 0 to 100.
+    pub fn score(&self) -> f64 {
+        if self.checked == 0 {
+            return 100.0;
+        }
+        let failed = self.issues.len().min(self.checked);
+        100.0 * (self.checked - failed) as f64 / self.checked as f64
+    }
+
+    fn check(&mut self, rule: &str, passed: bool, message: impl FnOnce() -> String) {
+        self.checked += 1;
+        if !passed {
+            self.issues.push(LintIssue {
+                rule: rule.to_string(),
+                message: message(),
+            });
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MessageQuality {
+    pub score: f64,
+    pub commits: usize,
+    pub authors: Vec<QualityScore>,
+    pub periods: Vec<QualityScore>,
+    // How often each rule failed
+    pub rule_failures: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QualityScore {
+    pub key: String,
+    pub commits: usize,
+    pub score: f64,
+}
+
+pub struct MessageLinter<'a> {
+    config: &'a MessageLintConfig,
+}
+
+impl<'a> MessageLinter<'a> {
+    pub fn new(config: &'a MessageLintConfig) -> Self {
+        Self { config }
+    }
+
+    /// Lint a commit message; `lines_changed` enables the body check for large diffs.
+    pub fn lint(&self, message: &str, lines_changed: Option<usize>) -> LintResult {
+        let mut result = LintResult::default();
+        let subject = message.lines().next().unwrap_or_default().trim();
+
+        if subject.is_empty() {
+            result.check("subject-empty", false, || "Subject line is empty".to_string());
+            return result;
+        }
+
+        let max = self.config.max_subject_length;
+        let length = subject.chars().count();
+        result.check("subject-length", length <= max, || {
+            format!("Subject is {} characters, more than {}", length, max)
+        });
+
+        let conventional = ConventionalCommit::parse(message);
+
+        if self.config.require_conventional {
+            result.check("conventional-commit", conventional.is_some(), || {
+                "Subject does not follow `type(scope): description`".to_string()
+            });
+        }
+
+        if let Some(commit) = &conventional {
+            if !self.config.conventional_types.is_empty() {
+                result.check(
+                    "conventional-type",
+                    self.config.conventional_types.contains(&commit.kind),
+                    || format!("Unknown commit type '{}'", commit.kind),
+                );
+            }
+        }
+
+        if self.config.imperative_mood {
+            let description = conventional.as_ref().map_or(subject, |c| c.description.as_str());
+            if let Some(word) = description.split_whitespace().next() {
+                result.check("imperative-mood", is_imperative(word), || {
+                    format!("Use the imperative mood, e.g. \"Add\" rather than \"{}\"", word)
+                });
+            }
+        }
+
+        if let (Some(threshold), Some(lines)) = (self.config.body_required_lines, lines_changed) {
+            if lines >= threshold {
+                let has_body = message.lines().skip(1).any(|line| !line.trim().is_empty());
+                result.check("body-required", has_body, || {
+                    format!("{} lines changed; explain the change in a message body", lines)
+                });
+            }
+        }
+
+        if self.config.require_issue_reference {
+            result.check("issue-reference", !message::issue_references(message).is_empty(), || {
+                "No issue reference such as #123 or PROJ-45".to_string()
+            });
+        }
+
+        result
+    }
+}
+
+fn is_imperative(word: &str) -> bool {
+    let word = word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+    if word.len() < 4 || IMPERATIVE_EXCEPTIONS.contains(&word.as_str()) {
+        return true;
+    }
+
+    if word.ends_with("ed") || word.ends_with("ing") {
+        return false;
+    }
+
+    // "adds", "fixes", "updates"
+    let stem = word.strip_suffix("es").filter(|s| COMMON_VERBS.contains(s))
+        .or_else(|| word.strip_suffix('s').filter(|s| COMMON_VERBS.contains(s)));
+    stem.is_none()
+}
+
+/// Strip the comments and scissors section git adds to a message being edited.
+pub fn clean_message(raw: &str) -> String {
+    raw.lines()
+        .take_while(|line| !line.starts_with("# ------------------------ >8 ------------------------"))
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Score every commit's message and aggregate per author and per period.
+pub fn assess(changes: &[Change], config: &MessageLintConfig) -> MessageQuality {
+    // Lint each commit once, with the size of the whole commit
+    let mut commits: BTreeMap<&str, (&Change, usize)> = BTreeMap::new();
+    for change in changes {
+        let (added, removed) = change.line_counts();
+        commits.entry(change.commit_id.as_str()).or_insert((change, 0)).1 += added + removed;
+    }
+
+    let linter = MessageLinter::new(config);
+    let mut quality = MessageQuality::default();
+    let mut authors: HashMap<&str, (usize, f64)> = HashMap::new();
+    let mut periods: BTreeMap<String, (usize, f64)> = BTreeMap::new();
+    let mut total = 0.0;
+
+    for (change, lines) in commits.values() {
+        let result = linter.lint(&change.message, Some(*lines));
+        let score = result.score();
+        total += score;
+
+        for issue in result.issues {
+            *quality.rule_failures.entry(issue.rule).or_insert(0) += 1;
+        }
+
+        let author = authors.entry(change.author.as_str()).or_default();
+        author.0 += 1;
+        author.1 += score;
+
+        let period = periods.entry(config.period.key(&change.timestamp)).or_default();
+        period.0 += 1;
+        period.1 += score;
+    }
+
+    let average = |(commits, total): (usize, f64)| total / commits as f64;
+
+    quality.commits = commits.len();
+    quality.score = if commits.is_empty() { 0.0 } else { total / commits.len() as f64 };
+    quality.authors = authors.into_iter()
+        .map(|(name, stats)| QualityScore {
+            key: name.to_string(),
+            commits: stats.0,
+            score: average(stats),
+        })
+        .collect();
+    quality.authors.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.key.cmp(&b.key)));
+    quality.periods = periods.into_iter()
+        .map(|(key, stats)| QualityScore {
+            key,
+            commits: stats.0,
+            score: average(stats),
+        })
+        .collect();
+
+    quality
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn lint(config: &MessageLintConfig, message: &str, lines: Option<usize>) -> Vec<String> {
+        MessageLinter::new(config).lint(message, lines).issues.into_iter().map(|i| i.rule).collect()
+    }
+
+    #[test]
+    fn test_lint_rules() {
+        let mut config = MessageLintConfig::default();
+
+        assert!(lint(&config, "Add parser for config files", Some(10)).is_empty());
+        assert_eq!(lint(&config, "Added parser", None), vec!["imperative-mood"]);
+        assert_eq!(lint(&config, "fix: fixes crash on empty input", None), vec!["imperative-mood"]);
+        assert!(lint(&config, "Bring back the old parser", None).is_empty());
+        assert_eq!(lint(&config, &"x".repeat(80), None), vec!["subject-length"]);
+        assert_eq!(lint(&config, "Rewrite parser", Some(500)), vec!["body-required"]);
+        assert!(lint(&config, "Rewrite parser\n\nThe old one was slow.", Some(500)).is_empty());
+        assert_eq!(lint(&config, "", None), vec!["subject-empty"]);
+
+        config.require_conventional = true;
+        config.require_issue_reference = true;
+        assert_eq!(lint(&config, "Add parser", None), vec!["conventional-commit", "issue-reference"]);
+        assert_eq!(lint(&config, "feature: add parser (#12)", None), vec!["conventional-type"]);
+        assert!(lint(&config, "feat(cli): add parser (#12)", None).is_empty());
+
+        let result = MessageLinter::new(&config).lint("Added parser", None);
+        assert_eq!(result.checked, 4);
+        assert_eq!(result.score(), 25.0);
+    }
+
+    #[test]
+    fn test_clean_message() {
+        let raw = "Add parser\n\nBody text\n# Please enter the commit message\n# ------------------------ >8 ------------------------\ndiff --git a/x b/x\n";
+        assert_eq!(clean_message(raw), "Add parser\n\nBody text");
+    }
+
+    #[test]
+    fn test_assess() {
+        let change = |commit_id: &str, author: &str, message: &str, month: u32| Change {
+            commit_id: commit_id.to_string(),
+            author: author.to_string(),
+            message: message.to_string(),
+            timestamp: Utc.with_ymd_and_hms(2024, month, 1, 0, 0, 0).unwrap(),
+            diff: "+a\n".to_string(),
+            ..Default::default()
+        };
+        let changes = vec![
+            change("a", "Ann", "Add parser", 1),
+            change("a", "Ann", "Add parser", 1),
+            change("b", "Ann", "Fixed bug", 1),
+            change("c", "Bob", "Update docs", 2),
+        ];
+
+        let quality = assess(&changes, &MessageLintConfig::default());
+        assert_eq!(quality.commits, 3);
+        assert_eq!(quality.authors[0].key, "Ann");
+        assert_eq!(quality.authors[0].commits, 2);
+        assert_eq!(quality.authors[0].score, 75.0);
+        assert_eq!(quality.periods.len(), 2);
+        assert_eq!(quality.periods[1].score, 100.0);
+        assert_eq!(quality.rule_failures["imperative-mood"], 1);
+    }
+}
@@ -1,9 +1,21 @@
 use clap::{Parser, Subcommand};
 use code_historian::{
     Analysis, Config, HistorianError, ReportGenerator, Result,
-    analyzer::Analyzer,
+    analyzer::{Analyzer, UncommittedScope},
+    changelog::{self, ChangelogGenerator, ChangelogGrouping},
+    feedback::{self, Correction, FeedbackStore},
     config::load_config,
+    hooks::{self, HookKind, HookManager, HookState},
+    lint::{self, MessageLinter},
+    ml::{self, ChangeClassifier},
     plugin::PluginManager,
+    precommit,
+    query::Query,
+    search::SearchHit,
+    server::{self, ApiState},
+    signatures::{self, SignatureVerifier},
+    symbols::{HistoryTarget, SymbolHistory, SymbolTracker},
+    workspace::{self, Workspace, WorkspaceAnalysis},
 };
 use std::path::{Path, PathBuf};
 use tracing::{info, warn};
@@ -22,6 +34,10 @@ enum Commands {
         /// Path to initialize (defaults to current directory)
         #[arg(short, long)]
         path: Option<PathBuf>,
+
+        /// Also install post-commit, post-merge and pre-push hooks
+        #[arg(long)]
+        hooks: bool,
     },
 
     /// Analyze repository and generate reports
@@ -37,6 +53,132 @@ enum Commands {
         /// Output directory for reports
         #[arg(short, long)]
         output: Option<PathBuf>,
+
+        /// Workspace file listing several repositories to analyze together
+        #[arg(short, long, conflicts_with = "path")]
+        workspace: Option<PathBuf>,
+
+        /// Analyze staged changes and fail when pre-commit thresholds are exceeded
+        #[arg(long, conflicts_with_all = ["workspace", "worktree"])]
+        staged: bool,
+
+        /// Analyze all uncommitted changes, including untracked files
+        #[arg(long, conflicts_with = "workspace")]
+        worktree: bool,
+
+        /// Only include matching changes, e.g. "category:security path:src/auth since:90d impact>5"
+        #[arg(long)]
+        filter: Option<Query>,
+    },
+
+    /// Generate a changelog between two refs
+    Changelog {
+        /// Repository path (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// Start ref, exclusive (defaults to the beginning of history)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// End ref, inclusive
+        #[arg(long, default_value = "HEAD")]
+        to: String,
+
+        /// Group entries by Conventional Commit type or by category
+        #[arg(long)]
+        group_by: Option<ChangelogGrouping>,
+
+        /// Output format (markdown or json)
+        #[arg(short, long, default_value = "markdown")]
+        format: String,
+
+        /// Handlebars template overriding the built-in Markdown layout
+        #[arg(long)]
+        template: Option<PathBuf>,
+
+        /// Output file (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Search commit messages and diffs
+    Search {
+        /// Words or identifiers to look for
+        query: String,
+
+        /// Repository path (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// Maximum number of results
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+
+        /// Output format (text or json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Show the commits that touched a symbol or line range
+    History {
+        /// Symbol such as "analyzer::Analyzer::analyze", or a line range such as "src/lib.rs:10-40"
+        target: HistoryTarget,
+
+        /// Repository path (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// Revision to start from
+        #[arg(long, default_value = "HEAD")]
+        rev: String,
+
+        /// Show the diff of each revision
+        #[arg(long)]
+        patch: bool,
+
+        /// Output format (text or json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Train the change classifier from labeled commits
+    Train {
+        /// Repository path (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// JSON lines of {"commit": ..., "categories": [...]}; Conventional Commit types label the rest
+        #[arg(long)]
+        labels: Option<PathBuf>,
+    },
+
+    /// Correct the categories of a commit, or of one file in it
+    Correct {
+        /// Commit to correct
+        commit: String,
+
+        /// Categories it belongs to; none marks it uncategorized
+        categories: Vec<String>,
+
+        /// Only correct this file of the commit
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Repository path (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+    },
+
+    /// Serve the analysis over a local HTTP API
+    Serve {
+        /// Repository path (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:3000")]
+        addr: String,
     },
 
     /// Watch repository for changes
@@ -57,6 +199,18 @@ enum Commands {
         #[command(subcommand)]
         action: PluginCommands,
     },
+
+    /// Manage git hooks
+    Hooks {
+        #[command(subcommand)]
+        action: HookCommands,
+    },
+
+    /// Check commit signatures
+    Signatures {
+        #[command(subcommand)]
+        action: SignatureCommands,
+    },
 }
 
 #[derive(Subcommand)]
@@ -91,6 +245,54 @@ enum PluginCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum HookCommands {
+    /// Install hooks, chaining any existing ones
+    Install {
+        /// Repository path (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+        /// Hooks to install (defaults to post-commit, post-merge and pre-push)
+        hooks: Vec<HookKind>,
+    },
+    /// Remove hooks and restore chained ones
+    Uninstall {
+        /// Repository path (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+        /// Hooks to remove (defaults to all)
+        hooks: Vec<HookKind>,
+    },
+    /// Show which hooks are installed
+    Status {
+        /// Repository path (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+    },
+    /// Run a hook's action; called by the installed hook scripts
+    Run {
+        hook: HookKind,
+        /// Arguments git passed to the hook
+        args: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SignatureCommands {
+    /// Fail when commits in a range are not signed
+    Check {
+        /// Repository path (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+        /// Commit range such as `origin/main..HEAD`, or a single rev
+        #[arg(default_value = "HEAD")]
+        range: String,
+        /// Also fail on signatures that do not verify
+        #[arg(long)]
+        require_verified: bool,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize logging
@@ -106,17 +308,57 @@ async fn main() -> Result<()> {
     let plugin_manager = PluginManager::new(&config)?;
 
     match cli.command {
-        Commands::Init { path } => {
+        Commands::Init { path, hooks } => {
             let path = path.unwrap_or_else(|| PathBuf::from("."));
             initialize_project(&path)?;
+
+            if hooks {
+                let manager = HookManager::new(&git2::Repository::open(&path)?)?;
+                manager.install(&HookKind::DEFAULT)?;
+                info!("Installed git hooks in {}", manager.hooks_dir().display());
+            }
+        }
+
+        Commands::Analyze { path, staged, worktree, .. } if staged || worktree => {
+            let path = path.unwrap_or_else(|| PathBuf::from("."));
+            let scope = if staged { UncommittedScope::Staged } else { UncommittedScope::Worktree };
+
+            let mut analyzer = Analyzer::new(&config, &plugin_manager)?;
+            let analysis = analyzer.analyze_uncommitted(&path, scope)?;
+
+            for change in &analysis.changes {
+                println!("{:>5.1}  {}  {:?}", change.impact_score, change.file_path.display(), change.categories);
+            }
+
+            exit_on_violations(&precommit::check(&analysis, &config.precommit));
         }
 
-        Commands::Analyze { path, format, output } => {
+        Commands::Analyze { format, output, workspace: Some(workspace_file), filter, .. } => {
+            let workspace_dir = workspace_file.parent().unwrap_or_else(|| Path::new(".")).to_path_buf();
+            let output = output.unwrap_or_else(|| workspace_dir.join(".code-historian/reports"));
+            let workspace = Workspace::load(&workspace_file)?;
+
+            let analysis = workspace::analyze_workspace(&workspace, |repo| {
+                let mut analyzer = Analyzer::new(&config, &plugin_manager)?;
+                if let Some(query) = &filter {
+                    analyzer = analyzer.with_query(query.clone());
+                }
+                analyzer.analyze(&repo.path)
+            })?;
+            info!("Analyzed {} repositories", analysis.repositories.len());
+
+            generate_workspace_reports(&analysis, &format, &output)?;
+        }
+
+        Commands::Analyze { path, format, output, filter, .. } => {
             let path = path.unwrap_or_else(|| PathBuf::from("."));
             let output = output.unwrap_or_else(|| path.join(".code-historian/reports"));
 
             // Create analyzer
-            let analyzer = Analyzer::new(&config, &plugin_manager)?;
+            let mut analyzer = Analyzer::new(&config, &plugin_manager)?;
+            if let Some(query) = filter {
+                analyzer = analyzer.with_query(query);
+            }
 
             // Perform analysis
             info!("Analyzing repository at {}", path.display());
@@ -126,6 +368,170 @@ async fn main() -> Result<()> {
             generate_reports(&analysis, &format, &output)?;
         }
 
+        Commands::Changelog { path, from, to, group_by, format, template, output } => {
+            let path = path.unwrap_or_else(|| PathBuf::from("."));
+            let analyzer = Analyzer::new(&config, &plugin_manager)?;
+            let analysis = analyzer.analyze(&path)?;
+
+            let repo = git2::Repository::open(&path)?;
+            let generator = ChangelogGenerator::new(&config.changelog)
+                .with_grouping(group_by.unwrap_or(config.changelog.group_by));
+            let changelog = generator.build(&repo, from.as_deref(), &to, &analysis.changes)?;
+
+            let content = match format.to_lowercase().as_str() {
+                "markdown" => {
+                    let template = template.or_else(|| config.changelog.template.clone())
+                        .map(std::fs::read_to_string)
+                        .transpose()?;
+                    changelog::render_markdown(&changelog, template.as_deref())?
+                }
+                "json" => changelog::render_json(&changelog)?,
+                _ => {
+                    return Err(HistorianError::InvalidArgument(format!(
+                        "Unsupported changelog format: {}",
+                        format
+                    )));
+                }
+            };
+
+            match output {
+                Some(output) => {
+                    std::fs::write(&output, content)?;
+                    info!("Generated changelog at {}", output.display());
+                }
+                None => print!("{}", content),
+            }
+        }
+
+        Commands::Hooks { action } => {
+            match action {
+                HookCommands::Install { path, hooks } => {
+                    let manager = hook_manager(path)?;
+                    manager.install(if hooks.is_empty() { &HookKind::DEFAULT } else { &hooks })?;
+                    info!("Installed git hooks in {}", manager.hooks_dir().display());
+                }
+                HookCommands::Uninstall { path, hooks } => {
+                    let manager = hook_manager(path)?;
+                    manager.uninstall(if hooks.is_empty() { &HookKind::ALL } else { &hooks })?;
+                    info!("Removed git hooks from {}", manager.hooks_dir().display());
+                }
+                HookCommands::Status { path } => {
+                    for status in hook_manager(path)?.status()? {
+                        let state = match status.state {
+                            HookState::NotInstalled => "not installed",
+                            HookState::Installed { chained: true } => "installed (chains existing hook)",
+                            HookState::Installed { chained: false } => "installed",
+                            HookState::Foreign => "another hook is installed",
+                        };
+                        println!("{:<12} {}", status.kind.file_name(), state);
+                    }
+                }
+                HookCommands::Run { hook, args } => {
+                    run_hook(hook, &args, &config, &plugin_manager)?;
+                }
+            }
+        }
+
+        Commands::Signatures { action } => {
+            match action {
+                SignatureCommands::Check { path, range, require_verified } => {
+                    let path = path.unwrap_or_else(|| PathBuf::from("."));
+                    let repo = git2::Repository::open(&path)?;
+                    let verifier = SignatureVerifier::new(&config.signatures, repo.workdir().unwrap_or(&path));
+
+                    let commits = signatures::commits_in_range(&repo, &range)?;
+                    let require_verified = require_verified || config.signatures.require_verified;
+                    exit_on_violations(&signatures::check_policy(&repo, &verifier, &commits, require_verified)?);
+                    info!("All {} commits in {} are signed", commits.len(), range);
+                }
+            }
+        }
+
+        Commands::Search { query, path, limit, format } => {
+            let path = path.unwrap_or_else(|| PathBuf::from("."));
+            let analyzer = Analyzer::new(&config, &plugin_manager)?;
+            let hits = analyzer.search_index(&path)?.search(&query, limit);
+
+            match format.to_lowercase().as_str() {
+                "text" => print_search_hits(&hits),
+                "json" => println!("{}", serde_json::to_string_pretty(&hits)?),
+                _ => {
+                    return Err(HistorianError::InvalidArgument(format!(
+                        "Unsupported search format: {}",
+                        format
+                    )));
+                }
+            }
+        }
+
+        Commands::History { target, path, rev, patch, format } => {
+            let path = path.unwrap_or_else(|| PathBuf::from("."));
+            let classifier = ChangeClassifier::from_config(&config, &path)?;
+            let repo = git2::Repository::open(&path)?;
+            let history = SymbolTracker::new(&repo, &classifier)?
+                .with_corrections(FeedbackStore::new(&config.history_dir(&path)).load()?)
+                .history(&target, &rev)?;
+
+            match format.to_lowercase().as_str() {
+                "text" => print_symbol_history(&history, patch),
+                "json" => println!("{}", serde_json::to_string_pretty(&history)?),
+                _ => {
+                    return Err(HistorianError::InvalidArgument(format!(
+                        "Unsupported history format: {}",
+                        format
+                    )));
+                }
+            }
+        }
+
+        Commands::Train { path, labels } => {
+            let path = path.unwrap_or_else(|| PathBuf::from("."));
+            let labels = labels.map(|labels| ml::load_labels(&labels)).transpose()?.unwrap_or_default();
+            let mut analyzer = Analyzer::new(&config, &plugin_manager)?;
+            let analysis = analyzer.analyze(&path)?;
+
+            match analyzer.train_classifier(&path, &analysis.changes, &labels)? {
+                Some(model) => {
+                    info!("Trained the classifier on {} changes", model.documents());
+                    for (category, count) in model.category_counts() {
+                        println!("{:<16} {}", format!("{:?}", category), count);
+                    }
+                }
+                None => warn!("No labeled changes found; use Conventional Commit types or pass --labels"),
+            }
+        }
+
+        Commands::Correct { commit, categories, file, path } => {
+            let path = path.unwrap_or_else(|| PathBuf::from("."));
+            let repo = git2::Repository::open(&path)?;
+            let commit = feedback::resolve_commit(&repo, &commit)?;
+            let categories = categories.iter()
+                .map(|c| c.parse())
+                .collect::<Result<Vec<_>>>()?;
+            let author = repo.signature().ok().and_then(|s| s.name().map(str::to_string));
+
+            let correction = Correction::new(commit, categories)
+                .with_file_path(file)
+                .with_author(author);
+            let store = FeedbackStore::new(&config.history_dir(&path));
+            store.record(&correction)?;
+            info!("Recorded the correction in {}", store.path().display());
+        }
+
+        Commands::Serve { path, addr } => {
+            let path = path.unwrap_or_else(|| PathBuf::from("."));
+            let analyzer = Analyzer::new(&config, &plugin_manager)?;
+            let analysis = analyzer.analyze(&path)?;
+            let index = analyzer.search_index(&path)?;
+            let classifier = ChangeClassifier::from_config(&config, &path)?;
+            let state = ApiState::new(analysis)
+                .with_search(index)
+                .with_repository(path)
+                .with_config(config.clone())
+                .with_classifier(classifier);
+            server::serve(&addr, state).await?;
+        }
+
         Commands::Watch { path } => {
             let path = path.unwrap_or_else(|| PathBuf::from("."));
             watch_repository(&path, &config, &plugin_manager).await?;
@@ -169,6 +575,123 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+fn exit_on_violations(violations: &[precommit::Violation]) {
+    if violations.is_empty() {
+        return;
+    }
+
+    for violation in violations {
+        match &violation.file_path {
+            Some(file) => eprintln!("error: {}: {}", file.display(), violation.message),
+            None => eprintln!("error: {}", violation.message),
+        }
+    }
+    std::process::exit(1);
+}
+
+fn print_search_hits(hits: &[SearchHit]) {
+    if hits.is_empty() {
+        println!("No matches");
+        return;
+    }
+
+    for hit in hits {
+        let short_id = &hit.commit_id[..hit.commit_id.len().min(8)];
+        println!("{} {} {}", short_id, hit.timestamp.format("%Y-%m-%d"), hit.summary);
+        println!("    {} ({})", hit.file_path.display(), hit.author);
+        println!("    {}", hit.snippet);
+    }
+}
+
+fn print_symbol_history(history: &SymbolHistory, patch: bool) {
+    for revision in &history.revisions {
+        let short_id = &revision.commit_id[..revision.commit_id.len().min(8)];
+        let categories: Vec<String> = revision.categories.iter().map(|c| format!("{:?}", c)).collect();
+        println!(
+            "{} {} {:<8} {}",
+            short_id,
+            revision.timestamp.format("%Y-%m-%d"),
+            format!("{:?}", revision.kind).to_lowercase(),
+            revision.summary
+        );
+        match &revision.previous_path {
+            Some(previous) => println!(
+                "    {} -> {}:{}-{}",
+                previous.display(),
+                revision.file_path.display(),
+                revision.start_line,
+                revision.end_line
+            ),
+            None => println!("    {}:{}-{}", revision.file_path.display(), revision.start_line, revision.end_line),
+        }
+        if !categories.is_empty() {
+            println!("    {}", categories.join(", "));
+        }
+        if patch {
+            println!("\n{}", revision.diff);
+        }
+    }
+}
+
+fn hook_manager(path: Option<PathBuf>) -> Result<HookManager> {
+    let path = path.unwrap_or_else(|| PathBuf::from("."));
+    HookManager::new(&git2::Repository::open(&path)?)
+}
+
+// Hooks run from the repository's working tree
+fn run_hook(hook: HookKind, args: &[String], config: &Config, plugin_manager: &PluginManager) -> Result<()> {
+    let path = PathBuf::from(".");
+    let analyzer = Analyzer::new(config, plugin_manager)?;
+
+    match hook {
+        // Refresh the cached analysis and reports after history changes
+        HookKind::PostCommit | HookKind::PostMerge => {
+            let analysis = analyzer.analyze(&path)?;
+            generate_reports(&analysis, &config.core.output_format, &config.core.output_dir)?;
+        }
+        // Hold pushed commits to the same thresholds as pre-commit checks
+        HookKind::PrePush => {
+            let mut input = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut input)?;
+            let repo = git2::Repository::open(&path)?;
+            let pushed = hooks::pushed_commits(&repo, &hooks::parse_push_refs(&input))?;
+
+            let mut analysis = analyzer.analyze(&path)?;
+            analyzer.retain_changes(&mut analysis, |c| pushed.contains(&c.commit_id));
+            let mut violations = precommit::check(&analysis, &config.precommit);
+
+            if config.signatures.require_signed {
+                let verifier = SignatureVerifier::new(&config.signatures, repo.workdir().unwrap_or(&path));
+                let commits = pushed.iter().map(|id| git2::Oid::from_str(id)).collect::<std::result::Result<Vec<_>, _>>()?;
+                violations.extend(signatures::check_policy(&repo, &verifier, &commits, config.signatures.require_verified)?);
+            }
+
+            exit_on_violations(&violations);
+        }
+        // Lint the message being committed against the staged diff
+        HookKind::CommitMsg => {
+            let message_file = args.first()
+                .ok_or_else(|| HistorianError::InvalidArgument("commit-msg needs the message file".to_string()))?;
+            let message = lint::clean_message(&std::fs::read_to_string(message_file)?);
+
+            let repo = git2::Repository::open(&path)?;
+            let head = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+            let stats = repo.diff_tree_to_index(head.as_ref(), None, None)?.stats()?;
+
+            let result = MessageLinter::new(&config.message_lint)
+                .lint(&message, Some(stats.insertions() + stats.deletions()));
+            if !result.issues.is_empty() {
+                for issue in &result.issues {
+                    eprintln!("error: [{}] {}", issue.rule, issue.message);
+                }
+                std::process::exit(1);
+            }
+        }
+    }
+
+    Ok(())
+}
+
 fn initialize_project(path: &Path) -> Result<()> {
     info!("Initializing Code Historian project at {}", path.display());
 
@@ -214,6 +737,11 @@ fn generate_reports(analysis: &Analysis, format: &str, output_dir: &Path) -> Res
             let generator = ReportGenerator::new()?;
             generator.generate_html(analysis, &report_path)?;
             info!("Generated HTML report at {}", report_path.display());
+
+            let component_reports = generator.generate_component_reports(analysis, &output_dir.join("components"))?;
+            if !component_reports.is_empty() {
+                info!("Generated {} component reports", component_reports.len());
+            }
         }
         _ => {
             return Err(HistorianError::InvalidArgument(format!(
@@ -226,6 +754,31 @@ fn generate_reports(analysis: &Analysis, format: &str, output_dir: &Path) -> Res
     Ok(())
 }
 
+fn generate_workspace_reports(analysis: &WorkspaceAnalysis, format: &str, output_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(output_dir)?;
+
+    match format.to_lowercase().as_str() {
+        "html" => {
+            let generator = ReportGenerator::new()?;
+            let reports = generator.generate_workspace_reports(analysis, output_dir)?;
+            info!("Generated workspace report with {} repository reports", reports.len() - 1);
+        }
+        "json" => {
+            let report_path = output_dir.join("workspace.json");
+            let json = serde_json::to_string_pretty(analysis)
+                .map_err(|e| HistorianError::Analysis(format!("Failed to serialize workspace: {}", e)))?;
+            std::fs::write(&report_path, json)?;
+            info!("Generated JSON report at {}", report_path.display());
+        }
+        _ => {
+            // Other formats fall back to the combined analysis
+            generate_reports(&analysis.combined(), format, output_dir)?;
+        }
+    }
+
+    Ok(())
+}
+
 async fn watch_repository(path: &Path, config: &Config, plugin_manager: &PluginManager) -> Result<()> {
     use notify::{Config as NotifyConfig, RecommendedWatcher, RecursiveMode, Watcher};
     use std::sync::mpsc::channel;
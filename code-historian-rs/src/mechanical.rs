@@ -0,0 +1,338 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use git2::Repository;
+use quote::ToTokens;
+use serde::{Serialize, Deserialize};
+use crate::Result;
+use crate::analyzer::Change;
+use crate::diff::{self, DiffLine};
+use crate::lexer::{Lexer, TokenContext};
+use crate::rules;
+
+const DEFAULT_IGNORE_REVS_FILE: &str = ".git-blame-ignore-revs";
+
+const PRETTIER_EXTENSIONS: &[&str] = &[
+    "js", "jsx", "mjs", "cjs", "ts", "tsx", "json", "css", "scss", "less", "html", "vue",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MechanicalKind {
+    LineEndings,
+    Whitespace,
+    Formatting,
+    Move,
+    IgnoredRevision,
+}
+
+/// Compare the old and new contents of a file for changes that leave the code as it was.
+pub fn classify(path: &Path, old: &str, new: &str) -> Option<MechanicalKind> {
+    if old == new {
+        return None;
+    }
+
+    if old.replace("\r\n", "\n") == new.replace("\r\n", "\n") {
+        return Some(MechanicalKind::LineEndings);
+    }
+
+    if strip_whitespace(path, old) == strip_whitespace(path, new) {
+        return Some(MechanicalKind::Whitespace);
+    }
+
+    if is_formatting_only(path, old, new) {
+        return Some(MechanicalKind::Formatting);
+    }
+
+    // Lines reordered within a file are left to `detect_moves`, which can tell hunks apart
+    None
+}
+
+// Whitespace outside string literals, keeping the indentation of languages where it is syntax
+fn strip_whitespace(path: &Path, content: &str) -> String {
+    let indented = is_indentation_sensitive(path);
+    let mut lexer = Lexer::for_path(Some(path));
+    let mut stripped = String::with_capacity(content.len());
+    for line in content.lines() {
+        let tokens = lexer.line(line);
+        if indented && !line.trim().is_empty() {
+            stripped.push('\n');
+            stripped.push_str(&line[..line.len() - line.trim_start().len()]);
+        }
+        for token in tokens {
+            let piece = &line[token.range];
+            if token.context == TokenContext::String {
+                stripped.push_str(piece);
+            } else {
+                stripped.extend(piece.chars().filter(|c| !c.is_whitespace()));
+            }
+        }
+    }
+    stripped
+}
+
+fn is_indentation_sensitive(path: &Path) -> bool {
+    let makefile = path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n == "Makefile" || n == "GNUmakefile" || n.ends_with(".mk"));
+    makefile || matches!(rules::language(path), Some("python" | "yaml"))
+}
+
+fn is_formatting_only(path: &Path, old: &str, new: &str) -> bool {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+
+    match extension {
+        // rustfmt output must parse to the same syntax tree
+        "rs" => {
+            let (Ok(old_file), Ok(new_file)) = (syn::parse_file(old), syn::parse_file(new)) else {
+                return false;
+            };
+            let normalize = |file: syn::File| drop_trailing_commas(&strip_whitespace(path, &file.into_token_stream().to_string()));
+            // The token stream drops plain comments, so those are compared separately
+            comments(path, old) == comments(path, new) && normalize(old_file) == normalize(new_file)
+        }
+        ext if PRETTIER_EXTENSIONS.contains(&ext) => {
+            // Quote style and semicolons are rewritten by prettier as well, but never inside strings
+            let normalize = |content: &str| -> Vec<String> {
+                token_runs(path, content).into_iter()
+                    .map(|(context, text)| match context {
+                        TokenContext::Code => drop_trailing_commas(&text.replace(';', "")),
+                        TokenContext::String => double_quoted(&text),
+                        TokenContext::Comment => text,
+                    })
+                    .filter(|text| !text.is_empty())
+                    .collect()
+            };
+            normalize(old) == normalize(new)
+        }
+        _ => false,
+    }
+}
+
+// Comment text in order, including block comments over several lines
+fn comments(path: &Path, content: &str) -> Vec<String> {
+    token_runs(path, content).into_iter()
+        .filter(|(context, _)| *context == TokenContext::Comment)
+        .map(|(_, text)| text)
+        .collect()
+}
+
+// Code, strings and comments in order, with whitespace dropped outside strings; code runs on
+// across lines, so a trailing comma and the bracket on the next line end up together
+fn token_runs(path: &Path, content: &str) -> Vec<(TokenContext, String)> {
+    let mut lexer = Lexer::for_path(Some(path));
+    let mut runs: Vec<(TokenContext, String)> = Vec::new();
+    for line in content.lines() {
+        for token in lexer.line(line) {
+            let piece = &line[token.range];
+            let piece: String = match token.context {
+                TokenContext::String => piece.to_string(),
+                _ => piece.chars().filter(|c| !c.is_whitespace()).collect(),
+            };
+            match runs.last_mut() {
+                Some((TokenContext::Code, code)) if token.context == TokenContext::Code => code.push_str(&piece),
+                _ if piece.is_empty() => {}
+                _ => runs.push((token.context, piece)),
+            }
+        }
+    }
+    runs
+}
+
+// Prettier prefers double quotes unless the string holds one
+fn double_quoted(string: &str) -> String {
+    match string.strip_prefix('\'').and_then(|rest| rest.strip_suffix('\'')) {
+        Some(body) if !body.contains('"') => format!("\"{}\"", body.replace("\\'", "'")),
+        _ => string.to_string(),
+    }
+}
+
+// Formatters add or drop trailing commas freely; expects whitespace already stripped
+fn drop_trailing_commas(content: &str) -> String {
+    let mut normalized = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ',' && matches!(chars.peek(), Some(')' | ']' | '}')) {
+            continue;
+        }
+        normalized.push(c);
+    }
+    normalized
+}
+
+/// Tag commits whose added lines are exactly the lines they removed elsewhere.
+///
+/// A line only counts as moved when it left another file or another hunk; lines rewritten in
+/// place, such as two swapped statements, are real edits.
+pub fn detect_moves(changes: &mut [Change]) {
+    type LineCounts = HashMap<String, usize>;
+    // Per commit, the added and removed lines, and the lines both added and removed in one hunk
+    let mut by_commit: HashMap<String, (LineCounts, LineCounts, bool)> = HashMap::new();
+
+    for change in changes.iter().filter(|c| c.blob.is_none()) {
+        let (added, removed, in_place) = by_commit.entry(change.commit_id.clone()).or_default();
+        let mut hunks: HashMap<usize, (HashSet<&str>, HashSet<&str>)> = HashMap::new();
+        let mut hunk = 0;
+        for line in diff::lines(&change.diff) {
+            let (target, content, added_line) = match line {
+                DiffLine::Hunk { .. } => {
+                    hunk += 1;
+                    continue;
+                }
+                DiffLine::Added(content) => (&mut *added, content, true),
+                DiffLine::Removed(content) => (&mut *removed, content, false),
+                _ => continue,
+            };
+            let content = content.trim();
+            if !content.is_empty() {
+                *target.entry(content.to_string()).or_insert(0) += 1;
+                let (hunk_added, hunk_removed) = hunks.entry(hunk).or_default();
+                if added_line {
+                    hunk_added.insert(content);
+                } else {
+                    hunk_removed.insert(content);
+                }
+            }
+        }
+        *in_place |= hunks.values().any(|(a, r)| !a.is_disjoint(r));
+    }
+
+    let moved: HashSet<String> = by_commit.into_iter()
+        .filter(|(_, (added, removed, in_place))| !added.is_empty() && added == removed && !in_place)
+        .map(|(commit_id, _)| commit_id)
+        .collect();
+
+    for change in changes.iter_mut() {
+        if change.mechanical.is_none() && change.blob.is_none() && moved.contains(&change.commit_id) {
+            change.mechanical = Some(MechanicalKind::Move);
+        }
+    }
+}
+
+/// Load the revisions to ignore, in the `.git-blame-ignore-revs` format.
+///
+/// The explicit file wins, then `blame.ignoreRevsFile` from the git config,
+/// then `.git-blame-ignore-revs` at the repository root.
+pub fn load_ignore_revs(repo: &Repository, file: Option<&Path>) -> Result<HashSet<String>> {
+    let Some(workdir) = repo.workdir() else {
+        return Ok(HashSet::new());
+    };
+
+    let path = match file {
+        Some(file) => Some(workdir.join(file)),
+        None => repo.config()?
+            .get_path("blame.ignoreRevsFile")
+            .ok()
+            .map(|p| if p.is_absolute() { p } else { workdir.join(p) }),
+    };
+    let path = path.unwrap_or_else(|| PathBuf::from(workdir).join(DEFAULT_IGNORE_REVS_FILE));
+
+    if !path.exists() {
+        return Ok(HashSet::new());
+    }
+
+    let mut revs = HashSet::new();
+    for line in std::fs::read_to_string(path)?.lines() {
+        let rev = line.split('#').next().unwrap_or("").trim();
+        if rev.is_empty() {
+            continue;
+        }
+        // Abbreviated hashes are resolved to full commit ids
+        match repo.revparse_single(rev) {
+            Ok(object) => {
+                revs.insert(object.id().to_string());
+            }
+            Err(_) => tracing::warn!("Ignoring unknown revision in ignore-revs file: {}", rev),
+        }
+    }
+
+    Ok(revs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn change(commit_id: &str, diff: &str) -> Change {
+        Change {
+            commit_id: commit_id.to_string(),
+            diff: diff.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        let rs = Path::new("src/lib.rs");
+        assert_eq!(classify(rs, "fn a() {}\n", "fn a() {}\r\n"), Some(MechanicalKind::LineEndings));
+        assert_eq!(classify(rs, "fn a() {}\n", "fn a()  {\n}\n"), Some(MechanicalKind::Whitespace));
+        assert_eq!(
+            classify(rs, "fn a(x: u8, y: u8,) {}\n", "fn a(x: u8, y: u8) {}\n"),
+            Some(MechanicalKind::Formatting),
+        );
+        assert_eq!(classify(rs, "fn a() {}\n// old\n", "fn a() {}\n// new\n"), None);
+        assert_eq!(classify(rs, "fn a() {}\n/* old\n   note */\n", "fn a() {}\n/* new\n   note */\n"), None);
+        assert_eq!(classify(rs, "fn a(x: u8,) {}\n/* a\n   note */\n", "fn a(x: u8) {}\n/* a\nnote */\n"), Some(MechanicalKind::Formatting));
+        assert_eq!(classify(rs, "fn a() {}\nfn b() {}\n", "fn b() {}\nfn a() {}\n"), None);
+        assert_eq!(classify(rs, "fn a() {}\n", "fn a() { 1 }\n"), None);
+
+        // Whitespace inside strings and indentation that is syntax are not layout
+        assert_eq!(classify(rs, "let s = \"a b\";\n", "let s = \"ab\";\n"), None);
+        let py = Path::new("app.py");
+        assert_eq!(classify(py, "if x:\n    a()\nb()\n", "if x:\n    a()\n    b()\n"), None);
+        assert_eq!(classify(py, "a( 1 )\n", "a(1)\n"), Some(MechanicalKind::Whitespace));
+        assert_eq!(classify(Path::new("Makefile"), "all:\n\tcc\n", "all:\n    cc\n"), None);
+
+        let ts = Path::new("web/app.ts");
+        assert_eq!(
+            classify(ts, "const a = ['x', 'y',]\n", "const a = [\"x\", \"y\"];\n"),
+            Some(MechanicalKind::Formatting),
+        );
+        // Quotes and semicolons inside strings are content
+        assert_eq!(classify(ts, "const a = 'a;b';\n", "const a = \"ab\";\n"), None);
+        assert_eq!(classify(ts, "const a = \"it's\";\n", "const a = 'it\"s';\n"), None);
+    }
+
+    #[test]
+    fn test_detect_moves() {
+        let mut changes = vec![
+            change("a", "--- a/src/a.rs\n+++ b/src/a.rs\n-fn moved() {}\n"),
+            change("a", "--- a/src/b.rs\n+++ b/src/b.rs\n+fn moved() {}\n"),
+            change("b", "+fn new() {}\n"),
+            // Reordered within one hunk
+            change("c", "@@ -1,2 +1,2 @@\n-a();\n-b();\n+b();\n+a();\n"),
+            // Moved between hunks of one file
+            change("d", "@@ -1,2 +1,1 @@\n-a();\n b();\n@@ -9,1 +8,2 @@\n c();\n+a();\n"),
+        ];
+        detect_moves(&mut changes);
+
+        assert_eq!(changes[0].mechanical, Some(MechanicalKind::Move));
+        assert_eq!(changes[1].mechanical, Some(MechanicalKind::Move));
+        assert_eq!(changes[2].mechanical, None);
+        assert_eq!(changes[3].mechanical, None);
+        assert_eq!(changes[4].mechanical, Some(MechanicalKind::Move));
+    }
+
+    #[test]
+    fn test_load_ignore_revs() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+
+        std::fs::write(temp_dir.path().join("lib.rs"), "fn a() {}\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("lib.rs")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let oid = repo.commit(Some("HEAD"), &sig, &sig, "style: rustfmt", &tree, &[]).unwrap();
+
+        assert!(load_ignore_revs(&repo, None).unwrap().is_empty());
+
+        let short = &oid.to_string()[..10];
+        std::fs::write(
+            temp_dir.path().join(DEFAULT_IGNORE_REVS_FILE),
+            format!("# formatting\n{} # rustfmt\n", short),
+        ).unwrap();
+        let revs = load_ignore_revs(&repo, None).unwrap();
+        assert!(revs.contains(&oid.to_string()));
+    }
+}
@@ -0,0 +1,168 @@
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConventionalCommit {
+    pub kind: String,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub description: String,
+    pub body: Option<String>,
+    pub footers: Vec<(String, String)>,
+}
+
+impl ConventionalCommit {
+    /// Parse a commit message of the form `type(scope)!: description`.
+    pub fn parse(message: &str) -> Option<Self> {
+        let mut lines = message.lines();
+        let subject = lines.next()?.trim();
+
+        let (header, description) = subject.split_once(':')?;
+        let description = description.trim();
+        if description.is_empty() || !description.starts_with(|c: char| !c.is_whitespace()) {
+            return None;
+        }
+
+        let (header, bang) = match header.strip_suffix('!') {
+            Some(header) => (header, true),
+            None => (header, false),
+        };
+
+        let (kind, scope) = match header.split_once('(') {
+            Some((kind, rest)) => {
+                let scope = rest.strip_suffix(')')?;
+                if scope.is_empty() || scope.contains(['(', ')']) {
+                    return None;
+                }
+                (kind, Some(scope.to_string()))
+            }
+            None => (header, None),
+        };
+
+        if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return None;
+        }
+
+        // Body and footers are separated from the subject by a blank line
+        let rest: Vec<&str> = lines.collect();
+        let mut body_lines = Vec::new();
+        let mut footers = Vec::new();
+        for line in &rest {
+            if let Some(footer) = parse_footer(line) {
+                footers.push(footer);
+            } else if footers.is_empty() {
+                body_lines.push(*line);
+            }
+        }
+
+        let body = body_lines.join("\n").trim().to_string();
+        let breaking = bang || footers.iter().any(|(token, _)| {
+            token == "BREAKING CHANGE" || token == "BREAKING-CHANGE"
+        });
+
+        Some(Self {
+            kind: kind.to_lowercase(),
+            scope,
+            breaking,
+            description: description.to_string(),
+            body: if body.is_empty() { None } else { Some(body) },
+            footers,
+        })
+    }
+
+    pub fn is_fix(&self) -> bool {
+        self.kind == "fix"
+    }
+}
+
+fn parse_footer(line: &str) -> Option<(String, String)> {
+    for prefix in ["BREAKING CHANGE", "BREAKING-CHANGE"] {
+        if let Some(value) = line.strip_prefix(prefix).and_then(|rest| rest.strip_prefix(':')) {
+            return Some((prefix.to_string(), value.trim().to_string()));
+        }
+    }
+
+    let (token, value) = line.split_once(": ").or_else(|| line.split_once(" #"))?;
+    if token.is_empty() || !token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+    Some((token.to_string(), value.trim().to_string()))
+}
+
+// Standards and algorithms that look like `KEY-123` but are never issue keys
+const NON_ISSUE_KEYS: &[&str] = &[
+    "AES", "ANSI", "CRC", "ECMA", "GMT", "IEC", "IEEE", "ISO", "PEP", "RFC", "RSA", "SHA", "TLS", "UCS", "UTC", "UTF",
+];
+
+/// Extract issue references such as `#123`, `GH-45` or `PROJ-678` from a message.
+pub fn issue_references(message: &str) -> Vec<String> {
+    let mut issues = Vec::new();
+
+    for word in message.split(|c: char| c.is_whitespace() || [',', '(', ')', '[', ']'].contains(&c)) {
+        let word = word.trim_end_matches(['.', ':', ';']);
+
+        let is_issue = if let Some(number) = word.strip_prefix('#') {
+            !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+        } else if let Some((project, number)) = word.split_once('-') {
+            project.len() >= 2
+                && project.chars().all(|c| c.is_ascii_uppercase())
+                && !NON_ISSUE_KEYS.contains(&project)
+                && !number.is_empty()
+                && number.chars().all(|c| c.is_ascii_digit())
+        } else {
+            false
+        };
+
+        if is_issue && !issues.iter().any(|i| i == word) {
+            issues.push(word.to_string());
+        }
+    }
+
+    issues
+}
+
+/// Check whether a message carries one of the given labels, e.g. `[bug]` or `hotfix:`.
+pub fn has_label(message: &str, labels: &[String]) -> bool {
+    let message = message.to_lowercase();
+    message
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_')
+        .any(|word| labels.iter().any(|label| label.eq_ignore_ascii_case(word)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_conventional_commit() {
+        let commit = ConventionalCommit::parse("fix(parser)!: handle empty input\n\nDetails here.\n\nRefs: #12").unwrap();
+        assert_eq!(commit.kind, "fix");
+        assert_eq!(commit.scope.as_deref(), Some("parser"));
+        assert!(commit.breaking);
+        assert!(commit.is_fix());
+        assert_eq!(commit.body.as_deref(), Some("Details here."));
+        assert_eq!(commit.footers, vec![("Refs".to_string(), "#12".to_string())]);
+
+        let commit = ConventionalCommit::parse("feat: add thing\n\nBREAKING CHANGE: removes old thing").unwrap();
+        assert!(commit.breaking);
+
+        assert!(ConventionalCommit::parse("Update README").is_none());
+        assert!(ConventionalCommit::parse("fix:").is_none());
+        assert!(ConventionalCommit::parse("fix(: broken").is_none());
+    }
+
+    #[test]
+    fn test_issue_references() {
+        let issues = issue_references("Fix crash (#42), see PROJ-7 and #42.");
+        assert_eq!(issues, vec!["#42".to_string(), "PROJ-7".to_string()]);
+        assert!(issue_references("utf-8 and #abc").is_empty());
+        assert!(issue_references("Decode UTF-8, hash with SHA-256 and print ISO-8601 dates").is_empty());
+    }
+
+    #[test]
+    fn test_has_label() {
+        let labels = vec!["bug".to_string(), "hotfix".to_string()];
+        assert!(has_label("[Bug] wrong total", &labels));
+        assert!(has_label("hotfix: wrong total", &labels));
+        assert!(!has_label("debugging output", &labels));
+    }
+}
@@ -1,130 +1,476 @@
-use std::collections::HashMap;
-use crate::{Category, Result};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use crate::{Category, Config, HistorianError, Result};
+use crate::analyzer::Change;
+use crate::diff::{self, DiffLine};
+use crate::lexer::{self, Side, TokenContext};
+use crate::message::ConventionalCommit;
+use crate::rules::{self, Rule};
+use crate::search;
+
+pub const MODEL_FILE: &str = "classifier.json";
+
+// Bumped whenever features change, so stale models are retrained rather than misread
+const MODEL_VERSION: u32 = 1;
+
+// Laplace smoothing for unseen token and category pairs
+const SMOOTHING: f64 = 1.0;
+
+// Changed lines read per change when extracting features
+const MAX_FEATURE_LINES: usize = 400;
+
+// Chance that a line matching a rule of weight 1 is a false positive; each further line makes it less likely
+const RULE_MISS: f64 = 0.35;
+
+// Removing code says less about what a change is for than writing it
+const REMOVED_LINE_WEIGHT: f64 = 0.5;
+
+// Model tokens reported as evidence per category
+const EVIDENCE_TOKENS: usize = 3;
+
+/// Confidence from which a categorization counts as certain rather than probable.
+pub const CERTAIN_CONFIDENCE: f64 = 0.9;
+
+/// How a trained model is combined with the keyword rules.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClassifierMode {
+    Rules,
+    Model,
+    // The model adds categories it is confident about and vetoes rule matches it finds unlikely
+    #[default]
+    Blend,
+}
+
+/// A category for a change, how sure the classifier is of it and what led there.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryScore {
+    pub category: Category,
+    // Between 0 and 1
+    pub confidence: f64,
+    pub evidence: Vec<String>,
+}
+
+impl CategoryScore {
+    /// A category recognized structurally, such as from a test path or a manifest.
+    pub fn certain(category: Category, evidence: &str) -> Self {
+        Self {
+            category,
+            confidence: 1.0,
+            evidence: vec![evidence.to_string()],
+        }
+    }
+
+    pub fn is_certain(&self) -> bool {
+        self.confidence >= CERTAIN_CONFIDENCE
+    }
+}
 
 pub struct ChangeClassifier {
-    rules: HashMap<String, Category>,
+    rules: Vec<Rule>,
+    model: Option<NaiveBayesModel>,
+    mode: ClassifierMode,
+    min_confidence: f64,
+}
+
+impl Default for ChangeClassifier {
+    fn default() -> Self {
+        Self {
+            rules: rules::builtin_rules(),
+            model: None,
+            mode: ClassifierMode::default(),
+            min_confidence: 0.6,
+        }
+    }
 }
 
 impl ChangeClassifier {
-    pub fn new() -> Result<Self> {
-        let mut rules = HashMap::new();
-        
-        // Architecture patterns
-        rules.insert("class".to_string(), Category::Architecture);
-        rules.insert("struct".to_string(), Category::Architecture);
-        rules.insert("interface".to_string(), Category::Architecture);
-        rules.insert("trait".to_string(), Category::Architecture);
-        
-        // API patterns
-        rules.insert("fn ".to_string(), Category::Api);
-        rules.insert("pub ".to_string(), Category::Api);
-        rules.insert("def ".to_string(), Category::Api);
-        rules.insert("function".to_string(), Category::Api);
-        
-        // Logic patterns
-        rules.insert("if ".to_string(), Category::Logic);
-        rules.insert("match ".to_string(), Category::Logic);
-        rules.insert("while ".to_string(), Category::Logic);
-        rules.insert("for ".to_string(), Category::Logic);
-        
-        // Data patterns
-        rules.insert("type ".to_string(), Category::Data);
-        rules.insert("enum ".to_string(), Category::Data);
-        rules.insert("struct ".to_string(), Category::Data);
-        rules.insert("const ".to_string(), Category::Data);
-        
-        // Error handling patterns
-        rules.insert("try".to_string(), Category::ErrorHandling);
-        rules.insert("catch".to_string(), Category::ErrorHandling);
-        rules.insert("throw".to_string(), Category::ErrorHandling);
-        rules.insert("Result".to_string(), Category::ErrorHandling);
-        
-        // Logging patterns
-        rules.insert("log".to_string(), Category::Logging);
-        rules.insert("debug!".to_string(), Category::Logging);
-        rules.insert("info!".to_string(), Category::Logging);
-        rules.insert("warn!".to_string(), Category::Logging);
-        
-        // Documentation patterns
-        rules.insert("///".to_string(), Category::Documentation);
-        rules.insert("/**".to_string(), Category::Documentation);
-        rules.insert("#[doc".to_string(), Category::Documentation);
-        rules.insert("//!".to_string(), Category::Documentation);
-        
-        // Testing patterns
-        rules.insert("#[test]".to_string(), Category::Testing);
-        rules.insert("assert".to_string(), Category::Testing);
-        rules.insert("expect".to_string(), Category::Testing);
-        rules.insert("mock".to_string(), Category::Testing);
-        
-        // Performance patterns
-        rules.insert("cache".to_string(), Category::Performance);
-        rules.insert("optimize".to_string(), Category::Performance);
-        rules.insert("performance".to_string(), Category::Performance);
-        rules.insert("benchmark".to_string(), Category::Performance);
-        
-        // Security patterns
-        rules.insert("encrypt".to_string(), Category::Security);
-        rules.insert("decrypt".to_string(), Category::Security);
-        rules.insert("auth".to_string(), Category::Security);
-        rules.insert("password".to_string(), Category::Security);
-        
-        // Refactoring patterns
-        rules.insert("refactor".to_string(), Category::Refactoring);
-        rules.insert("rename".to_string(), Category::Refactoring);
-        rules.insert("move".to_string(), Category::Refactoring);
-        rules.insert("extract".to_string(), Category::Refactoring);
-        
-        // Dependencies patterns
-        rules.insert("use ".to_string(), Category::Dependencies);
-        rules.insert("import ".to_string(), Category::Dependencies);
-        rules.insert("require".to_string(), Category::Dependencies);
-        rules.insert("extern".to_string(), Category::Dependencies);
-        
-        // Configuration patterns
-        rules.insert("config".to_string(), Category::Configuration);
-        rules.insert("env".to_string(), Category::Configuration);
-        rules.insert("setting".to_string(), Category::Configuration);
-        rules.insert("flag".to_string(), Category::Configuration);
-        
-        // UI/UX patterns
-        rules.insert("style".to_string(), Category::UiUx);
-        rules.insert("css".to_string(), Category::UiUx);
-        rules.insert("html".to_string(), Category::UiUx);
-        rules.insert("layout".to_string(), Category::UiUx);
-        
-        // Accessibility patterns
-        rules.insert("aria-".to_string(), Category::Accessibility);
-        rules.insert("role=".to_string(), Category::Accessibility);
-        rules.insert("alt=".to_string(), Category::Accessibility);
-        rules.insert("a11y".to_string(), Category::Accessibility);
+    /// The configured rules and rules file, with the trained model from `model_dir` when ML is
+    /// enabled and one exists; both are found in the history directory of the repository at `repo_path`.
+    pub fn from_config(config: &Config, repo_path: &Path) -> Result<Self> {
+        let classifier = Self::new()?
+            .with_rules(rules::load(config, Some(repo_path))?)
+            .with_min_confidence(config.ml.min_confidence);
+        if !config.core.ml_enabled {
+            return Ok(classifier);
+        }
 
-        Ok(Self { rules })
+        Ok(match NaiveBayesModel::load(&config.model_dir(repo_path)?.join(MODEL_FILE))? {
+            Some(model) => classifier.with_model(model, config.ml.mode, config.ml.min_confidence),
+            None => classifier,
+        })
+    }
+
+    pub fn with_model(mut self, model: NaiveBayesModel, mode: ClassifierMode, min_confidence: f64) -> Self {
+        self.model = Some(model);
+        self.mode = mode;
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    pub fn with_rules(mut self, rules: Vec<Rule>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Categories scoring below `min_confidence` are left out.
+    pub fn with_min_confidence(mut self, min_confidence: f64) -> Self {
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    pub fn model(&self) -> Option<&NaiveBayesModel> {
+        self.model.as_ref()
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// The built-in keyword rules, without a model.
+    pub fn new() -> Result<Self> {
+        Ok(Self::default())
     }
 
     pub fn predict_categories(&self, diff: &str) -> Result<Vec<Category>> {
-        let mut categories = std::collections::HashSet::new();
-        
-        // Process each line of the diff
-        for line in diff.lines() {
-            // Skip diff markers
-            if line.starts_with("+++") || line.starts_with("---") || line.starts_with("@@") {
+        self.predict("", diff)
+    }
+
+    /// Categories for a change from its message and diff.
+    pub fn predict(&self, message: &str, diff: &str) -> Result<Vec<Category>> {
+        Ok(self.score(message, diff)?.into_iter().map(|s| s.category).collect())
+    }
+
+    /// Categories reaching `min_confidence`, most confident first, with the evidence for each.
+    ///
+    /// Without a model, or for categories the model saw no examples of, the rules decide. Blending
+    /// averages the two where both have a say.
+    pub fn score(&self, message: &str, diff: &str) -> Result<Vec<CategoryScore>> {
+        self.score_change(diff_path(diff).as_deref(), message, diff)
+    }
+
+    /// Like `score`, for a change to `path`, which decides the language and path scoped rules.
+    pub fn score_change(&self, path: Option<&Path>, message: &str, diff: &str) -> Result<Vec<CategoryScore>> {
+        let mut ruled = self.rule_scores(path, message, diff);
+        let features = match (&self.model, self.mode) {
+            (Some(_), ClassifierMode::Model | ClassifierMode::Blend) => Some(features(message, diff)),
+            _ => None,
+        };
+
+        let mut scores: Vec<CategoryScore> = Category::ALL.into_iter()
+            .filter_map(|category| {
+                let rule = ruled.remove(&category);
+                let modeled = self.model.as_ref()
+                    .zip(features.as_ref())
+                    .and_then(|(model, features)| model.probability(&category, features));
+                let (confidence, evidence) = match (rule, modeled) {
+                    (None, None) => return None,
+                    (Some(rule), None) => (rule.confidence, rule.evidence),
+                    (Some(rule), Some((p, tokens))) if self.mode == ClassifierMode::Blend => {
+                        ((rule.confidence + p) / 2.0, rule.evidence.into_iter().chain(tokens).collect())
+                    }
+                    (_, Some(modeled)) => modeled,
+                };
+                (confidence >= self.min_confidence).then_some(CategoryScore { category, confidence, evidence })
+            })
+            .collect();
+
+        scores.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        Ok(scores)
+    }
+
+    fn rule_scores(&self, path: Option<&Path>, message: &str, diff: &str) -> HashMap<Category, CategoryScore> {
+        let rules: Vec<&Rule> = self.rules.iter().filter(|r| r.applies_to(path)).collect();
+        // Per category, the heaviest rule matching each line summed over lines, and the rules matched
+        let mut matches: HashMap<Category, (f64, BTreeSet<String>)> = HashMap::new();
+        let mut record = |matching: Vec<&Rule>, scale: f64| {
+            let mut strongest: HashMap<&Category, f64> = HashMap::new();
+            for rule in matching {
+                let weight = strongest.entry(&rule.category).or_insert(0.0);
+                *weight = weight.max(rule.weight);
+                matches.entry(rule.category.clone()).or_default().1.insert(rule.evidence());
+            }
+            for (category, weight) in strongest {
+                if let Some((strength, _)) = matches.get_mut(category) {
+                    *strength += weight * scale;
+                }
+            }
+        };
+
+        if !message.trim().is_empty() {
+            record(rules.iter().copied().filter(|r| r.target.message() && r.is_match(message)).collect(), 1.0);
+        }
+        for line in lexer::lex_diff(path, diff) {
+            let scale = match line.side {
+                Side::Added => 1.0,
+                Side::Removed => REMOVED_LINE_WEIGHT,
+            };
+            // Most rules look at the same parts of a line, so each blanked text is built once
+            let mut texts: HashMap<&[TokenContext], String> = HashMap::new();
+            let matching = rules.iter()
+                .copied()
+                .filter(|rule| rule.target.diff())
+                .filter(|rule| {
+                    let text = texts.entry(rule.contexts()).or_insert_with(|| line.text(rule.contexts()));
+                    rule.is_match(text)
+                })
+                .collect();
+            record(matching, scale);
+        }
+
+        matches.into_iter()
+            .map(|(category, (strength, evidence))| {
+                let score = CategoryScore {
+                    category: category.clone(),
+                    confidence: 1.0 - RULE_MISS.powf(strength),
+                    evidence: evidence.into_iter().collect(),
+                };
+                (category, score)
+            })
+            .collect()
+    }
+}
+
+// The file a diff is for, from its headers
+fn diff_path(diff: &str) -> Option<PathBuf> {
+    let header = |prefix: &str| diff.lines().find_map(|line| line.strip_prefix(prefix).map(str::to_string));
+    header("+++ b/").or_else(|| header("--- a/")).map(PathBuf::from)
+}
+
+/// A change with the categories it is known to belong to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainingExample {
+    pub message: String,
+    pub diff: String,
+    pub categories: Vec<Category>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CategoryStats {
+    category: Category,
+    documents: usize,
+    // Distinct tokens summed over its documents
+    tokens: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TokenStats {
+    documents: u32,
+    // Index into the category stats, and documents of that category containing the token
+    by_category: Vec<(usize, u32)>,
+}
+
+/// Multinomial naive Bayes over message and diff tokens, one yes/no model per category.
+///
+/// Each token counts once per change, which holds up better than raw counts on long diffs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NaiveBayesModel {
+    version: u32,
+    documents: usize,
+    tokens: u64,
+    categories: Vec<CategoryStats>,
+    vocabulary: HashMap<String, TokenStats>,
+}
+
+impl NaiveBayesModel {
+    pub fn train(examples: &[TrainingExample]) -> Self {
+        let mut model = Self {
+            version: MODEL_VERSION,
+            documents: 0,
+            tokens: 0,
+            categories: Category::ALL.into_iter()
+                .map(|category| CategoryStats { category, documents: 0, tokens: 0 })
+                .collect(),
+            vocabulary: HashMap::new(),
+        };
+
+        for example in examples.iter().filter(|e| !e.categories.is_empty()) {
+            let features = features(&example.message, &example.diff);
+            let indices: HashSet<usize> = example.categories.iter()
+                .filter_map(|c| model.categories.iter().position(|s| s.category == *c))
+                .collect();
+
+            model.documents += 1;
+            model.tokens += features.len() as u64;
+            for &index in &indices {
+                model.categories[index].documents += 1;
+                model.categories[index].tokens += features.len() as u64;
+            }
+            for feature in features {
+                let stats = model.vocabulary.entry(feature).or_default();
+                stats.documents += 1;
+                for &index in &indices {
+                    match stats.by_category.iter_mut().find(|(i, _)| *i == index) {
+                        Some((_, count)) => *count += 1,
+                        None => stats.by_category.push((index, 1)),
+                    }
+                }
+            }
+        }
+
+        model
+    }
+
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let model: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        Ok(Some(model).filter(|m| m.version == MODEL_VERSION))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn documents(&self) -> usize {
+        self.documents
+    }
+
+    /// Training examples per category, for categories that had any.
+    pub fn category_counts(&self) -> Vec<(Category, usize)> {
+        self.categories.iter()
+            .filter(|s| s.documents > 0)
+            .map(|s| (s.category.clone(), s.documents))
+            .collect()
+    }
+
+    // Probability that a change with these features belongs to `category`, with the tokens that
+    // argued for it most; None when training gave no examples on one side to compare against
+    fn probability(&self, category: &Category, features: &HashSet<String>) -> Option<(f64, Vec<String>)> {
+        let index = self.categories.iter().position(|s| s.category == *category)?;
+        let stats = &self.categories[index];
+        let (positive_docs, negative_docs) = (stats.documents, self.documents - stats.documents);
+        if positive_docs == 0 || negative_docs == 0 {
+            return None;
+        }
+
+        let vocabulary = self.vocabulary.len() as f64;
+        let positive_total = stats.tokens as f64 + SMOOTHING * vocabulary;
+        let negative_total = (self.tokens - stats.tokens) as f64 + SMOOTHING * vocabulary;
+
+        let mut log_odds = (positive_docs as f64 / negative_docs as f64).ln();
+        let mut contributions = Vec::new();
+        for feature in features {
+            let Some(token) = self.vocabulary.get(feature) else {
                 continue;
+            };
+            let positive = token.by_category.iter()
+                .find(|(i, _)| *i == index)
+                .map_or(0, |(_, count)| *count) as f64;
+            let negative = token.documents as f64 - positive;
+            let contribution = ((positive + SMOOTHING) / positive_total).ln() - ((negative + SMOOTHING) / negative_total).ln();
+            log_odds += contribution;
+            if contribution > 0.0 {
+                contributions.push((contribution, feature));
             }
-            
-            // Remove diff prefix (+ or -)
-            let line = line.trim_start_matches('+').trim_start_matches('-').trim();
-            
-            // Check each rule
-            for (pattern, category) in &self.rules {
-                if line.contains(pattern) {
-                    categories.insert(category.clone());
+        }
+
+        contributions.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        let evidence = contributions.into_iter()
+            .take(EVIDENCE_TOKENS)
+            .map(|(_, feature)| format!("token {:?}", feature))
+            .collect();
+        Some((1.0 / (1.0 + (-log_odds).exp()), evidence))
+    }
+}
+
+// Message tokens, changed-line tokens and file extensions, kept apart by prefix
+fn features(message: &str, diff: &str) -> HashSet<String> {
+    let mut features: HashSet<String> = search::tokenize(message).into_iter()
+        .map(|token| format!("m:{}", token))
+        .collect();
+
+    let mut changed = 0;
+    for line in diff::lines(diff) {
+        match line {
+            DiffLine::Header(header) => {
+                let extension = header.strip_prefix("+++ b/").and_then(|path| Path::new(path).extension());
+                if let Some(extension) = extension {
+                    features.insert(format!("ext:{}", extension.to_string_lossy().to_lowercase()));
                 }
             }
+            DiffLine::Added(content) | DiffLine::Removed(content) if changed < MAX_FEATURE_LINES => {
+                changed += 1;
+                features.extend(search::tokenize(content).into_iter().map(|token| format!("d:{}", token)));
+            }
+            _ => {}
         }
-        
-        Ok(categories.into_iter().collect())
     }
+
+    features
+}
+
+/// Categories implied by a Conventional Commit type, used as labels when no others are given.
+pub fn conventional_categories(message: &str) -> Vec<Category> {
+    let Some(commit) = ConventionalCommit::parse(message) else {
+        return Vec::new();
+    };
+    let category = match commit.kind.to_lowercase().as_str() {
+        "docs" | "doc" => Category::Documentation,
+        "test" | "tests" => Category::Testing,
+        "perf" => Category::Performance,
+        "refactor" => Category::Refactoring,
+        "build" | "deps" => Category::Dependencies,
+        "ci" | "config" => Category::Configuration,
+        "security" | "sec" => Category::Security,
+        "a11y" => Category::Accessibility,
+        _ => return Vec::new(),
+    };
+    vec![category]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LabelRecord {
+    commit: String,
+    categories: Vec<String>,
+}
+
+/// Reads labels from JSON lines such as `{"commit": "3f2a9c1", "categories": ["security"]}`.
+///
+/// Commits may be abbreviated; blank lines are skipped.
+pub fn load_labels(path: &Path) -> Result<Vec<(String, Vec<Category>)>> {
+    let content = std::fs::read_to_string(path)?;
+    let mut labels = Vec::new();
+    for (number, line) in content.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let record: LabelRecord = serde_json::from_str(line).map_err(|e| {
+            HistorianError::InvalidArgument(format!("{}:{}: {}", path.display(), number + 1, e))
+        })?;
+        let categories = record.categories.iter()
+            .map(|c| c.parse())
+            .collect::<Result<Vec<Category>>>()?;
+        labels.push((record.commit, categories));
+    }
+    Ok(labels)
+}
+
+/// Training examples from analyzed changes: explicit labels first, then Conventional Commit types.
+///
+/// The first `max_examples` labeled changes are kept, which are the newest for an analysis.
+pub fn training_examples<'c>(
+    changes: impl IntoIterator<Item = &'c Change>,
+    labels: &[(String, Vec<Category>)],
+    max_examples: usize,
+) -> Vec<TrainingExample> {
+    changes.into_iter()
+        .filter_map(|change| {
+            let categories = labels.iter()
+                .find(|(commit, _)| !commit.is_empty() && change.commit_id.starts_with(commit.as_str()))
+                .map(|(_, categories)| categories.clone())
+                .unwrap_or_else(|| conventional_categories(&change.message));
+            (!categories.is_empty()).then(|| TrainingExample {
+                message: change.message.clone(),
+                diff: change.diff.clone(),
+                categories,
+            })
+        })
+        .take(max_examples)
+        .collect()
 }
 
 #[cfg(test)]
@@ -136,24 +482,204 @@ mod tests {
         let classifier = ChangeClassifier::new().unwrap();
         
         // Test API and Logic changes
-        let diff = "
-            + pub fn new_function() {
-            +     if true {
-            +         // do something
-            +     }
-            + }
-        ";
+        let diff = "+pub fn new_function() {\n+    if true {\n+        // do something\n+    }\n+}\n";
         let categories = classifier.predict_categories(diff).unwrap();
         assert!(categories.contains(&Category::Api));
         assert!(categories.contains(&Category::Logic));
         
         // Test Security changes
-        let diff = "
-            + fn encrypt_password(password: &str) {
-            +     // encryption logic
-            + }
-        ";
+        let diff = "+fn encrypt_password(password: &str) {\n+    // encryption logic\n+}\n";
         let categories = classifier.predict_categories(diff).unwrap();
         assert!(categories.contains(&Category::Security));
+
+        // Imports are not dependency changes
+        let diff = "+use std::collections::HashMap;\n+import os\n";
+        let categories = classifier.predict_categories(diff).unwrap();
+        assert!(!categories.contains(&Category::Dependencies));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_category_scores() {
+        let classifier = ChangeClassifier::new().unwrap();
+
+        let scores = classifier.score("", "+let key = encrypt(data);\n").unwrap();
+        let security = scores.iter().find(|s| s.category == Category::Security).unwrap();
+        assert!((security.confidence - 0.65).abs() < 1e-9);
+        assert!(!security.is_certain());
+        assert_eq!(security.evidence, vec!["keyword \"encrypt\"".to_string()]);
+
+        // Each further matching line adds confidence
+        let diff = "+let key = encrypt(data);\n+check_password(&user)?;\n+let token = auth_token(&user);\n";
+        let scores = classifier.score("", diff).unwrap();
+        assert_eq!(scores[0].category, Category::Security);
+        assert!(scores[0].is_certain());
+        assert_eq!(scores[0].evidence.len(), 3);
+
+        // Below the cutoff categories are dropped
+        let strict = ChangeClassifier::new().unwrap().with_min_confidence(0.7);
+        assert!(strict.predict("", "+let key = encrypt(data);\n").unwrap().is_empty());
+        assert!(strict.predict("", diff).unwrap().contains(&Category::Security));
+    }
+
+    #[test]
+    fn test_token_aware_rules() {
+        let classifier = ChangeClassifier::new().unwrap();
+        let categories = |diff: &str| classifier.predict_categories(diff).unwrap();
+
+        // Keywords count on word boundaries only
+        assert!(!categories("+let environment = catalog.remove(item);\n").contains(&Category::Configuration));
+        assert!(categories("+let port = env_var(\"PORT\");\n").contains(&Category::Configuration));
+
+        // Comments and strings are not code, wherever they started
+        let diff = "+++ b/src/lib.rs\n@@ -1 +1,4 @@\n+/* load the config\n+ and log it */\n+let s = \"password\";\n";
+        let found = categories(diff);
+        assert!(!found.contains(&Category::Configuration));
+        assert!(!found.contains(&Category::Logging));
+        assert!(!found.contains(&Category::Security));
+        assert!(categories("+++ b/src/lib.rs\n+/// Documented\n").contains(&Category::Documentation));
+        assert!(!categories("+++ b/src/lib.rs\n+let url = \"a///b\";\n").contains(&Category::Documentation));
+
+        // Context lines do not count, and removed lines count for less than added ones
+        assert!(categories(" let key = encrypt(data);\n").is_empty());
+        let classifier = ChangeClassifier::new().unwrap().with_min_confidence(0.0);
+        let added = classifier.score("", "+let key = encrypt(data);\n").unwrap();
+        let removed = classifier.score("", "-let key = encrypt(data);\n").unwrap();
+        assert!(removed[0].confidence < added[0].confidence);
+        assert!((removed[0].confidence - (1.0 - RULE_MISS.powf(REMOVED_LINE_WEIGHT))).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_configured_rules() {
+        let config = Config {
+            classification: toml::from_str(r#"
+                builtin_rules = false
+
+                [[rules]]
+                category = "security"
+                pattern = "unsafe"
+                languages = ["rust"]
+                weight = 2.0
+
+                [[rules]]
+                name = "hotfix"
+                category = "error-handling"
+                pattern = "(?i)^hotfix"
+                kind = "regex"
+                target = "message"
+            "#).unwrap(),
+            ..Default::default()
+        };
+        let classifier = ChangeClassifier::from_config(&config, Path::new(".")).unwrap();
+
+        let scores = classifier.score_change(Some(Path::new("src/ffi.rs")), "Add bindings", "+unsafe { call() }\n").unwrap();
+        assert_eq!(scores.len(), 1);
+        assert!((scores[0].confidence - (1.0 - RULE_MISS * RULE_MISS)).abs() < 1e-9);
+        assert_eq!(scores[0].evidence, vec!["rule \"unsafe\"".to_string()]);
+        // Scoped to Rust, and the built-in rules are off
+        assert!(classifier.predict("", "+++ b/ffi.py\n+unsafe = True\n").unwrap().is_empty());
+        assert!(classifier.predict("", "+++ b/ffi.rs\n+unsafe { call() }\n").unwrap().contains(&Category::Security));
+
+        let categories = classifier.predict("Hotfix the crash", "+let x = 1;\n").unwrap();
+        assert_eq!(categories, vec![Category::ErrorHandling]);
+        assert!(classifier.predict("Add a test", "+// hotfix\n").unwrap().is_empty());
+    }
+
+    fn example(message: &str, path: &str, lines: &[&str]) -> TrainingExample {
+        let added: String = lines.iter().map(|line| format!("+{}\n", line)).collect();
+        TrainingExample {
+            message: message.to_string(),
+            diff: format!("--- a/{0}\n+++ b/{0}\n{1}", path, added),
+            categories: conventional_categories(message),
+        }
+    }
+
+    fn model() -> NaiveBayesModel {
+        NaiveBayesModel::train(&[
+            example("docs: describe installation", "README.md", &["Install the tool with cargo install"]),
+            example("docs: document configuration", "docs/config.md", &["Set the output directory with cargo run"]),
+            example("test: cover the parser", "tests/parser.rs", &["#[test]", "fn parses_empty_input() { assert!(parse(\"\").is_ok()); }"]),
+            example("test: cover the lexer", "tests/lexer.rs", &["#[test]", "fn lexes_numbers() { assert_eq!(lex(\"1\").len(), 1); }"]),
+            example("perf: preallocate tokens", "src/lexer.rs", &["let tokens = Vec::with_capacity(input.len());"]),
+            example("feat: add search", "src/search.rs", &["pub fn search() {}"]),
+        ])
+    }
+
+    #[test]
+    fn test_trained_model() {
+        let model = model();
+        assert_eq!(model.documents(), 5);
+        assert_eq!(model.category_counts(), vec![
+            (Category::Documentation, 2),
+            (Category::Testing, 2),
+            (Category::Performance, 1),
+        ]);
+
+        let docs = "+++ b/docs/guide.md\n+Install with cargo and clear the cache\n";
+        let classify = |mode| {
+            ChangeClassifier::new().unwrap()
+                .with_model(model.clone(), mode, 0.6)
+                .predict("Update the install guide", docs)
+                .unwrap()
+        };
+
+        let categories = classify(ClassifierMode::Model);
+        assert!(categories.contains(&Category::Documentation));
+        let scores = ChangeClassifier::new().unwrap()
+            .with_model(model.clone(), ClassifierMode::Model, 0.6)
+            .score("Update the install guide", docs)
+            .unwrap();
+        let documentation = scores.iter().find(|s| s.category == Category::Documentation).unwrap();
+        assert!(documentation.confidence >= 0.6);
+        assert!(documentation.evidence.iter().all(|e| e.starts_with("token ")));
+        assert!(!documentation.evidence.is_empty());
+        assert!(!categories.contains(&Category::Testing));
+        assert!(!categories.contains(&Category::Performance));
+
+        // The rules see "cache"; blending lets the model veto it
+        assert!(!classify(ClassifierMode::Blend).contains(&Category::Performance));
+        let categories = classify(ClassifierMode::Rules);
+        assert!(categories.contains(&Category::Performance));
+        assert!(!categories.contains(&Category::Documentation));
+
+        let classifier = ChangeClassifier::new().unwrap().with_model(model, ClassifierMode::Model, 0.6);
+        let categories = classifier
+            .predict("Add a regression test", "+++ b/tests/api.rs\n+#[test]\n+fn handles_errors() { assert!(call().is_err()); }\n")
+            .unwrap();
+        assert!(categories.contains(&Category::Testing));
+        // Categories without training examples are left to the rules
+        assert!(classifier.predict_categories("+fn check_password() {}\n").unwrap().contains(&Category::Security));
+    }
+
+    #[test]
+    fn test_training_data() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let labels_path = temp_dir.path().join("labels.jsonl");
+        std::fs::write(&labels_path, "{\"commit\": \"abc\", \"categories\": [\"security\", \"ErrorHandling\"]}\n\n").unwrap();
+        let labels = load_labels(&labels_path).unwrap();
+        assert_eq!(labels, vec![("abc".to_string(), vec![Category::Security, Category::ErrorHandling])]);
+
+        let change = |commit_id: &str, message: &str| Change {
+            commit_id: commit_id.to_string(),
+            message: message.to_string(),
+            ..Default::default()
+        };
+        let changes = vec![
+            change("abc123", "Check tokens"),
+            change("def456", "docs: fix typo"),
+            change("0a1b2c", "Tweak"),
+            change("3d4e5f", "test: more cases"),
+        ];
+        let examples = training_examples(&changes, &labels, 2);
+        assert_eq!(examples.len(), 2);
+        assert_eq!(examples[0].categories, vec![Category::Security, Category::ErrorHandling]);
+        assert_eq!(examples[1].categories, vec![Category::Documentation]);
+
+        std::fs::write(&labels_path, "{\"commit\": \"abc\", \"categories\": [\"colour\"]}\n").unwrap();
+        assert!(load_labels(&labels_path).is_err());
+
+        let model_path = temp_dir.path().join("models").join(MODEL_FILE);
+        assert!(NaiveBayesModel::load(&model_path).unwrap().is_none());
+        model().save(&model_path).unwrap();
+        assert_eq!(NaiveBayesModel::load(&model_path).unwrap().unwrap().documents(), 5);
+    }
+}
@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+use serde::{Serialize, Deserialize};
+use crate::Analysis;
+use crate::config::PrecommitConfig;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Violation {
+    pub file_path: Option<PathBuf>,
+    pub message: String,
+}
+
+/// Check an uncommitted analysis against the configured pre-commit thresholds.
+pub fn check(analysis: &Analysis, config: &PrecommitConfig) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    for change in &analysis.changes {
+        if let Some(max_impact) = config.max_impact {
+            if change.impact_score > max_impact {
+                violations.push(Violation {
+                    file_path: Some(change.file_path.clone()),
+                    message: format!("impact {:.1} exceeds the limit of {:.1}", change.impact_score, max_impact),
+                });
+            }
+        }
+
+        if let Some(category) = change.categories.iter().find(|c| config.fail_on_categories.contains(c)) {
+            violations.push(Violation {
+                file_path: Some(change.file_path.clone()),
+                message: format!("{:?} changes need review before committing", category),
+            });
+        }
+    }
+
+    if let Some(max_total) = config.max_total_impact {
+        let total: f64 = analysis.changes.iter().map(|c| c.impact_score).sum();
+        if total > max_total {
+            violations.push(Violation {
+                file_path: None,
+                message: format!("total impact {:.1} exceeds the limit of {:.1}", total, max_total),
+            });
+        }
+    }
+
+    if let Some(max_lines) = config.max_lines_changed {
+        let lines = analysis.metrics.lines_added + analysis.metrics.lines_removed;
+        if lines > max_lines {
+            violations.push(Violation {
+                file_path: None,
+                message: format!("{} lines changed, more than the limit of {}", lines, max_lines),
+            });
+        }
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Category, Change};
+    use crate::analyzer::Metrics;
+
+    fn change(path: &str, impact_score: f64, categories: Vec<Category>) -> Change {
+        Change {
+            commit_id: "STAGED".to_string(),
+            file_path: PathBuf::from(path),
+            impact_score,
+            categories,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check_thresholds() {
+        let analysis = Analysis {
+            changes: vec![
+                change("src/auth.rs", 9.0, vec![Category::Security]),
+                change("src/lib.rs", 3.0, vec![Category::Logic]),
+            ],
+            metrics: Metrics {
+                lines_added: 300,
+                lines_removed: 50,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert!(check(&analysis, &PrecommitConfig::default()).is_empty());
+
+        let config = PrecommitConfig {
+            max_impact: Some(8.0),
+            max_total_impact: Some(10.0),
+            max_lines_changed: Some(200),
+            fail_on_categories: vec![Category::Security],
+        };
+        let violations = check(&analysis, &config);
+        assert_eq!(violations.len(), 4);
+        assert_eq!(violations[0].file_path, Some(PathBuf::from("src/auth.rs")));
+        assert!(violations[1].message.contains("Security"));
+        assert!(violations[3].message.contains("350 lines"));
+    }
+}
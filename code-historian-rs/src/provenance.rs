@@ -0,0 +1,281 @@
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use git2::{BlameOptions, Commit, DiffOptions, Mailmap, Patch, Repository};
+use serde::{Serialize, Deserialize};
+use crate::{Result, HistorianError, Category};
+use crate::analyzer::{self, Change};
+use crate::changelog::IssueLink;
+use crate::feedback::{self, Correction};
+use crate::message;
+use crate::ml::ChangeClassifier;
+use crate::symbols::{HistoryTarget, SymbolTracker};
+
+// Lines either side of the requested one that make up its region
+const REGION_LINES: usize = 3;
+
+/// Who last wrote a line, why and when, and how unsettled the code around it has been since.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineProvenance {
+    pub file_path: PathBuf,
+    // 1-based
+    pub line: usize,
+    // Commit the lookup was made at
+    pub revision: String,
+    pub content: String,
+    pub commit_id: String,
+    pub author: String,
+    pub author_email: String,
+    pub timestamp: DateTime<Utc>,
+    // Author's offset from UTC, in minutes
+    pub timezone_offset: i32,
+    pub summary: String,
+    // Where the line was when the commit wrote it
+    pub original_path: PathBuf,
+    pub original_line: usize,
+    pub categories: Vec<Category>,
+    // Only known when the commit is part of an analysis
+    pub impact_score: Option<f64>,
+    pub issues: Vec<IssueLink>,
+    // Later commits that changed the lines around it
+    pub changes_since: usize,
+}
+
+impl LineProvenance {
+    /// Takes categories and impact from the analyzed change, when there is one.
+    pub fn annotate(&mut self, changes: &[Change]) {
+        let analyzed = changes.iter()
+            .find(|c| c.commit_id == self.commit_id && c.file_path == self.original_path);
+        if let Some(change) = analyzed {
+            self.categories = change.categories.clone();
+            self.impact_score = Some(change.impact_score);
+        }
+    }
+}
+
+pub struct ProvenanceResolver<'a> {
+    repo: &'a Repository,
+    classifier: &'a ChangeClassifier,
+    mailmap: Mailmap,
+    issue_url_template: Option<String>,
+    corrections: Vec<Correction>,
+}
+
+impl<'a> ProvenanceResolver<'a> {
+    pub fn new(repo: &'a Repository, classifier: &'a ChangeClassifier) -> Result<Self> {
+        Ok(Self {
+            repo,
+            classifier,
+            mailmap: repo.mailmap()?,
+            issue_url_template: None,
+            corrections: Vec::new(),
+        })
+    }
+
+    pub fn with_issue_url_template(mut self, template: Option<String>) -> Self {
+        self.issue_url_template = template;
+        self
+    }
+
+    /// Feedback corrections, which override the categories of the commits they cover.
+    pub fn with_corrections(mut self, corrections: Vec<Correction>) -> Self {
+        self.corrections = corrections;
+        self
+    }
+
+    /// Looks up `line` (1-based) of `path` as of `rev`; `path` may be absolute within the working tree.
+    pub fn resolve(&self, path: &Path, line: usize, rev: &str) -> Result<LineProvenance> {
+        let path = self.relative_path(path)?;
+        let revision = self.repo.revparse_single(rev)?.peel_to_commit()?;
+        let entry = revision.tree()?.get_path(&path)
+            .map_err(|_| HistorianError::InvalidArgument(format!("{} does not exist at {}", path.display(), rev)))?;
+        let blob = self.repo.find_blob(entry.id())?;
+        let content = String::from_utf8_lossy(blob.content()).into_owned();
+        let lines: Vec<&str> = content.lines().collect();
+        if line == 0 || line > lines.len() {
+            return Err(HistorianError::InvalidArgument(format!(
+                "{} has {} lines; line {} is out of range",
+                path.display(),
+                lines.len(),
+                line
+            )));
+        }
+
+        let mut blame_opts = BlameOptions::new();
+        blame_opts
+            .newest_commit(revision.id())
+            .track_copies_same_file(true)
+            .track_copies_same_commit_moves(true);
+        let blame = self.repo.blame_file(&path, Some(&mut blame_opts))?;
+        let hunk = blame.get_line(line)
+            .ok_or_else(|| HistorianError::Analysis(format!("No blame for {}:{}", path.display(), line)))?;
+
+        let commit = self.repo.find_commit(hunk.final_commit_id())?;
+        let original_path = hunk.path().map(Path::to_path_buf).unwrap_or_else(|| path.clone());
+        let original_line = hunk.orig_start_line() + (line - hunk.final_start_line());
+        let author = commit.author_with_mailmap(&self.mailmap)?;
+        let message = commit.message().unwrap_or("");
+
+        let region = HistoryTarget::Lines {
+            file: path.clone(),
+            start: line.saturating_sub(REGION_LINES).max(1),
+            end: (line + REGION_LINES).min(lines.len()),
+        };
+        let when = author.when();
+        let timestamp = DateTime::from_timestamp(when.seconds(), 0).unwrap_or_else(Utc::now);
+
+        Ok(LineProvenance {
+            file_path: path.clone(),
+            line,
+            revision: revision.id().to_string(),
+            content: lines[line - 1].to_string(),
+            commit_id: commit.id().to_string(),
+            author: author.name().unwrap_or("unknown").to_string(),
+            author_email: author.email().unwrap_or("").to_string(),
+            timestamp,
+            timezone_offset: when.offset_minutes(),
+            summary: commit.summary().unwrap_or("").to_string(),
+            categories: self.categorize(&commit, &original_path)?,
+            original_path,
+            original_line,
+            impact_score: None,
+            issues: message::issue_references(message).into_iter()
+                .map(|id| IssueLink::new(id, self.issue_url_template.as_deref()))
+                .collect(),
+            changes_since: self.changes_since(&region, rev, &commit)?,
+        })
+    }
+
+    fn relative_path(&self, path: &Path) -> Result<PathBuf> {
+        if path.is_relative() {
+            return Ok(path.to_path_buf());
+        }
+        self.repo.workdir()
+            .and_then(|workdir| path.strip_prefix(workdir).ok())
+            .map(Path::to_path_buf)
+            .ok_or_else(|| HistorianError::InvalidArgument(format!("{} is outside the repository", path.display())))
+    }
+
+    // Classifies the commit's own change to the file as an analysis would, for commits outside one
+    fn categorize(&self, commit: &Commit, path: &Path) -> Result<Vec<Category>> {
+        let parent_tree = commit.parent(0).ok().map(|p| p.tree()).transpose()?;
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.pathspec(path);
+        let diff = self.repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), Some(&mut diff_opts))?;
+
+        let mut text = String::new();
+        for idx in 0..diff.deltas().len() {
+            if let Some(mut patch) = Patch::from_diff(&diff, idx)? {
+                text.push_str(&String::from_utf8_lossy(&patch.to_buf()?));
+            }
+        }
+
+        let mut change = Change {
+            commit_id: commit.id().to_string(),
+            file_path: path.to_path_buf(),
+            message: commit.message().unwrap_or("").to_string(),
+            diff: text,
+            ..Default::default()
+        };
+        analyzer::categorize_change(self.classifier, &mut change)?;
+        feedback::apply(std::slice::from_mut(&mut change), &self.corrections);
+        Ok(change.categories)
+    }
+
+    // Only the history since the commit is walked
+    fn changes_since(&self, region: &HistoryTarget, rev: &str, commit: &Commit) -> Result<usize> {
+        let history = SymbolTracker::new(self.repo, self.classifier)?
+            .since(commit)
+            .history(region, rev)?;
+        Ok(history.revisions.len())
+    }
+}
+
+/// Provenance of `line` in `path` as of `rev`, categorized with `classifier`.
+pub fn resolve(repo: &Repository, classifier: &ChangeClassifier, path: &Path, line: usize, rev: &str) -> Result<LineProvenance> {
+    ProvenanceResolver::new(repo, classifier)?.resolve(path, line, rev)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Oid;
+    use tempfile::TempDir;
+
+    fn commit_file(repo: &Repository, content: &str, message: &str) -> Oid {
+        std::fs::write(repo.workdir().unwrap().join("lib.rs"), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("lib.rs")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let parents: Vec<Commit> = repo.head().ok()
+            .and_then(|h| h.peel_to_commit().ok())
+            .into_iter()
+            .collect();
+        let parents: Vec<&Commit> = parents.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap()
+    }
+
+    fn lines(changed: &[(usize, &str)]) -> String {
+        (1..=10)
+            .map(|n| match changed.iter().find(|(line, _)| *line == n) {
+                Some((_, text)) => format!("{}\n", text),
+                None => format!("let line{} = {};\n", n, n),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_line() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+
+        let added = commit_file(&repo, &lines(&[]), "Add lib (#12)");
+        let fixed = commit_file(&repo, &lines(&[(5, "let line5 = try_parse();")]), "fix: handle errors (PROJ-7)");
+        let before_head = commit_file(&repo, &lines(&[(5, "let line5 = try_parse();"), (7, "let line7 = 0;")]), "Tweak line 7");
+        commit_file(&repo, &lines(&[(1, "// header"), (5, "let line5 = try_parse();"), (7, "let line7 = 0;")]), "Add header");
+
+        let classifier = ChangeClassifier::new().unwrap();
+        let resolver = ProvenanceResolver::new(&repo, &classifier).unwrap()
+            .with_issue_url_template(Some("https://issues.example.com/{id}".to_string()));
+
+        let mut provenance = resolver.resolve(Path::new("lib.rs"), 5, "HEAD").unwrap();
+        assert_eq!(provenance.commit_id, fixed.to_string());
+        assert_eq!(provenance.content, "let line5 = try_parse();");
+        assert_eq!(provenance.issues[0].id, "PROJ-7");
+        assert_eq!(provenance.issues[0].url.as_deref(), Some("https://issues.example.com/PROJ-7"));
+        assert!(provenance.categories.contains(&Category::ErrorHandling));
+        assert_eq!(provenance.impact_score, None);
+        // Line 7 changed since, line 1 is outside the region
+        assert_eq!(provenance.changes_since, 1);
+
+        let corrected = ProvenanceResolver::new(&repo, &classifier).unwrap()
+            .with_corrections(vec![Correction::new(fixed.to_string(), vec![Category::Logging])])
+            .resolve(Path::new("lib.rs"), 5, "HEAD")
+            .unwrap();
+        assert_eq!(corrected.categories, vec![Category::Logging]);
+
+        let analyzed = Change {
+            commit_id: fixed.to_string(),
+            file_path: "lib.rs".into(),
+            categories: vec![Category::Security],
+            impact_score: 4.5,
+            ..Default::default()
+        };
+        provenance.annotate(&[analyzed]);
+        assert_eq!(provenance.categories, vec![Category::Security]);
+        assert_eq!(provenance.impact_score, Some(4.5));
+
+        let provenance = resolve(&repo, &ChangeClassifier::default(), &temp_dir.path().join("lib.rs"), 2, "HEAD").unwrap();
+        assert_eq!(provenance.commit_id, added.to_string());
+        assert_eq!(provenance.issues[0].id, "#12");
+        assert_eq!(provenance.changes_since, 2);
+
+        // Earlier revisions are resolved as they were
+        let provenance = resolve(&repo, &ChangeClassifier::default(), Path::new("lib.rs"), 1, &before_head.to_string()).unwrap();
+        assert_eq!(provenance.commit_id, added.to_string());
+
+        assert!(matches!(resolve(&repo, &ChangeClassifier::default(), Path::new("lib.rs"), 11, "HEAD"), Err(HistorianError::InvalidArgument(_))));
+        assert!(matches!(resolve(&repo, &ChangeClassifier::default(), Path::new("missing.rs"), 1, "HEAD"), Err(HistorianError::InvalidArgument(_))));
+    }
+}
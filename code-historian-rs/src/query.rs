@@ -0,0 +1,481 @@
+use std::path::Path;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use glob::Pattern;
+use regex::Regex;
+use crate::{Result, HistorianError, Category};
+use crate::analyzer::Change;
+
+/// A parsed filter expression such as
+/// `category:security path:src/auth since:90d impact>5`.
+///
+/// Terms next to each other are combined with AND; `OR`, `NOT` and
+/// parentheses work as usual. An empty query matches every change.
+#[derive(Debug, Clone)]
+pub struct Query {
+    source: String,
+    expr: Option<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Term(Term),
+}
+
+#[derive(Debug, Clone)]
+pub enum Term {
+    // Case-insensitive substring of the author's name or email
+    Author(String),
+    Path(PathMatch),
+    Category(Category),
+    Since(DateTime<Utc>),
+    Until(DateTime<Utc>),
+    Impact(Comparison, f64),
+    // Case-insensitive substring of the commit message
+    Message(String),
+    MessageMatches(Regex),
+}
+
+/// A directory or file prefix, or a glob when the value has wildcards.
+#[derive(Debug, Clone)]
+pub enum PathMatch {
+    Prefix(String),
+    Glob(Pattern),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl Comparison {
+    fn compare(&self, left: f64, right: f64) -> bool {
+        match self {
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Eq => (left - right).abs() < f64::EPSILON,
+            Comparison::Ge => left >= right,
+            Comparison::Gt => left > right,
+        }
+    }
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self> {
+        Self::parse_at(input, Utc::now())
+    }
+
+    /// Parse with relative dates such as `since:90d` counted back from `now`.
+    pub fn parse_at(input: &str, now: DateTime<Utc>) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0, now };
+
+        let expr = if parser.tokens.is_empty() {
+            None
+        } else {
+            let expr = parser.or()?;
+            if let Some((token, at)) = parser.tokens.get(parser.pos) {
+                return Err(query_error(format!("unexpected {} at position {}", token.describe(), at)));
+            }
+            Some(expr)
+        };
+
+        Ok(Self { source: input.trim().to_string(), expr })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn expr(&self) -> Option<&Expr> {
+        self.expr.as_ref()
+    }
+
+    pub fn matches(&self, change: &Change) -> bool {
+        match &self.expr {
+            Some(expr) => expr.matches(change),
+            None => true,
+        }
+    }
+
+    pub fn filter<'c>(&'c self, changes: &'c [Change]) -> impl Iterator<Item = &'c Change> + 'c {
+        changes.iter().filter(move |change| self.matches(change))
+    }
+}
+
+impl std::str::FromStr for Query {
+    type Err = HistorianError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Query::parse(s)
+    }
+}
+
+impl Expr {
+    pub fn matches(&self, change: &Change) -> bool {
+        match self {
+            Expr::And(left, right) => left.matches(change) && right.matches(change),
+            Expr::Or(left, right) => left.matches(change) || right.matches(change),
+            Expr::Not(inner) => !inner.matches(change),
+            Expr::Term(term) => term.matches(change),
+        }
+    }
+}
+
+impl Term {
+    pub fn matches(&self, change: &Change) -> bool {
+        match self {
+            Term::Author(author) => {
+                change.author.to_lowercase().contains(author) || change.author_email.to_lowercase().contains(author)
+            }
+            Term::Path(PathMatch::Prefix(prefix)) => change.file_path.starts_with(Path::new(prefix)),
+            Term::Path(PathMatch::Glob(pattern)) => pattern.matches_path(&change.file_path),
+            Term::Category(category) => change.categories.contains(category),
+            Term::Since(since) => change.timestamp >= *since,
+            Term::Until(until) => change.timestamp < *until,
+            Term::Impact(comparison, value) => comparison.compare(change.impact_score, *value),
+            Term::Message(text) => change.message.to_lowercase().contains(text),
+            Term::MessageMatches(regex) => regex.is_match(&change.message),
+        }
+    }
+}
+
+fn query_error(message: String) -> HistorianError {
+    HistorianError::Query(message)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    // field, operator, value
+    Term(String, String, String),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::LParen => "'('".to_string(),
+            Token::RParen => "')'".to_string(),
+            Token::And => "AND".to_string(),
+            Token::Or => "OR".to_string(),
+            Token::Not => "NOT".to_string(),
+            Token::Term(field, op, value) => format!("'{}{}{}'", field, op, value),
+        }
+    }
+}
+
+// Tokens with the character position they start at, for error messages
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let start = i;
+        match chars[i] {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => {
+                tokens.push((Token::LParen, start));
+                i += 1;
+            }
+            ')' => {
+                tokens.push((Token::RParen, start));
+                i += 1;
+            }
+            '!' => {
+                tokens.push((Token::Not, start));
+                i += 1;
+            }
+            '&' | '|' if chars.get(i + 1) == Some(&chars[i]) => {
+                tokens.push((if chars[i] == '&' { Token::And } else { Token::Or }, start));
+                i += 2;
+            }
+            c if c.is_alphabetic() => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+
+                let op_start = i;
+                while i < chars.len() && matches!(chars[i], ':' | '~' | '<' | '>' | '=') {
+                    i += 1;
+                }
+                let op: String = chars[op_start..i].iter().collect();
+
+                if op.is_empty() {
+                    let keyword = match word.to_uppercase().as_str() {
+                        "AND" => Token::And,
+                        "OR" => Token::Or,
+                        "NOT" => Token::Not,
+                        _ => return Err(query_error(format!(
+                            "expected a filter such as author:, path: or impact> at position {}, found '{}'",
+                            start, word
+                        ))),
+                    };
+                    tokens.push((keyword, start));
+                    continue;
+                }
+
+                let value = read_value(&chars, &mut i)?;
+                if value.is_empty() {
+                    return Err(query_error(format!("missing value after '{}{}' at position {}", word, op, start)));
+                }
+                tokens.push((Token::Term(word.to_lowercase(), op, value), start));
+            }
+            c => return Err(query_error(format!("unexpected '{}' at position {}", c, start))),
+        }
+    }
+
+    Ok(tokens)
+}
+
+// A double-quoted string with backslash escapes, or a bare word ending at whitespace or ')'
+fn read_value(chars: &[char], i: &mut usize) -> Result<String> {
+    let mut value = String::new();
+
+    if chars.get(*i) == Some(&'"') {
+        let start = *i;
+        *i += 1;
+        loop {
+            match chars.get(*i) {
+                None => return Err(query_error(format!("unterminated string starting at position {}", start))),
+                Some('"') => {
+                    *i += 1;
+                    return Ok(value);
+                }
+                Some('\\') if *i + 1 < chars.len() => {
+                    value.push(chars[*i + 1]);
+                    *i += 2;
+                }
+                Some(c) => {
+                    value.push(*c);
+                    *i += 1;
+                }
+            }
+        }
+    }
+
+    while *i < chars.len() && !chars[*i].is_whitespace() && chars[*i] != ')' {
+        value.push(chars[*i]);
+        *i += 1;
+    }
+    Ok(value)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    now: DateTime<Utc>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or_else(
+            || self.tokens.last().map_or(0, |(_, at)| at + 1),
+            |(_, at)| *at,
+        )
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.pos += 1,
+                // Adjacent terms are an implicit AND
+                Some(Token::Not) | Some(Token::LParen) | Some(Token::Term(..)) => {}
+                _ => return Ok(expr),
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let at = self.position();
+        let Some((token, _)) = self.tokens.get(self.pos).cloned() else {
+            return Err(query_error(format!("expression ends early at position {}", at)));
+        };
+        self.pos += 1;
+
+        match token {
+            Token::LParen => {
+                let expr = self.or()?;
+                if self.peek() != Some(&Token::RParen) {
+                    return Err(query_error(format!("missing ')' for '(' at position {}", at)));
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            Token::Term(field, op, value) => Ok(Expr::Term(self.term(&field, &op, &value, at)?)),
+            other => Err(query_error(format!("unexpected {} at position {}", other.describe(), at))),
+        }
+    }
+
+    fn term(&self, field: &str, op: &str, value: &str, at: usize) -> Result<Term> {
+        let unsupported = || query_error(format!("'{}' does not support '{}' at position {}", field, op, at));
+
+        match (field, op) {
+            ("author", ":") => Ok(Term::Author(value.to_lowercase())),
+            ("path", ":") => {
+                if value.contains(['*', '?', '[']) {
+                    Pattern::new(value)
+                        .map(|pattern| Term::Path(PathMatch::Glob(pattern)))
+                        .map_err(|e| query_error(format!("invalid path pattern '{}': {}", value, e)))
+                } else {
+                    Ok(Term::Path(PathMatch::Prefix(value.trim_end_matches('/').to_string())))
+                }
+            }
+            ("category", ":") => value.parse()
+                .map(Term::Category)
+                .map_err(|_| query_error(format!("unknown category '{}' at position {}", value, at))),
+            ("since", ":") => Ok(Term::Since(self.date(value, at)?)),
+            ("until", ":") => Ok(Term::Until(self.date(value, at)?)),
+            ("impact", _) => {
+                let comparison = match op {
+                    "<" => Comparison::Lt,
+                    "<=" => Comparison::Le,
+                    ":" | "=" => Comparison::Eq,
+                    ">=" => Comparison::Ge,
+                    ">" => Comparison::Gt,
+                    _ => return Err(unsupported()),
+                };
+                value.parse()
+                    .map(|number| Term::Impact(comparison, number))
+                    .map_err(|_| query_error(format!("impact needs a number, found '{}' at position {}", value, at)))
+            }
+            ("message", ":") => Ok(Term::Message(value.to_lowercase())),
+            ("message", "~") => Regex::new(value)
+                .map(Term::MessageMatches)
+                .map_err(|e| query_error(format!("invalid message pattern '{}': {}", value, e))),
+            ("author" | "path" | "category" | "since" | "until" | "message", _) => Err(unsupported()),
+            _ => Err(query_error(format!("unknown filter '{}' at position {}", field, at))),
+        }
+    }
+
+    // `2024-01-31`, or a relative age such as `90d`, `2w`, `6m` or `1y`
+    fn date(&self, value: &str, at: usize) -> Result<DateTime<Utc>> {
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+        }
+
+        let invalid = || query_error(format!(
+            "expected a date like 2024-01-31 or an age like 90d, found '{}' at position {}",
+            value, at
+        ));
+        let (amount, unit) = value.split_at(value.len() - value.chars().last().map_or(0, char::len_utf8));
+        let amount: i64 = amount.parse().map_err(|_| invalid())?;
+        let per_unit = match unit {
+            "d" => 1,
+            "w" => 7,
+            "m" => 30,
+            "y" => 365,
+            _ => return Err(invalid()),
+        };
+        amount.checked_mul(per_unit)
+            .and_then(Duration::try_days)
+            .and_then(|age| self.now.checked_sub_signed(age))
+            .ok_or_else(|| query_error(format!("age '{}' at position {} is out of range", value, at)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::path::PathBuf;
+
+    fn change(author: &str, path: &str, category: Category, day: u32, impact_score: f64, message: &str) -> Change {
+        Change {
+            author: author.to_string(),
+            author_email: format!("{}@example.com", author.to_lowercase()),
+            file_path: PathBuf::from(path),
+            categories: vec![category],
+            timestamp: Utc.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap(),
+            impact_score,
+            message: message.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn matching(query: &str, changes: &[Change]) -> Vec<usize> {
+        let now = Utc.with_ymd_and_hms(2024, 3, 31, 0, 0, 0).unwrap();
+        let query = Query::parse_at(query, now).unwrap();
+        changes.iter().enumerate().filter(|(_, c)| query.matches(c)).map(|(i, _)| i).collect()
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let changes = vec![
+            change("Ann", "src/auth/login.rs", Category::Security, 30, 7.5, "Fix token check (#12)"),
+            change("Bob", "src/authz.rs", Category::Security, 29, 9.0, "Harden roles"),
+            change("Ann", "src/auth/session.rs", Category::Logic, 1, 2.0, "Refactor session"),
+            change("Cy", "docs/auth.md", Category::Documentation, 20, 1.0, "Document login"),
+        ];
+
+        assert_eq!(matching("category:security path:src/auth since:7d impact>5", &changes), vec![0]);
+        assert_eq!(matching("path:src/auth", &changes), vec![0, 2]);
+        assert_eq!(matching("path:\"src/*.rs\"", &changes), vec![0, 1, 2]);
+        assert_eq!(matching("author:ann OR author:cy@", &changes), vec![0, 2, 3]);
+        assert_eq!(matching("NOT category:security AND impact<=2", &changes), vec![2, 3]);
+        assert_eq!(matching("!(author:ann || category:documentation)", &changes), vec![1]);
+        assert_eq!(matching("message~\"#\\\\d+\"", &changes), vec![0]);
+        assert_eq!(matching("message:LOGIN", &changes), vec![3]);
+        assert_eq!(matching("since:2024-03-20 until:2024-03-30", &changes), vec![1, 3]);
+        assert_eq!(matching("category:error-handling", &changes), Vec::<usize>::new());
+        assert_eq!(matching("", &changes), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |query: &str| match Query::parse(query) {
+            Err(HistorianError::Query(message)) => message,
+            other => panic!("expected a query error for {:?}, got {:?}", query, other),
+        };
+
+        assert!(error("author:").contains("missing value"));
+        assert!(error("security").contains("expected a filter"));
+        assert!(error("colour:red").contains("unknown filter 'colour'"));
+        assert!(error("(author:ann").contains("missing ')'"));
+        assert!(error("author:ann OR").contains("ends early"));
+        assert!(error("impact>high").contains("needs a number"));
+        assert!(error("since:9223372036854775807y").contains("out of range"));
+        assert!(error("since:99999999999d").contains("out of range"));
+        assert!(error("since:yesterday").contains("expected a date"));
+        assert!(error("category:bugs").contains("unknown category"));
+        assert!(error("author>ann").contains("does not support"));
+        assert!(error("message:\"open").contains("unterminated"));
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use chrono::{DateTime, TimeZone, Utc};
+use git2::{Oid, Repository};
+use semver::{Version, VersionReq};
+use serde::{Serialize, Deserialize};
+use crate::{Result, HistorianError};
+use crate::analyzer::{Change, Metrics};
+use crate::config::ReleasesConfig;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Release {
+    pub tag: String,
+    pub version: Option<Version>,
+    pub commit_id: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Everything that went into one release since the previous one.
+///
+/// `tag` is `None` for commits that have not been released yet.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReleaseInterval {
+    pub tag: Option<String>,
+    pub previous_tag: Option<String>,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub metrics: Metrics,
+    pub contributors: Vec<Contributor>,
+    pub top_changes: Vec<TopChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Contributor {
+    pub name: String,
+    pub commits: usize,
+    pub changes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopChange {
+    pub commit_id: String,
+    pub file_path: PathBuf,
+    pub summary: String,
+    pub impact_score: f64,
+}
+
+pub fn parse_tag_pattern(pattern: &str) -> Result<VersionReq> {
+    VersionReq::parse(pattern)
+        .map_err(|e| HistorianError::Config(format!("Invalid release tag pattern '{}': {}", pattern, e)))
+}
+
+// Tags such as `v1.2.3` and `release-1.2.3` carry the version after the prefix
+fn tag_version(tag: &str) -> Option<Version> {
+    let start = tag.find(|c: char| c.is_ascii_digit())?;
+    Version::parse(&tag[start..]).ok()
+}
+
+/// List the tags treated as releases, oldest first.
+///
+/// With a `tag_pattern`, only tags whose version satisfies it are releases.
+pub fn find_releases(repo: &Repository, config: &ReleasesConfig) -> Result<Vec<Release>> {
+    let pattern = config.tag_pattern.as_deref().map(parse_tag_pattern).transpose()?;
+    let mut releases = Vec::new();
+
+    for tag in repo.tag_names(None)?.iter().flatten() {
+        let version = tag_version(tag);
+        if let Some(pattern) = &pattern {
+            if !version.as_ref().is_some_and(|v| pattern.matches(v)) {
+                continue;
+            }
+        }
+
+        // Tags on trees or blobs are not releases
+        let Ok(commit) = repo.revparse_single(&format!("refs/tags/{}", tag)).and_then(|o| o.peel_to_commit()) else {
+            continue;
+        };
+
+        releases.push(Release {
+            tag: tag.to_string(),
+            version,
+            commit_id: commit.id().to_string(),
+            timestamp: Utc.timestamp_opt(commit.time().seconds(), 0).single().unwrap_or_default(),
+        });
+    }
+
+    releases.sort_by(|a, b| {
+        a.timestamp.cmp(&b.timestamp)
+            .then_with(|| a.version.cmp(&b.version))
+            .then_with(|| a.tag.cmp(&b.tag))
+    });
+    Ok(releases)
+}
+
+/// Map each commit to the index of the first release that contains it.
+///
+/// Commits missing from the map have not been released.
+pub fn assign_commits(repo: &Repository, releases: &[Release]) -> Result<HashMap<String, usize>> {
+    let mut assigned = HashMap::new();
+
+    for (idx, release) in releases.iter().enumerate() {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(Oid::from_str(&release.commit_id)?)?;
+        for earlier in &releases[..idx] {
+            revwalk.hide(Oid::from_str(&earlier.commit_id)?)?;
+        }
+
+        for oid in revwalk {
+            assigned.entry(oid?.to_string()).or_insert(idx);
+        }
+    }
+
+    Ok(assigned)
+}
+
+/// Group changes by release, oldest first, with unreleased work last.
+pub fn partition<'c>(changes: &'c [Change], assigned: &HashMap<String, usize>) -> Vec<(Option<usize>, Vec<&'c Change>)> {
+    let mut groups: BTreeMap<(bool, Option<usize>), Vec<&Change>> = BTreeMap::new();
+    for change in changes {
+        let release = assigned.get(&change.commit_id).copied();
+        groups.entry((release.is_none(), release)).or_default().push(change);
+    }
+
+    groups.into_iter()
+        .map(|((_, release), changes)| (release, changes))
+        .collect()
+}
+
+pub fn build_interval(
+    release: Option<usize>,
+    releases: &[Release],
+    changes: &[&Change],
+    metrics: Metrics,
+    top_changes: usize,
+) -> ReleaseInterval {
+    let previous = match release {
+        Some(idx) => idx.checked_sub(1),
+        None => releases.len().checked_sub(1),
+    };
+
+    let mut contributors: HashMap<&str, (HashSet<&str>, usize)> = HashMap::new();
+    for change in changes {
+        let (commits, count) = contributors.entry(change.author.as_str()).or_default();
+        commits.insert(change.commit_id.as_str());
+        *count += 1;
+    }
+    let mut contributors: Vec<Contributor> = contributors.into_iter()
+        .map(|(name, (commits, changes))| Contributor {
+            name: name.to_string(),
+            commits: commits.len(),
+            changes,
+        })
+        .collect();
+    contributors.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.name.cmp(&b.name)));
+
+    let mut ranked: Vec<&&Change> = changes.iter().collect();
+    ranked.sort_by(|a, b| b.impact_score.partial_cmp(&a.impact_score).unwrap_or(std::cmp::Ordering::Equal));
+
+    ReleaseInterval {
+        tag: release.map(|idx| releases[idx].tag.clone()),
+        previous_tag: previous.map(|idx| releases[idx].tag.clone()),
+        timestamp: release.map(|idx| releases[idx].timestamp),
+        metrics,
+        contributors,
+        top_changes: ranked.into_iter()
+            .take(top_changes)
+            .map(|change| TopChange {
+                commit_id: change.commit_id.clone(),
+                file_path: change.file_path.clone(),
+                summary: change.message.lines().next().unwrap_or_default().to_string(),
+                impact_score: change.impact_score,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn commit_file(repo: &Repository, path: &str, content: &str, message: &str) -> Oid {
+        std::fs::write(repo.workdir().unwrap().join(path), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let parents: Vec<git2::Commit> = repo.head().ok()
+            .and_then(|h| h.peel_to_commit().ok())
+            .into_iter()
+            .collect();
+        let parents: Vec<&git2::Commit> = parents.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap()
+    }
+
+    fn tag(repo: &Repository, name: &str, oid: Oid) {
+        let object = repo.find_object(oid, None).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        repo.tag(name, &object, &sig, name, false).unwrap();
+    }
+
+    fn change(commit_id: &str, author: &str, impact_score: f64) -> Change {
+        Change {
+            commit_id: commit_id.to_string(),
+            author: author.to_string(),
+            message: format!("change {}\n\nbody", commit_id),
+            impact_score,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_releases_and_assignment() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+
+        let first = commit_file(&repo, "a.txt", "1", "first");
+        tag(&repo, "v0.1.0", first);
+        let second = commit_file(&repo, "a.txt", "2", "second");
+        let third = commit_file(&repo, "a.txt", "3", "third");
+        tag(&repo, "v1.0.0", third);
+        repo.tag_lightweight("nightly", &repo.find_object(third, None).unwrap(), false).unwrap();
+        let fourth = commit_file(&repo, "a.txt", "4", "fourth");
+
+        let config = ReleasesConfig {
+            tag_pattern: Some(">=0.1.0".to_string()),
+            ..Default::default()
+        };
+        let releases = find_releases(&repo, &config).unwrap();
+        let tags: Vec<&str> = releases.iter().map(|r| r.tag.as_str()).collect();
+        assert_eq!(tags, vec!["v0.1.0", "v1.0.0"]);
+        assert_eq!(releases[1].version, Some(Version::new(1, 0, 0)));
+
+        let assigned = assign_commits(&repo, &releases).unwrap();
+        assert_eq!(assigned[&first.to_string()], 0);
+        assert_eq!(assigned[&second.to_string()], 1);
+        assert_eq!(assigned[&third.to_string()], 1);
+        assert!(!assigned.contains_key(&fourth.to_string()));
+
+        let releases = find_releases(&repo, &ReleasesConfig::default()).unwrap();
+        assert_eq!(releases.len(), 3);
+
+        assert!(parse_tag_pattern("not a version").is_err());
+    }
+
+    #[test]
+    fn test_partition_and_build() {
+        let releases = vec![
+            Release { tag: "v1.0.0".to_string(), version: None, commit_id: "a".to_string(), timestamp: Utc::now() },
+            Release { tag: "v1.1.0".to_string(), version: None, commit_id: "c".to_string(), timestamp: Utc::now() },
+        ];
+        let assigned: HashMap<String, usize> = [("a", 0), ("b", 1), ("c", 1)].into_iter()
+            .map(|(id, idx)| (id.to_string(), idx))
+            .collect();
+        let changes = vec![
+            change("d", "Ann", 2.0),
+            change("c", "Bob", 9.0),
+            change("b", "Ann", 3.0),
+            change("b", "Ann", 1.0),
+            change("a", "Bob", 1.0),
+        ];
+
+        let groups = partition(&changes, &assigned);
+        let order: Vec<Option<usize>> = groups.iter().map(|(release, _)| *release).collect();
+        assert_eq!(order, vec![Some(0), Some(1), None]);
+
+        let interval = build_interval(Some(1), &releases, &groups[1].1, Metrics::default(), 2);
+        assert_eq!(interval.tag.as_deref(), Some("v1.1.0"));
+        assert_eq!(interval.previous_tag.as_deref(), Some("v1.0.0"));
+        assert_eq!(interval.contributors[0].name, "Ann");
+        assert_eq!(interval.contributors[0].commits, 1);
+        assert_eq!(interval.contributors[0].changes, 2);
+        assert_eq!(interval.top_changes.len(), 2);
+        assert_eq!(interval.top_changes[0].commit_id, "c");
+        assert_eq!(interval.top_changes[0].summary, "change c");
+
+        let unreleased = build_interval(None, &releases, &groups[2].1, Metrics::default(), 5);
+        assert_eq!(unreleased.tag, None);
+        assert_eq!(unreleased.previous_tag.as_deref(), Some("v1.1.0"));
+    }
+}
@@ -1,17 +1,29 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use chrono::Utc;
 use handlebars::Handlebars;
 use serde::Serialize;
 use serde_json::json;
 use crate::{Result, HistorianError, Analysis, Change, Pattern};
+use crate::activity::{ActivityFlag, GroupActivity};
+use crate::dependencies::{DependencyChangeKind, VersionBump};
+use crate::attributes::ExcludedFile;
+use crate::components::ComponentCoupling;
+use crate::lint::QualityScore;
+use crate::releases::{ReleaseInterval, TopChange};
+use crate::signatures::SigningStats;
+use crate::szz::FileDefectDensity;
+use crate::testing::{TestPeriodMetrics, UntestedHotspot};
+use crate::workspace::{AuthorSummary, WorkspaceAnalysis};
 
 #[derive(Serialize)]
 struct ReportContext {
     generated_at: String,
+    filter: Option<String>,
     total_commits: usize,
     total_changes: usize,
     lines_added: usize,
     lines_removed: usize,
+    mechanical_changes: usize,
     avg_impact: f64,
     high_impact_count: usize,
     most_impacted_files: Vec<String>,
@@ -19,10 +31,176 @@ struct ReportContext {
     patterns: Vec<Pattern>,
     category_labels: Vec<String>,
     category_data: Vec<usize>,
+    category_confidence: Vec<ConfidenceRow>,
     timeline_labels: Vec<String>,
     timeline_data: Vec<usize>,
     impact_labels: Vec<String>,
     impact_data: Vec<f64>,
+    fix_commit_count: usize,
+    fix_inducing_count: usize,
+    defect_hotspots: Vec<FileDefectDensity>,
+    dependencies_added: usize,
+    dependencies_removed: usize,
+    dependencies_updated: usize,
+    major_bumps: usize,
+    dependency_changes: Vec<DependencyRow>,
+    test_commit_share: f64,
+    test_periods: Vec<TestPeriodMetrics>,
+    untested_hotspots: Vec<UntestedHotspot>,
+    large_file_threshold: String,
+    large_files: Vec<LargeFileRow>,
+    blob_growth_labels: Vec<String>,
+    blob_growth_data: Vec<i64>,
+    repository_size: String,
+    lfs_size: String,
+    generated_changes: usize,
+    vendored_changes: usize,
+    excluded_files: Vec<ExcludedFile>,
+    components: Vec<ComponentRow>,
+    component_coupling: Vec<ComponentCoupling>,
+    releases: Vec<ReleaseRow>,
+    message_quality_score: Option<String>,
+    message_quality_authors: Vec<QualityRow>,
+    message_quality_periods: Vec<QualityRow>,
+    message_rule_failures: Vec<(String, usize)>,
+    punch_card_data: Option<String>,
+    after_hours_share: String,
+    weekend_share: String,
+    activity_authors: Vec<ActivityRow>,
+    activity_teams: Vec<ActivityRow>,
+    signed_share: Option<String>,
+    verified_share: String,
+    signing_labels: Vec<String>,
+    signing_data: Vec<f64>,
+    signing_periods: Vec<SigningRow>,
+    signing_authors: Vec<SigningRow>,
+    workspace_name: Option<String>,
+    repositories: Vec<RepositoryRow>,
+    authors: Vec<AuthorSummary>,
+}
+
+#[derive(Serialize)]
+struct ReleaseRow {
+    name: String,
+    date: String,
+    commits: usize,
+    commits_delta: String,
+    changes: usize,
+    lines_added: usize,
+    lines_removed: usize,
+    contributors: usize,
+    top_categories: String,
+    top_changes: Vec<TopChange>,
+}
+
+#[derive(Serialize)]
+struct QualityRow {
+    key: String,
+    commits: usize,
+    score: String,
+}
+
+impl From<&QualityScore> for QualityRow {
+    fn from(score: &QualityScore) -> Self {
+        Self {
+            key: score.key.clone(),
+            commits: score.commits,
+            score: format!("{:.1}", score.score),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ActivityRow {
+    name: String,
+    commits: usize,
+    after_hours: String,
+    weekend: String,
+    flags: String,
+}
+
+impl From<&GroupActivity> for ActivityRow {
+    fn from(group: &GroupActivity) -> Self {
+        Self {
+            name: group.name.clone(),
+            commits: group.profile.commits,
+            after_hours: format_share(group.profile.after_hours_share()),
+            weekend: format_share(group.profile.weekend_share()),
+            flags: group.flags.iter()
+                .map(|flag| match flag {
+                    ActivityFlag::AfterHours => "after hours",
+                    ActivityFlag::Weekend => "weekends",
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SigningRow {
+    key: String,
+    commits: usize,
+    signed: String,
+    verified: String,
+}
+
+impl From<&SigningStats> for SigningRow {
+    fn from(stats: &SigningStats) -> Self {
+        Self {
+            key: stats.key.clone(),
+            commits: stats.commits,
+            signed: format_share(stats.signed_share()),
+            verified: format_share(share(stats.verified, stats.commits)),
+        }
+    }
+}
+
+// Scored categorizations of one category, split at the certainty threshold
+#[derive(Serialize)]
+struct ConfidenceRow {
+    category: String,
+    certain: usize,
+    probable: usize,
+    average: String,
+}
+
+#[derive(Serialize)]
+struct RepositoryRow {
+    name: String,
+    commits: usize,
+    changes: usize,
+    lines_added: usize,
+    lines_removed: usize,
+    report_path: String,
+}
+
+#[derive(Serialize)]
+struct ComponentRow {
+    name: String,
+    commits: usize,
+    changes: usize,
+    lines_added: usize,
+    lines_removed: usize,
+    report_path: String,
+}
+
+#[derive(Serialize)]
+struct DependencyRow {
+    date: String,
+    commit_id: String,
+    manifest: String,
+    name: String,
+    change: String,
+}
+
+#[derive(Serialize)]
+struct LargeFileRow {
+    file_path: String,
+    size: String,
+    lfs: bool,
+    commit_id: String,
+    date: String,
 }
 
 pub struct ReportGenerator {
@@ -40,9 +218,44 @@ impl ReportGenerator {
     pub fn generate_html(&self, analysis: &Analysis, output_path: &Path) -> Result<()> {
         // Prepare context data
         let context = self.prepare_context(analysis)?;
+        self.render(&context, output_path)
+    }
+
+    /// Write the combined workspace report plus one drill-down report per repository
+    /// under `<output_dir>/repos/<name>/report.html`.
+    pub fn generate_workspace_reports(&self, workspace: &WorkspaceAnalysis, output_dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
 
+        let mut context = self.prepare_context(&workspace.combined())?;
+        context.workspace_name = workspace.name.clone();
+        context.authors = workspace.authors.clone();
+        context.repositories = workspace.repositories.iter()
+            .map(|repo| RepositoryRow {
+                name: repo.name.clone(),
+                commits: repo.analysis.metrics.total_commits,
+                changes: repo.analysis.metrics.total_changes,
+                lines_added: repo.analysis.metrics.lines_added,
+                lines_removed: repo.analysis.metrics.lines_removed,
+                report_path: format!("repos/{}/report.html", component_dir(&repo.name)),
+            })
+            .collect();
+
+        let path = output_dir.join("report.html");
+        self.render(&context, &path)?;
+        paths.push(path);
+
+        for repo in &workspace.repositories {
+            let path = output_dir.join("repos").join(component_dir(&repo.name)).join("report.html");
+            self.generate_html(&repo.analysis, &path)?;
+            paths.push(path);
+        }
+
+        Ok(paths)
+    }
+
+    fn render(&self, context: &ReportContext, output_path: &Path) -> Result<()> {
         // Render template
-        let html = self.handlebars.render("report", &context)
+        let html = self.handlebars.render("report", context)
             .map_err(|e| HistorianError::Visualization(format!("Failed to render template: {}", e)))?;
 
         // Create output directory if it doesn't exist
@@ -56,6 +269,29 @@ impl ReportGenerator {
         Ok(())
     }
 
+    /// Write one HTML report per component to `<output_dir>/<component>/report.html`.
+    pub fn generate_component_reports(&self, analysis: &Analysis, output_dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+
+        for component in &analysis.components {
+            let component_analysis = Analysis {
+                changes: analysis.changes.iter()
+                    .filter(|c| c.component.as_deref() == Some(component.name.as_str()))
+                    .cloned()
+                    .collect(),
+                metrics: component.metrics.clone(),
+                patterns: component.patterns.clone(),
+                ..Default::default()
+            };
+
+            let path = output_dir.join(component_dir(&component.name)).join("report.html");
+            self.generate_html(&component_analysis, &path)?;
+            paths.push(path);
+        }
+
+        Ok(paths)
+    }
+
     fn prepare_context(&self, analysis: &Analysis) -> Result<ReportContext> {
         // Calculate high impact changes
         let high_impact_threshold = 7.0;
@@ -90,6 +326,29 @@ impl ReportGenerator {
 
         let (category_labels, category_counts): (Vec<_>, Vec<_>) = category_data.into_iter().unzip();
 
+        // Changes cached before scoring have categories but no scores and are left out
+        let mut confidence: std::collections::HashMap<String, (usize, usize, f64)> = std::collections::HashMap::new();
+        for score in analysis.changes.iter().flat_map(|c| &c.category_scores) {
+            let (certain, probable, total) = confidence.entry(format!("{:?}", score.category)).or_default();
+            if score.is_certain() {
+                *certain += 1;
+            } else {
+                *probable += 1;
+            }
+            *total += score.confidence;
+        }
+        let mut category_confidence: Vec<ConfidenceRow> = confidence.into_iter()
+            .map(|(category, (certain, probable, total))| ConfidenceRow {
+                category,
+                certain,
+                probable,
+                average: format_share(total / (certain + probable) as f64),
+            })
+            .collect();
+        category_confidence.sort_by(|a, b| {
+            (b.certain + b.probable).cmp(&(a.certain + a.probable)).then_with(|| a.category.cmp(&b.category))
+        });
+
         // Prepare timeline data
         let mut timeline_data: Vec<(String, usize)> = Vec::new();
         let mut changes_by_date: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
@@ -130,12 +389,111 @@ impl ReportGenerator {
 
         let (impact_labels, impact_counts): (Vec<_>, Vec<_>) = impact_data.into_iter().unzip();
 
+        // Prepare defect data
+        let (fix_commit_count, fix_inducing_count, defect_hotspots) = match &analysis.defects {
+            Some(defects) => (
+                defects.fix_commits.len(),
+                defects.bug_introducing.len(),
+                defects.file_density.iter().take(10).cloned().collect(),
+            ),
+            None => (0, 0, Vec::new()),
+        };
+
+        // Prepare dependency timeline
+        let timeline = &analysis.dependency_timeline;
+        let count_kind = |f: fn(&DependencyChangeKind) -> bool| {
+            timeline.iter().filter(|c| f(&c.kind)).count()
+        };
+        let dependencies_added = count_kind(|k| matches!(k, DependencyChangeKind::Added { .. }));
+        let dependencies_removed = count_kind(|k| matches!(k, DependencyChangeKind::Removed { .. }));
+        let dependencies_updated = count_kind(|k| matches!(k, DependencyChangeKind::Updated { .. }));
+        let major_bumps = count_kind(|k| matches!(k, DependencyChangeKind::Updated { bump: VersionBump::Major, .. }));
+
+        let dependency_changes = timeline.iter()
+            .map(|c| DependencyRow {
+                date: c.timestamp.format("%Y-%m-%d").to_string(),
+                commit_id: c.commit_id.chars().take(8).collect(),
+                manifest: c.manifest.display().to_string(),
+                name: c.name.clone(),
+                change: c.kind.describe(),
+            })
+            .collect();
+
+        // Prepare large file data
+        let large_files = analysis.large_files.large_files.iter()
+            .map(|f| LargeFileRow {
+                file_path: f.file_path.display().to_string(),
+                size: format_bytes(f.size as i64),
+                lfs: f.lfs,
+                commit_id: f.commit_id.chars().take(8).collect(),
+                date: f.timestamp.format("%Y-%m-%d").to_string(),
+            })
+            .collect();
+
+        let growth = &analysis.large_files.blob_growth;
+        let blob_growth_labels = growth.iter()
+            .map(|p| p.timestamp.format("%Y-%m-%d").to_string())
+            .collect();
+        let blob_growth_data = growth.iter().map(|p| p.total_bytes).collect();
+        let (repository_size, lfs_size) = growth.last()
+            .map(|p| (format_bytes(p.total_bytes), format_bytes(p.lfs_bytes)))
+            .unwrap_or_else(|| (format_bytes(0), format_bytes(0)));
+
+        // Prepare component summary
+        let components = analysis.components.iter()
+            .map(|c| ComponentRow {
+                name: c.name.clone(),
+                commits: c.metrics.total_commits,
+                changes: c.metrics.total_changes,
+                lines_added: c.metrics.lines_added,
+                lines_removed: c.metrics.lines_removed,
+                report_path: format!("components/{}/report.html", component_dir(&c.name)),
+            })
+            .collect();
+
+        // Compare each release with the one before it
+        let releases = analysis.releases.iter()
+            .enumerate()
+            .map(|(idx, release)| {
+                let previous = idx.checked_sub(1).map(|i| &analysis.releases[i]);
+                release_row(release, previous)
+            })
+            .collect();
+
+        let quality = &analysis.message_quality;
+        let mut message_rule_failures: Vec<(String, usize)> = quality.rule_failures.iter()
+            .map(|(rule, count)| (rule.clone(), *count))
+            .collect();
+        message_rule_failures.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+        // Punch card bubbles, sized relative to the busiest hour
+        let activity = &analysis.activity;
+        let busiest = activity.overall.punch_card.iter().flatten().copied().max().unwrap_or(0);
+        let punch_card_data = (busiest > 0).then(|| {
+            let points: Vec<serde_json::Value> = activity.overall.punch_card.iter()
+                .enumerate()
+                .flat_map(|(day, hours)| hours.iter().enumerate().map(move |(hour, count)| (day, hour, *count)))
+                .filter(|(_, _, count)| *count > 0)
+                .map(|(day, hour, count)| json!({
+                    "x": hour,
+                    "y": day,
+                    "r": 3.0 + 12.0 * count as f64 / busiest as f64,
+                    "count": count,
+                }))
+                .collect();
+            serde_json::Value::Array(points).to_string()
+        });
+
+        let signing = &analysis.signing;
+
         Ok(ReportContext {
             generated_at: Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+            filter: analysis.filter.clone(),
             total_commits: analysis.metrics.total_commits,
             total_changes: analysis.metrics.total_changes,
             lines_added: analysis.metrics.lines_added,
             lines_removed: analysis.metrics.lines_removed,
+            mechanical_changes: analysis.metrics.mechanical_changes,
             avg_impact,
             high_impact_count,
             most_impacted_files,
@@ -143,18 +501,138 @@ impl ReportGenerator {
             patterns: analysis.patterns.clone(),
             category_labels,
             category_data: category_counts,
+            category_confidence,
             timeline_labels,
             timeline_data: timeline_counts,
             impact_labels,
             impact_data: impact_counts,
+            fix_commit_count,
+            fix_inducing_count,
+            defect_hotspots,
+            dependencies_added,
+            dependencies_removed,
+            dependencies_updated,
+            major_bumps,
+            dependency_changes,
+            test_commit_share: analysis.test_evolution.test_commit_share,
+            test_periods: analysis.test_evolution.periods.clone(),
+            untested_hotspots: analysis.test_evolution.untested_hotspots.iter().take(10).cloned().collect(),
+            large_file_threshold: format_bytes(analysis.large_files.threshold as i64),
+            large_files,
+            blob_growth_labels,
+            blob_growth_data,
+            repository_size,
+            lfs_size,
+            generated_changes: analysis.exclusions.generated_changes,
+            vendored_changes: analysis.exclusions.vendored_changes,
+            excluded_files: analysis.exclusions.files.clone(),
+            components,
+            component_coupling: analysis.component_coupling.clone(),
+            releases,
+            message_quality_score: (quality.commits > 0).then(|| format!("{:.1}", quality.score)),
+            message_quality_authors: quality.authors.iter().map(QualityRow::from).collect(),
+            message_quality_periods: quality.periods.iter().map(QualityRow::from).collect(),
+            message_rule_failures,
+            punch_card_data,
+            after_hours_share: format_share(activity.overall.after_hours_share()),
+            weekend_share: format_share(activity.overall.weekend_share()),
+            activity_authors: activity.authors.iter().map(ActivityRow::from).collect(),
+            activity_teams: activity.teams.iter().map(ActivityRow::from).collect(),
+            signed_share: (signing.overall.commits > 0).then(|| format_share(signing.overall.signed_share())),
+            verified_share: format_share(share(signing.overall.verified, signing.overall.commits)),
+            signing_labels: signing.periods.iter().map(|p| p.key.clone()).collect(),
+            signing_data: signing.periods.iter().map(|p| p.signed_share() * 100.0).collect(),
+            signing_periods: signing.periods.iter().map(SigningRow::from).collect(),
+            signing_authors: signing.authors.iter().map(SigningRow::from).collect(),
+            workspace_name: None,
+            repositories: Vec::new(),
+            authors: Vec::new(),
         })
     }
 }
 
+fn release_row(release: &ReleaseInterval, previous: Option<&ReleaseInterval>) -> ReleaseRow {
+    let mut categories: Vec<(String, usize)> = release.metrics.category_distribution.iter()
+        .map(|(k, v)| (format!("{:?}", k), *v))
+        .collect();
+    categories.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let commits_delta = previous
+        .map(|p| match release.metrics.total_commits as i64 - p.metrics.total_commits as i64 {
+            0 => "±0".to_string(),
+            delta => format!("{:+}", delta),
+        })
+        .unwrap_or_default();
+
+    ReleaseRow {
+        name: release.tag.clone().unwrap_or_else(|| "Unreleased".to_string()),
+        date: release.timestamp.map(|t| t.format("%Y-%m-%d").to_string()).unwrap_or_default(),
+        commits: release.metrics.total_commits,
+        commits_delta,
+        changes: release.metrics.total_changes,
+        lines_added: release.metrics.lines_added,
+        lines_removed: release.metrics.lines_removed,
+        contributors: release.contributors.len(),
+        top_categories: categories.into_iter()
+            .take(3)
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>()
+            .join(", "),
+        top_changes: release.top_changes.clone(),
+    }
+}
+
+// Package names such as `@acme/dashboard` become a single directory; a short hash of the name
+// keeps `@acme/dashboard` and `acme-dashboard` apart
+fn component_dir(name: &str) -> String {
+    let dir: String = name.trim_start_matches('@')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '-' })
+        .collect();
+    if dir == name {
+        return dir;
+    }
+
+    // FNV-1a, which stays the same between runs and builds
+    let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{}-{:08x}", dir, hash as u32)
+}
+
+fn format_share(share: f64) -> String {
+    format!("{:.1}%", share * 100.0)
+}
+
+fn share(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+fn format_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value.abs() >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::Category;
+    use chrono::TimeZone;
     use std::collections::HashMap;
     use tempfile::TempDir;
 
@@ -172,19 +650,69 @@ mod tests {
         impact_distribution.insert("src/main.rs".to_string(), 8.5);
         impact_distribution.insert("src/lib.rs".to_string(), 6.2);
 
+        // A Saturday night commit
+        let after_hours = vec![Change {
+            commit_id: "late".to_string(),
+            author: "Test Author".to_string(),
+            timestamp: Utc.with_ymd_and_hms(2024, 1, 6, 23, 0, 0).unwrap(),
+            ..Default::default()
+        }];
+
         let analysis = Analysis {
             changes: vec![
                 Change {
                     commit_id: "test1".to_string(),
                     author: "Test Author".to_string(),
+                    author_email: String::new(),
                     timestamp: Utc::now(),
+                    timezone_offset: 0,
                     message: "Test commit".to_string(),
                     file_path: "src/main.rs".into(),
                     diff: "test diff".to_string(),
                     categories: vec![Category::Performance],
+                    category_scores: vec![crate::ml::CategoryScore {
+                        category: Category::Performance,
+                        confidence: 0.65,
+                        evidence: vec!["keyword \"cache\"".to_string()],
+                    }],
                     impact_score: 8.5,
                     metrics: HashMap::new(),
                     annotations: vec![],
+                    fix_inducing: true,
+                    code_kind: crate::testing::CodeKind::Production,
+                    blob: None,
+                    mechanical: None,
+                    origin: Default::default(),
+                    component: None,
+                    signature: None,
+                },
+                Change {
+                    commit_id: "test2".to_string(),
+                    author: "Test Author".to_string(),
+                    author_email: String::new(),
+                    timestamp: Utc::now(),
+                    timezone_offset: 0,
+                    message: "Add logo".to_string(),
+                    file_path: "assets/logo.png".into(),
+                    diff: String::new(),
+                    categories: vec![],
+                    category_scores: Vec::new(),
+                    impact_score: 1.0,
+                    metrics: HashMap::new(),
+                    annotations: vec![],
+                    fix_inducing: false,
+                    code_kind: crate::testing::CodeKind::Production,
+                    blob: Some(crate::blobs::BlobChange {
+                        kind: crate::blobs::BlobKind::Binary,
+                        old_size: 0,
+                        new_size: 2048,
+                        size_delta: 2048,
+                        lfs_oid: None,
+                    }),
+                    mechanical: None,
+                    origin: Default::default(),
+                    component: Some("assets".to_string()),
+                    signature: None,
                 },
             ],
             metrics: crate::analyzer::Metrics {
@@ -194,6 +722,7 @@ mod tests {
                 lines_removed: 5,
                 category_distribution,
                 impact_distribution,
+                mechanical_changes: 0,
             },
             patterns: vec![
                 Pattern {
@@ -205,6 +734,122 @@ mod tests {
                 },
             ],
             cache_info: None,
+            defects: Some(crate::szz::DefectReport {
+                fix_commits: vec!["fix1".to_string()],
+                bug_introducing: vec![crate::szz::BugIntroduction {
+                    commit_id: "test1".to_string(),
+                    fixed_by: vec!["fix1".to_string()],
+                    files: vec!["src/main.rs".into()],
+                }],
+                file_density: vec![FileDefectDensity {
+                    file_path: "src/main.rs".into(),
+                    defects: 1,
+                    changes: 2,
+                    density: 0.5,
+                }],
+            }),
+            dependency_timeline: vec![crate::dependencies::DependencyChange {
+                commit_id: "test1".to_string(),
+                author: "Test Author".to_string(),
+                timestamp: Utc::now(),
+                manifest: "Cargo.toml".into(),
+                ecosystem: crate::dependencies::Ecosystem::Cargo,
+                name: "serde".to_string(),
+                kind: DependencyChangeKind::Updated {
+                    from: "1.0".to_string(),
+                    to: "2.0".to_string(),
+                    bump: VersionBump::Major,
+                },
+            }],
+            large_files: crate::blobs::LargeFileReport {
+                threshold: 1024,
+                large_files: vec![crate::blobs::LargeFile {
+                    file_path: "assets/logo.png".into(),
+                    size: 2048,
+                    lfs: false,
+                    commit_id: "test2".to_string(),
+                    timestamp: Utc::now(),
+                }],
+                blob_growth: vec![],
+            },
+            exclusions: crate::attributes::ExclusionSummary {
+                generated_changes: 1,
+                vendored_changes: 0,
+                files: vec![ExcludedFile {
+                    file_path: "src/schema.rs".into(),
+                    origin: crate::attributes::FileOrigin::Generated,
+                    changes: 1,
+                }],
+            },
+            components: vec![crate::components::ComponentSummary {
+                name: "@acme/assets".to_string(),
+                metrics: crate::analyzer::Metrics {
+                    total_commits: 1,
+                    total_changes: 1,
+                    ..Default::default()
+                },
+                patterns: vec![],
+            }],
+            component_coupling: vec![ComponentCoupling {
+                first: "@acme/assets".to_string(),
+                second: "api".to_string(),
+                co_changes: 3,
+                strength: 0.75,
+            }],
+            releases: vec![
+                crate::releases::ReleaseInterval {
+                    tag: Some("v1.0.0".to_string()),
+                    metrics: crate::analyzer::Metrics {
+                        total_commits: 4,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                crate::releases::ReleaseInterval {
+                    tag: Some("v1.1.0".to_string()),
+                    previous_tag: Some("v1.0.0".to_string()),
+                    metrics: crate::analyzer::Metrics {
+                        total_commits: 1,
+                        ..Default::default()
+                    },
+                    top_changes: vec![TopChange {
+                        commit_id: "test1".to_string(),
+                        file_path: "src/main.rs".into(),
+                        summary: "Speed up parser".to_string(),
+                        impact_score: 8.5,
+                    }],
+                    ..Default::default()
+                },
+            ],
+            message_quality: crate::lint::MessageQuality {
+                score: 200.0 / 3.0,
+                commits: 3,
+                authors: vec![QualityScore {
+                    key: "Test Author".to_string(),
+                    commits: 3,
+                    score: 200.0 / 3.0,
+                }],
+                periods: Vec::new(),
+                rule_failures: [("imperative-mood".to_string(), 2)].into_iter().collect(),
+            },
+            activity: crate::activity::analyze(&after_hours, &Default::default()),
+            filter: Some("impact>5".to_string()),
+            signing: crate::signatures::SigningCoverage {
+                overall: SigningStats {
+                    key: String::new(),
+                    commits: 4,
+                    signed: 2,
+                    verified: 1,
+                },
+                periods: vec![SigningStats {
+                    key: "2024-01".to_string(),
+                    commits: 4,
+                    signed: 2,
+                    verified: 1,
+                }],
+                authors: Vec::new(),
+            },
+            ..Default::default()
         };
 
         // Generate report
@@ -219,5 +864,71 @@ mod tests {
         assert!(content.contains("Code Evolution Report"));
         assert!(content.contains("Test Pattern"));
         assert!(content.contains("Test Author"));
+        assert!(content.contains("Defect Analysis"));
+        assert!(content.contains("Needed a fix"));
+        assert!(content.contains("1.0 -&gt; 2.0 (Major)"));
+        assert!(content.contains("Test Evolution"));
+        assert!(content.contains("Large Files"));
+        assert!(content.contains("2.0 KiB"));
+        assert!(content.contains("Binary change"));
+        assert!(content.contains("Excluded Files"));
+        assert!(content.contains("src/schema.rs"));
+        assert!(content.contains("Components"));
+        let assets = component_dir("@acme/assets");
+        assert!(content.contains(&format!("components/{}/report.html", assets)));
+        assert!(content.contains("Releases"));
+        assert!(content.contains("Top Changes in v1.1.0"));
+        assert!(content.contains("<td class=\"py-2\">-3</td>"));
+        assert!(content.contains("Commit Message Quality"));
+        assert!(content.contains("66.7"));
+        assert!(content.contains("imperative-mood"));
+        assert!(content.contains("Working Hours"));
+        assert!(content.contains("punchCardChart"));
+        assert!(content.contains("<td class=\"py-2 text-red-600\">weekends</td>"));
+        assert!(content.contains("Filter: <code>impact&gt;5</code>"));
+        assert!(content.contains("Commit Signing"));
+        assert!(content.contains("Signed Commits: <span class=\"font-semibold\">50.0%</span>"));
+        assert!(content.contains("Classification Confidence"));
+        assert!(content.contains("<td class=\"py-2\">65.0%</td>"));
+
+        let paths = generator.generate_component_reports(&analysis, &temp_dir.path().join("components")).unwrap();
+        assert_eq!(paths, vec![temp_dir.path().join("components").join(&assets).join("report.html")]);
+        assert!(paths[0].exists());
+
+        let workspace = crate::workspace::Workspace {
+            name: Some("acme".to_string()),
+            repositories: vec![],
+            identities: vec![],
+        };
+        let repo = crate::workspace::WorkspaceRepository {
+            path: "services/api".into(),
+            name: None,
+        };
+        let workspace = crate::workspace::aggregate(&workspace, vec![(repo, analysis)]);
+        let paths = generator.generate_workspace_reports(&workspace, &temp_dir.path().join("workspace")).unwrap();
+        assert_eq!(paths[1], temp_dir.path().join("workspace/repos/api/report.html"));
+        assert!(paths[1].exists());
+
+        let content = std::fs::read_to_string(&paths[0]).unwrap();
+        assert!(content.contains("Repositories"));
+        assert!(content.contains("repos/api/report.html"));
+        assert!(content.contains("api/src/main.rs"));
+    }
+
+    #[test]
+    fn test_component_dir() {
+        assert_eq!(component_dir("api"), "api");
+        assert_eq!(component_dir("acme-dashboard"), "acme-dashboard");
+        let scoped = component_dir("@acme/dashboard");
+        assert!(scoped.starts_with("acme-dashboard-"));
+        assert_ne!(scoped, component_dir("acme/dashboard"));
+        assert_eq!(scoped, component_dir("@acme/dashboard"));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(-5 * 1024 * 1024), "-5.0 MiB");
+    }
+}
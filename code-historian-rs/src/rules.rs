@@ -0,0 +1,416 @@
+use std::path::Path;
+use glob::Pattern;
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+use crate::{Category, Config, HistorianError, Result};
+use crate::config::{ClassificationConfig, RuleDefinition};
+use crate::ignore::compile_patterns;
+use crate::lexer::{self, TokenContext};
+
+// Keyword rules used unless `classification.builtin_rules` is off
+const BUILTIN_RULES: &[(Category, &[&str])] = &[
+    (Category::Architecture, &["class", "struct", "interface", "trait"]),
+    (Category::Api, &["fn ", "pub ", "def ", "function"]),
+    (Category::Logic, &["if ", "match ", "while ", "for "]),
+    (Category::Data, &["type ", "enum ", "struct ", "const "]),
+    (Category::ErrorHandling, &["try", "catch", "throw", "Result"]),
+    (Category::Logging, &["log", "debug!", "info!", "warn!"]),
+    (Category::Documentation, &["///", "/**", "#[doc", "//!"]),
+    (Category::Performance, &["cache", "optimize", "performance", "benchmark"]),
+    (Category::Security, &["encrypt", "decrypt", "auth", "password"]),
+    (Category::Refactoring, &["refactor", "rename", "move", "extract"]),
+    (Category::Configuration, &["config", "env", "setting", "flag"]),
+    (Category::UiUx, &["style", "css", "html", "layout"]),
+    (Category::Accessibility, &["aria-", "role=", "alt=", "a11y"]),
+];
+
+// Built-in keywords that open doc comments, so are looked for in comments rather than code
+const COMMENT_KEYWORDS: &[&str] = &["///", "/**", "//!"];
+
+// File extensions by language, for scoping rules
+const LANGUAGES: &[(&str, &[&str])] = &[
+    ("rust", &["rs"]),
+    ("python", &["py", "pyi"]),
+    ("javascript", &["js", "jsx", "mjs", "cjs"]),
+    ("typescript", &["ts", "tsx", "mts", "cts"]),
+    ("java", &["java"]),
+    ("kotlin", &["kt", "kts"]),
+    ("go", &["go"]),
+    ("c", &["c", "h"]),
+    ("cpp", &["cc", "cpp", "cxx", "hh", "hpp", "hxx"]),
+    ("csharp", &["cs"]),
+    ("ruby", &["rb"]),
+    ("php", &["php"]),
+    ("swift", &["swift"]),
+    ("scala", &["scala"]),
+    ("shell", &["sh", "bash", "zsh"]),
+    ("sql", &["sql"]),
+    ("html", &["html", "htm"]),
+    ("css", &["css", "scss", "sass", "less"]),
+    ("markdown", &["md", "markdown"]),
+    ("toml", &["toml"]),
+    ("yaml", &["yml", "yaml"]),
+    ("json", &["json"]),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternKind {
+    #[default]
+    Literal,
+    Regex,
+}
+
+/// What a rule's pattern is matched against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleTarget {
+    // Changed lines of the diff, one match per line
+    #[default]
+    Diff,
+    Message,
+    Both,
+}
+
+impl RuleTarget {
+    pub fn diff(self) -> bool {
+        matches!(self, RuleTarget::Diff | RuleTarget::Both)
+    }
+
+    pub fn message(self) -> bool {
+        matches!(self, RuleTarget::Message | RuleTarget::Both)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Literal(String),
+    Regex(Regex),
+}
+
+/// A compiled classification rule.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub category: Category,
+    pub target: RuleTarget,
+    pub weight: f64,
+    matcher: Matcher,
+    contexts: Vec<TokenContext>,
+    languages: Vec<String>,
+    paths: Vec<Pattern>,
+    builtin: bool,
+}
+
+impl Rule {
+    pub fn literal(category: Category, pattern: &str) -> Self {
+        let context = if COMMENT_KEYWORDS.contains(&pattern) { TokenContext::Comment } else { TokenContext::Code };
+        Self {
+            name: format!("{}:{}", category_key(&category), pattern.trim()),
+            category,
+            target: RuleTarget::Diff,
+            weight: 1.0,
+            matcher: Matcher::Literal(pattern.to_string()),
+            contexts: vec![context],
+            languages: Vec::new(),
+            paths: Vec::new(),
+            builtin: true,
+        }
+    }
+
+    /// Literal words such as `"env "` match whole words and identifier parts, other literals
+    /// anywhere in the text.
+    pub fn is_match(&self, text: &str) -> bool {
+        match &self.matcher {
+            Matcher::Literal(literal) if lexer::is_word(literal.trim()) => lexer::contains_word(text, literal.trim()),
+            Matcher::Literal(literal) => text.contains(literal.as_str()),
+            Matcher::Regex(regex) => regex.is_match(text),
+        }
+    }
+
+    /// The parts of changed lines the rule looks at.
+    pub fn contexts(&self) -> &[TokenContext] {
+        &self.contexts
+    }
+
+    /// Whether the rule covers a file; scoped rules never apply when the file is unknown.
+    pub fn applies_to(&self, path: Option<&Path>) -> bool {
+        if self.languages.is_empty() && self.paths.is_empty() {
+            return true;
+        }
+        let Some(path) = path else {
+            return false;
+        };
+        let language_ok = self.languages.is_empty()
+            || language(path).is_some_and(|l| self.languages.iter().any(|wanted| wanted == l));
+        let path_ok = self.paths.is_empty() || self.paths.iter().any(|p| p.matches_path(path));
+        language_ok && path_ok
+    }
+
+    /// How the rule is named in a category's evidence.
+    pub fn evidence(&self) -> String {
+        match (&self.matcher, self.builtin) {
+            (Matcher::Literal(literal), true) => format!("keyword {:?}", literal),
+            _ => format!("rule {:?}", self.name),
+        }
+    }
+
+    fn compile(definition: &RuleDefinition) -> std::result::Result<Self, String> {
+        let category: Category = definition.category.parse().map_err(|e: HistorianError| e.to_string())?;
+        if definition.pattern.is_empty() {
+            return Err("pattern is empty".to_string());
+        }
+        let matcher = match definition.kind {
+            PatternKind::Literal => Matcher::Literal(definition.pattern.clone()),
+            PatternKind::Regex => Matcher::Regex(Regex::new(&definition.pattern).map_err(|e| e.to_string())?),
+        };
+        if definition.contexts.is_empty() {
+            return Err("contexts is empty".to_string());
+        }
+        if !definition.weight.is_finite() || definition.weight <= 0.0 {
+            return Err(format!("weight must be a positive number, not {}", definition.weight));
+        }
+        let languages = definition.languages.iter()
+            .map(|l| l.to_lowercase())
+            .collect::<Vec<_>>();
+        if let Some(unknown) = languages.iter().find(|l| !LANGUAGES.iter().any(|(name, _)| name == l)) {
+            return Err(format!("unknown language '{}'", unknown));
+        }
+
+        Ok(Self {
+            name: definition.name.clone().unwrap_or_else(|| definition.pattern.clone()),
+            category,
+            target: definition.target,
+            weight: definition.weight,
+            matcher,
+            contexts: definition.contexts.clone(),
+            languages,
+            paths: compile_patterns(&definition.paths).map_err(|e| e.to_string())?,
+            builtin: false,
+        })
+    }
+}
+
+/// Language of a file from its extension, as named in rule scopes.
+pub fn language(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    LANGUAGES.iter()
+        .find(|(_, extensions)| extensions.contains(&extension.as_str()))
+        .map(|(name, _)| *name)
+}
+
+pub fn builtin_rules() -> Vec<Rule> {
+    BUILTIN_RULES.iter()
+        .flat_map(|(category, patterns)| patterns.iter().map(move |p| Rule::literal(category.clone(), p)))
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Vec<RuleDefinition>,
+}
+
+/// The rules `config` classifies with: built-ins not disabled, then `[[classification.rules]]`,
+/// then the rules file of the repository at `repo_path`, if given.
+///
+/// Every invalid rule is reported in one error.
+pub fn load(config: &Config, repo_path: Option<&Path>) -> Result<Vec<Rule>> {
+    let classification = &config.classification;
+    let mut errors = Vec::new();
+    let mut rules = builtin(classification, &mut errors);
+
+    let mut definitions: Vec<(String, &RuleDefinition)> = classification.rules.iter()
+        .enumerate()
+        .map(|(index, rule)| (format!("classification.rules[{}]", index), rule))
+        .collect();
+    let file = repo_path.and_then(|repo_path| config.rules_file(repo_path))
+        .map(|path| read_rules_file(&path).map(|file| (path, file)))
+        .transpose()?;
+    if let Some((path, file)) = &file {
+        definitions.extend(file.rules.iter()
+            .enumerate()
+            .map(|(index, rule)| (format!("{} rules[{}]", path.display(), index), rule)));
+    }
+
+    for (location, definition) in definitions {
+        match Rule::compile(definition) {
+            Ok(rule) => rules.push(rule),
+            Err(e) => errors.push(format!("{} ({}): {}", location, definition.pattern, e)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(rules)
+    } else {
+        Err(HistorianError::Config(format!("Invalid classification rules: {}", errors.join("; "))))
+    }
+}
+
+// Disabled entries name a category, or a category and keyword such as "configuration:env"
+fn builtin(classification: &ClassificationConfig, errors: &mut Vec<String>) -> Vec<Rule> {
+    if !classification.builtin_rules {
+        return Vec::new();
+    }
+
+    let mut rules = builtin_rules();
+    for entry in &classification.disabled_rules {
+        let (category, keyword) = match entry.split_once(':') {
+            Some((category, keyword)) => (category, Some(keyword)),
+            None => (entry.as_str(), None),
+        };
+        let Ok(category) = category.parse::<Category>() else {
+            errors.push(format!("disabled rule '{}' names no category", entry));
+            continue;
+        };
+        let name = keyword.map(|k| format!("{}:{}", category_key(&category), k.trim()));
+        let before = rules.len();
+        rules.retain(|rule| rule.category != category || name.as_ref().is_some_and(|n| *n != rule.name));
+        if rules.len() == before {
+            errors.push(format!("disabled rule '{}' matches no built-in rule", entry));
+        }
+    }
+    rules
+}
+
+fn read_rules_file(path: &Path) -> Result<RulesFile> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        HistorianError::Config(format!("Cannot read rules file {}: {}", path.display(), e))
+    })?;
+    toml::from_str(&content).map_err(|e| HistorianError::Config(format!("{}: {}", path.display(), e)))
+}
+
+fn category_key(category: &Category) -> String {
+    format!("{:?}", category).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn definition(category: &str, pattern: &str) -> RuleDefinition {
+        RuleDefinition {
+            name: None,
+            category: category.to_string(),
+            pattern: pattern.to_string(),
+            kind: PatternKind::Literal,
+            target: RuleTarget::Diff,
+            contexts: vec![TokenContext::Code],
+            languages: Vec::new(),
+            paths: Vec::new(),
+            weight: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_builtin_rules() {
+        let rules = load(&Config::default(), None).unwrap();
+        assert_eq!(rules.len(), 52);
+        assert!(rules.iter().any(|r| r.name == "configuration:env"));
+
+        let mut config = Config::default();
+        config.classification.disabled_rules = vec!["configuration:env".to_string(), "logging".to_string()];
+        let rules = load(&config, None).unwrap();
+        assert_eq!(rules.len(), 47);
+        assert!(rules.iter().any(|r| r.name == "configuration:config"));
+        assert!(!rules.iter().any(|r| r.category == Category::Logging));
+
+        config.classification.builtin_rules = false;
+        assert!(load(&config, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.classification.builtin_rules = false;
+        config.classification.rules = vec![RuleDefinition {
+            name: Some("sql".to_string()),
+            kind: PatternKind::Regex,
+            contexts: vec![TokenContext::Code, TokenContext::String],
+            languages: vec!["Python".to_string()],
+            paths: vec!["src/db/**".to_string()],
+            weight: 2.0,
+            ..definition("security", r"(?i)execute\(.*%s")
+        }];
+        // Relative to the repository's history directory
+        std::fs::create_dir(temp_dir.path().join(".code-historian")).unwrap();
+        std::fs::write(
+            temp_dir.path().join(".code-historian/rules.toml"),
+            "[[rules]]\ncategory = \"documentation\"\npattern = \"docs:\"\ntarget = \"message\"\n",
+        ).unwrap();
+        config.classification.rules_file = Some("rules.toml".into());
+
+        assert_eq!(load(&config, None).unwrap().len(), 1);
+        let rules = load(&config, Some(temp_dir.path())).unwrap();
+        assert_eq!(rules.len(), 2);
+        let sql = &rules[0];
+        assert!(sql.is_match("cursor.EXECUTE(\"select %s\" % name)"));
+        assert!(sql.applies_to(Some(Path::new("src/db/users.py"))));
+        assert!(!sql.applies_to(Some(Path::new("src/db/users.rs"))));
+        assert!(!sql.applies_to(Some(Path::new("tests/users.py"))));
+        assert!(!sql.applies_to(None));
+        assert_eq!(sql.contexts(), [TokenContext::Code, TokenContext::String]);
+        assert_eq!(sql.evidence(), "rule \"sql\"");
+        assert_eq!(rules[1].target, RuleTarget::Message);
+        assert_eq!(rules[1].name, "docs:");
+        assert!(rules[1].applies_to(None));
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let mut config = Config::default();
+        config.classification.disabled_rules = vec!["colour".to_string(), "logging:println".to_string()];
+        config.classification.rules = vec![
+            RuleDefinition { kind: PatternKind::Regex, ..definition("security", "(unclosed") },
+            definition("colour", "red"),
+            RuleDefinition { weight: 0.0, ..definition("logic", "if") },
+            RuleDefinition { languages: vec!["cobol".to_string()], ..definition("logic", "if") },
+            RuleDefinition { paths: vec!["[".to_string()], ..definition("logic", "if") },
+            RuleDefinition { contexts: Vec::new(), ..definition("logic", "if") },
+        ];
+        let Err(HistorianError::Config(message)) = load(&config, None) else {
+            panic!("invalid rules were accepted");
+        };
+        for expected in [
+            "'colour' names no category",
+            "'logging:println' matches no built-in rule",
+            "classification.rules[0] ((unclosed)",
+            "Unknown category 'colour'",
+            "weight must be a positive number",
+            "unknown language 'cobol'",
+            "classification.rules[4]",
+            "contexts is empty",
+        ] {
+            assert!(message.contains(expected), "{} missing from {}", expected, message);
+        }
+
+        config.classification = ClassificationConfig {
+            rules_file: Some("missing.toml".into()),
+            ..Default::default()
+        };
+        assert!(matches!(load(&config, Some(Path::new("missing"))), Err(HistorianError::Config(_))));
+    }
+
+    #[test]
+    fn test_language() {
+        assert_eq!(language(Path::new("src/lib.rs")), Some("rust"));
+        assert_eq!(language(Path::new("App.TSX")), Some("typescript"));
+        assert_eq!(language(Path::new("Makefile")), None);
+    }
+
+    #[test]
+    fn test_word_rules() {
+        let env = Rule::literal(Category::Configuration, "env");
+        assert!(env.is_match("let env_var = read_env();"));
+        assert!(!env.is_match("let environment = 1;"));
+        assert!(Rule::literal(Category::Api, "fn ").is_match("pub fn new() {"));
+        assert!(!Rule::literal(Category::Api, "fn ").is_match("let fnord = 1;"));
+        assert!(Rule::literal(Category::Logging, "debug!").is_match("debug!(\"x\");"));
+
+        let doc = Rule::literal(Category::Documentation, "///");
+        assert_eq!(doc.contexts(), [TokenContext::Comment]);
+        assert_eq!(env.contexts(), [TokenContext::Code]);
+    }
+}
@@ -0,0 +1,415 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use crate::Result;
+use crate::analyzer::Change;
+use crate::diff::{self, DiffLine};
+
+pub const INDEX_FILE: &str = "search-index.json";
+
+// Bumped whenever tokenization or weighting changes, forcing a rebuild
+const INDEX_VERSION: u32 = 1;
+
+// BM25 parameters
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+// A term in the commit message or path counts for more than one in the diff
+const MESSAGE_WEIGHT: f64 = 3.0;
+const PATH_WEIGHT: f64 = 2.0;
+const DIFF_WEIGHT: f64 = 1.0;
+
+const SNIPPET_CHARS: usize = 160;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Document {
+    commit_id: String,
+    file_path: PathBuf,
+    author: String,
+    timestamp: DateTime<Utc>,
+    summary: String,
+    // Weighted term count, for length normalisation
+    length: f64,
+    // Changed lines kept for snippets
+    lines: Vec<String>,
+}
+
+/// Inverted index over commit messages, file paths and changed lines, one document per change.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchIndex {
+    version: u32,
+    // Commit the index was last brought up to date with
+    head: Option<String>,
+    commits: HashSet<String>,
+    documents: Vec<Document>,
+    // Term to (document, weighted frequency)
+    postings: HashMap<String, Vec<(u32, f64)>>,
+    total_length: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub commit_id: String,
+    pub file_path: PathBuf,
+    pub author: String,
+    pub timestamp: DateTime<Utc>,
+    pub summary: String,
+    pub score: f64,
+    pub snippet: String,
+    // Query terms found in the snippet, for highlighting
+    pub matched_terms: Vec<String>,
+}
+
+impl SearchIndex {
+    /// Loads the index at `path`; a missing or outdated index loads empty.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let index: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if index.version != INDEX_VERSION {
+            return Ok(Self::default());
+        }
+        Ok(index)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn head(&self) -> Option<&str> {
+        self.head.as_deref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Indexes changes from commits not seen before and returns how many were added.
+    ///
+    /// `history` holds every commit in the repository; indexed commits outside it were rewritten
+    /// away and are dropped. Commits left out of a filtered `changes` stay indexed.
+    pub fn update(&mut self, changes: &[Change], history: &HashSet<String>, head: &str, snippet_lines: usize) -> usize {
+        if self.version != INDEX_VERSION {
+            *self = Self { version: INDEX_VERSION, ..Self::default() };
+        }
+        self.retain_commits(history);
+
+        let new: Vec<&Change> = changes.iter().filter(|c| !self.commits.contains(&c.commit_id)).collect();
+        for change in &new {
+            self.add(change, snippet_lines);
+        }
+        self.commits.extend(new.iter().map(|c| c.commit_id.clone()));
+        self.head = Some(head.to_string());
+        new.len()
+    }
+
+    // Drops the documents of commits outside `history` and renumbers the rest
+    fn retain_commits(&mut self, history: &HashSet<String>) {
+        if self.commits.iter().all(|c| history.contains(c)) {
+            return;
+        }
+        self.commits.retain(|c| history.contains(c));
+
+        let mut renumbered = Vec::with_capacity(self.documents.len());
+        let mut kept = Vec::new();
+        for document in std::mem::take(&mut self.documents) {
+            if history.contains(&document.commit_id) {
+                renumbered.push(Some(kept.len() as u32));
+                kept.push(document);
+            } else {
+                renumbered.push(None);
+            }
+        }
+        self.documents = kept;
+        self.total_length = self.documents.iter().map(|d| d.length).sum();
+
+        for postings in self.postings.values_mut() {
+            *postings = postings.iter()
+                .filter_map(|&(doc, frequency)| renumbered[doc as usize].map(|doc| (doc, frequency)))
+                .collect();
+        }
+        self.postings.retain(|_, postings| !postings.is_empty());
+    }
+
+    fn add(&mut self, change: &Change, snippet_lines: usize) {
+        let mut frequencies: HashMap<String, f64> = HashMap::new();
+        let mut record = |text: &str, weight: f64| {
+            for token in tokenize(text) {
+                *frequencies.entry(token).or_default() += weight;
+            }
+        };
+
+        record(&change.message, MESSAGE_WEIGHT);
+        record(&change.file_path.to_string_lossy(), PATH_WEIGHT);
+        for line in changed_lines(&change.diff) {
+            record(line, DIFF_WEIGHT);
+        }
+
+        let doc = self.documents.len() as u32;
+        let length: f64 = frequencies.values().sum();
+        for (term, frequency) in frequencies {
+            self.postings.entry(term).or_default().push((doc, frequency));
+        }
+        self.total_length += length;
+
+        self.documents.push(Document {
+            commit_id: change.commit_id.clone(),
+            file_path: change.file_path.clone(),
+            author: change.author.clone(),
+            timestamp: change.timestamp,
+            summary: change.message.lines().next().unwrap_or_default().to_string(),
+            length,
+            lines: diff::lines(&change.diff)
+                .filter_map(|line| match line {
+                    DiffLine::Added(content) => Some(format!("+{}", content)),
+                    DiffLine::Removed(content) => Some(format!("-{}", content)),
+                    _ => None,
+                })
+                .take(snippet_lines)
+                .collect(),
+        });
+    }
+
+    /// Ranks changes against the terms in `query` with BM25, best match first.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let mut terms = tokenize(query);
+        terms.sort();
+        terms.dedup();
+        if terms.is_empty() || self.documents.is_empty() {
+            return Vec::new();
+        }
+
+        let count = self.documents.len() as f64;
+        let average_length = (self.total_length / count).max(1.0);
+        let mut scores: HashMap<u32, f64> = HashMap::new();
+
+        for term in &terms {
+            let Some(postings) = self.postings.get(term) else {
+                continue;
+            };
+            let matching = postings.len() as f64;
+            let idf = (1.0 + (count - matching + 0.5) / (matching + 0.5)).ln();
+            for &(doc, frequency) in postings {
+                let length = self.documents[doc as usize].length;
+                let norm = K1 * (1.0 - B + B * length / average_length);
+                *scores.entry(doc).or_default() += idf * frequency * (K1 + 1.0) / (frequency + norm);
+            }
+        }
+
+        let mut ranked: Vec<(u32, f64)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked.into_iter()
+            .take(limit)
+            .map(|(doc, score)| self.hit(&self.documents[doc as usize], score, &terms))
+            .collect()
+    }
+
+    fn hit(&self, document: &Document, score: f64, terms: &[String]) -> SearchHit {
+        // The summary or changed line containing the most distinct query terms
+        let (line, matched_terms) = std::iter::once(&document.summary)
+            .chain(&document.lines)
+            .map(|line| {
+                let tokens: HashSet<String> = tokenize(line).into_iter().collect();
+                let matched: Vec<String> = terms.iter().filter(|t| tokens.contains(*t)).cloned().collect();
+                (line, matched)
+            })
+            .fold((&document.summary, Vec::new()), |best, candidate| {
+                if candidate.1.len() > best.1.len() { candidate } else { best }
+            });
+
+        SearchHit {
+            commit_id: document.commit_id.clone(),
+            file_path: document.file_path.clone(),
+            author: document.author.clone(),
+            timestamp: document.timestamp,
+            summary: document.summary.clone(),
+            score,
+            snippet: snippet(line, &matched_terms),
+            matched_terms,
+        }
+    }
+}
+
+/// Lowercased words and identifiers, with compound identifiers also split into their parts.
+///
+/// `parseConfigFile` yields `parse`, `config`, `file` and `parseconfigfile`, so searching for
+/// either the whole identifier or a part of it finds the change.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for word in text.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        let word = word.trim_matches('_');
+        if word.chars().count() < 2 {
+            continue;
+        }
+
+        let whole = word.to_lowercase();
+        let parts = split_identifier(word);
+        if parts.len() > 1 {
+            tokens.extend(parts.into_iter().filter(|p| p.chars().count() >= 2 && *p != whole));
+        }
+        tokens.push(whole);
+    }
+    tokens
+}
+
+// Splits at underscores and case changes: `HTTPServer_config` is `http`, `server`, `config`
+fn split_identifier(word: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    for piece in word.split('_').filter(|p| !p.is_empty()) {
+        let chars: Vec<char> = piece.chars().collect();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (previous, current) = (chars[i - 1], chars[i]);
+            let next_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+            if (previous.is_lowercase() && current.is_uppercase())
+                || (previous.is_uppercase() && current.is_uppercase() && next_lower)
+            {
+                parts.push(chars[start..i].iter().collect::<String>().to_lowercase());
+                start = i;
+            }
+        }
+        parts.push(chars[start..].iter().collect::<String>().to_lowercase());
+    }
+    parts
+}
+
+fn changed_lines(diff: &str) -> impl Iterator<Item = &str> {
+    diff::lines(diff).filter_map(|line| line.changed())
+}
+
+// Trims the line to a window around the first matched term
+fn snippet(line: &str, matched_terms: &[String]) -> String {
+    let line = line.trim();
+    if line.chars().count() <= SNIPPET_CHARS {
+        return line.to_string();
+    }
+
+    let lower = line.to_lowercase();
+    let first_match = matched_terms.iter()
+        .filter_map(|term| lower.find(term.as_str()))
+        .min()
+        .filter(|&at| lower.len() == line.len() && line.is_char_boundary(at))
+        .map(|at| line[..at].chars().count())
+        .unwrap_or(0);
+
+    let start = first_match.saturating_sub(SNIPPET_CHARS / 4);
+    let window: String = line.chars().skip(start).take(SNIPPET_CHARS).collect();
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    snippet.push_str(window.trim());
+    if start + SNIPPET_CHARS < line.chars().count() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(commit_id: &str, path: &str, message: &str, diff: &str) -> Change {
+        Change {
+            commit_id: commit_id.to_string(),
+            author: "Ann".to_string(),
+            message: message.to_string(),
+            file_path: path.into(),
+            diff: diff.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn history(changes: &[Change]) -> HashSet<String> {
+        changes.iter().map(|c| c.commit_id.clone()).collect()
+    }
+
+    fn changes() -> Vec<Change> {
+        vec![
+            change("a", "src/config.rs", "Add config loader\n\nReads TOML files.", "+fn parseConfigFile(path: &Path) {}\n context_line\n"),
+            change("b", "src/net/http.rs", "fix: retry failed requests", "+let retry_count = 3;\n-let attempts = 1;\n"),
+            change("c", "README.md", "Document retries", "+We retry requests three times.\n"),
+        ]
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("parseConfigFile"), vec!["parse", "config", "file", "parseconfigfile"]);
+        assert_eq!(tokenize("HTTPServer retry_count x"), vec!["http", "server", "httpserver", "retry", "count", "retry_count"]);
+        assert_eq!(tokenize("__init__ ok"), vec!["init", "ok"]);
+    }
+
+    #[test]
+    fn test_ranked_search_with_snippets() {
+        let mut index = SearchIndex::default();
+        assert_eq!(index.update(&changes(), &history(&changes()), "c", 10), 3);
+
+        let hits = index.search("retry", 10);
+        assert_eq!(hits.len(), 2);
+        // The message match outweighs the diff-only match
+        assert_eq!(hits[0].commit_id, "b");
+        assert_eq!(hits[0].matched_terms, vec!["retry"]);
+        assert_eq!(hits[1].commit_id, "c");
+        assert!(hits[0].score > hits[1].score);
+
+        // Identifiers match whole or by their parts; context lines are not indexed
+        let hits = index.search("parseConfigFile", 10);
+        assert_eq!(hits[0].commit_id, "a");
+        assert_eq!(hits[0].snippet, "+fn parseConfigFile(path: &Path) {}");
+        assert_eq!(index.search("config", 10)[0].commit_id, "a");
+        assert!(index.search("context_line", 10).is_empty());
+
+        assert_eq!(index.search("retry", 1).len(), 1);
+        assert!(index.search("  ", 10).is_empty());
+    }
+
+    #[test]
+    fn test_incremental_update_and_persistence() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join(INDEX_FILE);
+        assert!(SearchIndex::load(&path).unwrap().is_empty());
+
+        let mut all = changes();
+        let mut index = SearchIndex::default();
+        index.update(&all[..2], &history(&all[..2]), "b", 10);
+        all.push(change("d", "src/net/http.rs", "Tune retry backoff", "+backoff();\n"));
+        assert_eq!(index.update(&all, &history(&all), "d", 10), 2);
+        assert_eq!(index.len(), 4);
+        index.save(&path).unwrap();
+
+        let loaded = SearchIndex::load(&path).unwrap();
+        assert_eq!(loaded.head(), Some("d"));
+        assert_eq!(loaded.search("backoff", 10)[0].commit_id, "d");
+
+        // A filtered analysis leaves the other commits indexed
+        let mut index = loaded;
+        assert_eq!(index.update(&all[3..], &history(&all), "d", 10), 0);
+        assert_eq!(index.len(), 4);
+
+        // Rewritten history drops commits that no longer exist
+        index.update(&all[3..], &history(&all[1..]), "d", 10);
+        assert_eq!(index.len(), 3);
+        assert!(index.search("loader", 10).is_empty());
+        assert_eq!(index.search("backoff", 10)[0].commit_id, "d");
+        assert_eq!(index.search("retry", 10).len(), 3);
+    }
+
+    #[test]
+    fn test_snippet_window() {
+        let line = format!("{} needle {}", "a ".repeat(100), "b ".repeat(100));
+        let snippet = snippet(&line, &["needle".to_string()]);
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains("needle"));
+        assert!(snippet.chars().count() <= SNIPPET_CHARS + 2);
+    }
+}
@@ -0,0 +1,501 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use axum::{
+    extract::{Query as QueryParams, State},
+    http::{HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use tokio::sync::RwLock;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::info;
+use crate::{Result, HistorianError, Analysis, Category, Change, Config};
+use crate::feedback::{self, Correction, FeedbackStore};
+use crate::ml::ChangeClassifier;
+use crate::provenance::{LineProvenance, ProvenanceResolver};
+use crate::query::Query;
+use crate::search::{SearchHit, SearchIndex};
+use crate::symbols::{HistoryTarget, SymbolHistory, SymbolTracker};
+
+const DEFAULT_LIMIT: usize = 100;
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+#[derive(Clone)]
+pub struct ApiState {
+    analysis: Arc<RwLock<Analysis>>,
+    search: Arc<SearchIndex>,
+    // Repository for endpoints that read history directly
+    repository: Option<PathBuf>,
+    config: Arc<Config>,
+    classifier: Arc<ChangeClassifier>,
+}
+
+impl ApiState {
+    pub fn new(analysis: Analysis) -> Self {
+        Self {
+            analysis: Arc::new(RwLock::new(analysis)),
+            search: Arc::new(SearchIndex::default()),
+            repository: None,
+            config: Arc::new(Config::default()),
+            classifier: Arc::new(ChangeClassifier::default()),
+        }
+    }
+
+    pub fn with_search(mut self, index: SearchIndex) -> Self {
+        self.search = Arc::new(index);
+        self
+    }
+
+    pub fn with_repository(mut self, path: PathBuf) -> Self {
+        self.repository = Some(path);
+        self
+    }
+
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = Arc::new(config);
+        self
+    }
+
+    /// Categorizes histories and provenance, normally `ChangeClassifier::from_config`.
+    pub fn with_classifier(mut self, classifier: ChangeClassifier) -> Self {
+        self.classifier = Arc::new(classifier);
+        self
+    }
+
+    fn repository(&self) -> Result<PathBuf> {
+        self.repository.clone()
+            .ok_or_else(|| HistorianError::InvalidArgument("no repository is being served".to_string()))
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ChangesParams {
+    pub filter: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangesResponse {
+    // Matching changes before `limit` and `offset` apply
+    pub total: usize,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchParams {
+    pub q: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct HistoryParams {
+    pub target: Option<String>,
+    pub rev: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ProvenanceParams {
+    pub path: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub rev: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FeedbackRequest {
+    pub commit: String,
+    #[serde(default)]
+    pub file_path: Option<PathBuf>,
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FeedbackResponse {
+    pub correction: Correction,
+    // Served changes whose categories it changed
+    pub changes: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub query: String,
+    pub hits: Vec<SearchHit>,
+}
+
+impl IntoResponse for HistorianError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            HistorianError::Query(_) | HistorianError::InvalidArgument(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+pub fn router(state: ApiState) -> Router {
+    let router = Router::new()
+        .route("/api/changes", get(list_changes))
+        .route("/api/search", get(search))
+        .route("/api/history", get(symbol_history))
+        .route("/api/provenance", get(line_provenance))
+        .route("/api/feedback", post(record_feedback));
+    let router = match cors(&state.config) {
+        Some(cors) => router.layer(cors),
+        None => router,
+    };
+    router.with_state(state)
+}
+
+// Other pages may only read, and only from the configured origins
+fn cors(config: &Config) -> Option<CorsLayer> {
+    let origins: Vec<HeaderValue> = config.server.allowed_origins.iter()
+        .filter_map(|origin| origin.parse().ok())
+        .collect();
+    if origins.is_empty() {
+        return None;
+    }
+    Some(CorsLayer::new().allow_origin(AllowOrigin::list(origins)).allow_methods([Method::GET]))
+}
+
+pub async fn serve(addr: &str, state: ApiState) -> Result<()> {
+    let addr: SocketAddr = addr.parse()
+        .map_err(|e| HistorianError::InvalidArgument(format!("Invalid address '{}': {}", addr, e)))?;
+
+    info!("Serving the API on http://{}", addr);
+    axum::Server::bind(&addr)
+        .serve(router(state).into_make_service())
+        .await
+        .map_err(|e| HistorianError::Io(std::io::Error::other(e)))
+}
+
+async fn list_changes(
+    State(state): State<ApiState>,
+    QueryParams(params): QueryParams<ChangesParams>,
+) -> Result<Json<ChangesResponse>> {
+    let query = Query::parse(params.filter.as_deref().unwrap_or_default())?;
+    let analysis = state.analysis.read().await;
+
+    let matching: Vec<&Change> = query.filter(&analysis.changes).collect();
+    Ok(Json(ChangesResponse {
+        total: matching.len(),
+        changes: matching.into_iter()
+            .skip(params.offset.unwrap_or(0))
+            .take(params.limit.unwrap_or(DEFAULT_LIMIT))
+            .cloned()
+            .collect(),
+    }))
+}
+
+async fn search(
+    State(state): State<ApiState>,
+    QueryParams(params): QueryParams<SearchParams>,
+) -> Result<Json<SearchResponse>> {
+    let query = params.q.unwrap_or_default();
+    if query.trim().is_empty() {
+        return Err(HistorianError::InvalidArgument("missing search query 'q'".to_string()));
+    }
+
+    let hits = state.search.search(&query, params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT));
+    Ok(Json(SearchResponse { query, hits }))
+}
+
+async fn symbol_history(
+    State(state): State<ApiState>,
+    QueryParams(params): QueryParams<HistoryParams>,
+) -> Result<Json<SymbolHistory>> {
+    let repository = state.repository()?;
+    let target: HistoryTarget = params.target.unwrap_or_default().parse()?;
+    let rev = params.rev.unwrap_or_else(|| "HEAD".to_string());
+
+    let history_dir = state.config.history_dir(&repository);
+
+    // Walking history is blocking git work
+    let classifier = state.classifier.clone();
+    let history = tokio::task::spawn_blocking(move || {
+        let repo = git2::Repository::open(&repository)?;
+        SymbolTracker::new(&repo, &classifier)?
+            .with_corrections(FeedbackStore::new(&history_dir).load()?)
+            .history(&target, &rev)
+    })
+    .await
+    .map_err(|e| HistorianError::Analysis(e.to_string()))??;
+    Ok(Json(history))
+}
+
+async fn line_provenance(
+    State(state): State<ApiState>,
+    QueryParams(params): QueryParams<ProvenanceParams>,
+) -> Result<Json<LineProvenance>> {
+    let repository = state.repository()?;
+    let (Some(path), Some(line)) = (params.path, params.line) else {
+        return Err(HistorianError::InvalidArgument("'path' and 'line' are required".to_string()));
+    };
+    let rev = params.rev.unwrap_or_else(|| "HEAD".to_string());
+    let issue_url_template = state.config.changelog.issue_url_template.clone();
+    let history_dir = state.config.history_dir(&repository);
+
+    let classifier = state.classifier.clone();
+    let mut provenance = tokio::task::spawn_blocking(move || {
+        let repo = git2::Repository::open(&repository)?;
+        ProvenanceResolver::new(&repo, &classifier)?
+            .with_issue_url_template(issue_url_template)
+            .with_corrections(FeedbackStore::new(&history_dir).load()?)
+            .resolve(&path, line, &rev)
+    })
+    .await
+    .map_err(|e| HistorianError::Analysis(e.to_string()))??;
+
+    provenance.annotate(&state.analysis.read().await.changes);
+    Ok(Json(provenance))
+}
+
+async fn record_feedback(
+    State(state): State<ApiState>,
+    Json(request): Json<FeedbackRequest>,
+) -> Result<(StatusCode, Json<FeedbackResponse>)> {
+    let repository = state.repository()?;
+    let categories = request.categories.iter()
+        .map(|c| c.parse())
+        .collect::<Result<Vec<Category>>>()?;
+    let history_dir = state.config.history_dir(&repository);
+
+    let correction = tokio::task::spawn_blocking(move || {
+        let commit = feedback::resolve_commit(&git2::Repository::open(&repository)?, &request.commit)?;
+        let correction = Correction::new(commit, categories)
+            .with_file_path(request.file_path)
+            .with_author(request.author);
+        FeedbackStore::new(&history_dir).record(&correction)?;
+        Ok::<_, HistorianError>(correction)
+    })
+    .await
+    .map_err(|e| HistorianError::Analysis(e.to_string()))??;
+
+    let changes = feedback::apply(&mut state.analysis.write().await.changes, std::slice::from_ref(&correction));
+    Ok((StatusCode::CREATED, Json(FeedbackResponse { correction, changes })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    fn analysis() -> Analysis {
+        let change = |commit_id: &str, category: Category, impact_score: f64| Change {
+            commit_id: commit_id.to_string(),
+            message: format!("Change {:?} handling", category),
+            file_path: "src/auth/login.rs".into(),
+            categories: vec![category],
+            impact_score,
+            ..Default::default()
+        };
+        Analysis {
+            changes: vec![
+                change("a", Category::Security, 8.0),
+                change("b", Category::Security, 2.0),
+                change("c", Category::Logic, 9.0),
+            ],
+            ..Default::default()
+        }
+    }
+
+    async fn get(uri: &str) -> (StatusCode, serde_json::Value) {
+        let analysis = analysis();
+        let mut index = SearchIndex::default();
+        let history = analysis.changes.iter().map(|c| c.commit_id.clone()).collect();
+        index.update(&analysis.changes, &history, "c", 10);
+        request(ApiState::new(analysis).with_search(index), uri).await
+    }
+
+    async fn request(state: ApiState, uri: &str) -> (StatusCode, serde_json::Value) {
+        send(state, Request::builder().uri(uri).body(Body::empty()).unwrap()).await
+    }
+
+    async fn post(state: ApiState, uri: &str, body: serde_json::Value) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        send(state, request).await
+    }
+
+    async fn send(state: ApiState, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = router(state).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_list_changes() {
+        let (status, body) = get("/api/changes?filter=category%3Asecurity%20impact%3E5").await;
+        assert_eq!(status, StatusCode::OK);
+        let response: ChangesResponse = serde_json::from_value(body).unwrap();
+        assert_eq!(response.total, 1);
+        assert_eq!(response.changes[0].commit_id, "a");
+
+        let (_, body) = get("/api/changes?limit=1&offset=1").await;
+        let response: ChangesResponse = serde_json::from_value(body).unwrap();
+        assert_eq!(response.total, 3);
+        assert_eq!(response.changes[0].commit_id, "b");
+
+        let (status, body) = get("/api/changes?filter=colour%3Ared").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("unknown filter"));
+    }
+
+    #[tokio::test]
+    async fn test_search() {
+        let (status, body) = get("/api/search?q=logic").await;
+        assert_eq!(status, StatusCode::OK);
+        let response: SearchResponse = serde_json::from_value(body).unwrap();
+        assert_eq!(response.hits.len(), 1);
+        assert_eq!(response.hits[0].commit_id, "c");
+        assert_eq!(response.hits[0].snippet, "Change Logic handling");
+
+        let (_, body) = get("/api/search?q=login&limit=2").await;
+        assert_eq!(body["hits"].as_array().unwrap().len(), 2);
+
+        let (status, _) = get("/api/search").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    fn repository() -> (tempfile::TempDir, git2::Oid) {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let repo = git2::Repository::init(temp_dir.path()).unwrap();
+        std::fs::write(temp_dir.path().join("lib.rs"), "fn parse() {\n    1\n}\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(std::path::Path::new("lib.rs")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let commit = repo.commit(Some("HEAD"), &sig, &sig, "Add parse (#3)", &tree, &[]).unwrap();
+        (temp_dir, commit)
+    }
+
+    #[tokio::test]
+    async fn test_symbol_history() {
+        let (temp_dir, _) = repository();
+        let state = ApiState::new(analysis()).with_repository(temp_dir.path().to_path_buf());
+        let (status, body) = request(state.clone(), "/api/history?target=parse").await;
+        assert_eq!(status, StatusCode::OK);
+        let history: SymbolHistory = serde_json::from_value(body).unwrap();
+        assert_eq!(history.revisions.len(), 1);
+        assert_eq!(history.revisions[0].summary, "Add parse (#3)");
+
+        let (status, _) = request(state, "/api/history?target=missing").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = get("/api/history?target=parse").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_line_provenance() {
+        let (temp_dir, commit) = repository();
+        let analysis = Analysis {
+            changes: vec![Change {
+                commit_id: commit.to_string(),
+                file_path: "lib.rs".into(),
+                impact_score: 3.0,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut config = Config::default();
+        config.changelog.issue_url_template = Some("https://example.com/issues/{id}".to_string());
+        let state = ApiState::new(analysis)
+            .with_repository(temp_dir.path().to_path_buf())
+            .with_config(config);
+
+        let (status, body) = request(state.clone(), "/api/provenance?path=lib.rs&line=2").await;
+        assert_eq!(status, StatusCode::OK);
+        let provenance: LineProvenance = serde_json::from_value(body).unwrap();
+        assert_eq!(provenance.commit_id, commit.to_string());
+        assert_eq!(provenance.content, "    1");
+        assert_eq!(provenance.impact_score, Some(3.0));
+        assert_eq!(provenance.issues[0].url.as_deref(), Some("https://example.com/issues/3"));
+
+        let (status, _) = request(state.clone(), "/api/provenance?path=lib.rs&line=9").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = request(state, "/api/provenance?path=lib.rs").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_record_feedback() {
+        let (temp_dir, commit) = repository();
+        let served = Analysis {
+            changes: vec![Change {
+                commit_id: commit.to_string(),
+                file_path: "lib.rs".into(),
+                categories: vec![Category::Api],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let state = ApiState::new(served).with_repository(temp_dir.path().to_path_buf());
+
+        let short = &commit.to_string()[..8];
+        let body = json!({ "commit": short, "categories": ["logic", "error-handling"], "author": "Ann" });
+        let (status, body) = post(state.clone(), "/api/feedback", body).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let response: FeedbackResponse = serde_json::from_value(body).unwrap();
+        assert_eq!(response.changes, 1);
+        assert_eq!(response.correction.commit, commit.to_string());
+        assert_eq!(response.correction.categories, vec![Category::Logic, Category::ErrorHandling]);
+
+        // Stored in the served repository rather than the working directory
+        let stored = FeedbackStore::new(&temp_dir.path().join(".code-historian")).load().unwrap();
+        assert_eq!(stored, vec![response.correction]);
+        let (_, body) = request(state.clone(), "/api/changes?filter=category%3Aerrorhandling").await;
+        assert_eq!(body["total"], 1);
+
+        // Prefixes that name no single commit are refused
+        for commit in ["a", "deadbeef"] {
+            let (status, _) = post(state.clone(), "/api/feedback", json!({ "commit": commit, "categories": [] })).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+        let (status, _) = post(state, "/api/feedback", json!({ "commit": short, "categories": ["colour"] })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = post(ApiState::new(analysis()), "/api/feedback", json!({ "commit": short, "categories": [] })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    async fn preflight(state: ApiState, uri: &str, method: &str, origin: &str) -> axum::http::HeaderMap {
+        let request = Request::builder()
+            .method("OPTIONS")
+            .uri(uri)
+            .header("origin", origin)
+            .header("access-control-request-method", method)
+            .body(Body::empty())
+            .unwrap();
+        router(state).oneshot(request).await.unwrap().headers().clone()
+    }
+
+    #[tokio::test]
+    async fn test_cors() {
+        // No page may read the API unless configured
+        let headers = preflight(ApiState::new(analysis()), "/api/changes", "GET", "https://example.com").await;
+        assert!(headers.get("access-control-allow-origin").is_none());
+
+        let mut config = Config::default();
+        config.server.allowed_origins = vec!["http://localhost:63342".to_string()];
+        let state = ApiState::new(analysis()).with_config(config);
+        let headers = preflight(state.clone(), "/api/changes", "GET", "http://localhost:63342").await;
+        assert_eq!(headers["access-control-allow-origin"], "http://localhost:63342");
+        let headers = preflight(state.clone(), "/api/changes", "GET", "https://example.com").await;
+        assert!(headers.get("access-control-allow-origin").is_none());
+
+        // Writes stay same-origin
+        let headers = preflight(state, "/api/feedback", "POST", "http://localhost:63342").await;
+        assert_eq!(headers["access-control-allow-methods"], "GET");
+    }
+}
@@ -0,0 +1,351 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use git2::{ErrorCode, Oid, Repository};
+use tempfile::NamedTempFile;
+use serde::{Serialize, Deserialize};
+use crate::{Result, HistorianError};
+use crate::analyzer::Change;
+use crate::config::SignaturesConfig;
+use crate::precommit::Violation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureKind {
+    Gpg,
+    Ssh,
+    X509,
+}
+
+impl SignatureKind {
+    fn detect(signature: &str) -> Option<Self> {
+        let header = signature.trim_start();
+        if header.starts_with("-----BEGIN PGP SIGNATURE-----") {
+            Some(SignatureKind::Gpg)
+        } else if header.starts_with("-----BEGIN SSH SIGNATURE-----") {
+            Some(SignatureKind::Ssh)
+        } else if header.starts_with("-----BEGIN SIGNED MESSAGE-----") {
+            Some(SignatureKind::X509)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureStatus {
+    // Signed, but no keyring is configured to check it against
+    Signed,
+    Verified,
+    Unverified,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitSignature {
+    pub kind: Option<SignatureKind>,
+    pub status: SignatureStatus,
+    // GPG user ID or SSH principal of a verified signature
+    pub signer: Option<String>,
+}
+
+/// Signing coverage for one period or author.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SigningStats {
+    pub key: String,
+    pub commits: usize,
+    pub signed: usize,
+    pub verified: usize,
+}
+
+impl SigningStats {
+    fn record(&mut self, signature: Option<&CommitSignature>) {
+        self.commits += 1;
+        if let Some(signature) = signature {
+            self.signed += 1;
+            if signature.status == SignatureStatus::Verified {
+                self.verified += 1;
+            }
+        }
+    }
+
+    pub fn signed_share(&self) -> f64 {
+        if self.commits == 0 {
+            0.0
+        } else {
+            self.signed as f64 / self.commits as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SigningCoverage {
+    pub overall: SigningStats,
+    pub periods: Vec<SigningStats>,
+    pub authors: Vec<SigningStats>,
+}
+
+pub struct SignatureVerifier<'a> {
+    config: &'a SignaturesConfig,
+    gpg_keyring: Option<PathBuf>,
+    allowed_signers: Option<PathBuf>,
+}
+
+impl<'a> SignatureVerifier<'a> {
+    /// Relative keyring paths are resolved against `base`, usually the repository root.
+    pub fn new(config: &'a SignaturesConfig, base: &Path) -> Self {
+        Self {
+            config,
+            gpg_keyring: config.gpg_keyring.as_ref().map(|p| base.join(p)),
+            allowed_signers: config.allowed_signers.as_ref().map(|p| base.join(p)),
+        }
+    }
+
+    /// Extract and verify a commit's signature; `None` if the commit is unsigned.
+    pub fn verify(&self, repo: &Repository, commit_id: Oid) -> Result<Option<CommitSignature>> {
+        let (signature, signed_data) = match repo.extract_signature(&commit_id, None) {
+            Ok(extracted) => extracted,
+            Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let signature_text = String::from_utf8_lossy(&signature);
+        let kind = SignatureKind::detect(&signature_text);
+
+        let checked = match (kind, &self.gpg_keyring, &self.allowed_signers) {
+            (Some(SignatureKind::Gpg), Some(keyring), _) => {
+                Some(self.verify_gpg(keyring, &signature, &signed_data)?)
+            }
+            (Some(SignatureKind::Ssh), _, Some(allowed_signers)) => {
+                Some(self.verify_ssh(allowed_signers, &signature, &signed_data)?)
+            }
+            _ => None,
+        };
+
+        Ok(Some(match checked {
+            Some(Some(signer)) => CommitSignature { kind, status: SignatureStatus::Verified, signer: Some(signer) },
+            Some(None) => CommitSignature { kind, status: SignatureStatus::Unverified, signer: None },
+            None => CommitSignature { kind, status: SignatureStatus::Signed, signer: None },
+        }))
+    }
+
+    // Returns the signer's user ID when gpg reports a good signature
+    fn verify_gpg(&self, keyring: &Path, signature: &[u8], data: &[u8]) -> Result<Option<String>> {
+        let signature_file = signature_file(signature)?;
+        let mut command = Command::new(&self.config.gpg_program);
+        command.arg("--status-fd=1")
+            .arg("--no-default-keyring")
+            .arg("--keyring")
+            .arg(keyring)
+            .arg("--verify")
+            .arg(signature_file.path())
+            .arg("-");
+
+        let (_, stdout) = run_with_input(&mut command, data)?;
+        Ok(stdout.lines()
+            .find_map(|line| line.strip_prefix("[GNUPG:] GOODSIG "))
+            .map(|rest| rest.split_once(' ').map_or(rest, |(_, user_id)| user_id).to_string()))
+    }
+
+    // Returns the matching principal when ssh-keygen accepts the signature
+    fn verify_ssh(&self, allowed_signers: &Path, signature: &[u8], data: &[u8]) -> Result<Option<String>> {
+        let signature_file = signature_file(signature)?;
+
+        let mut find = Command::new(&self.config.ssh_program);
+        find.args(["-Y", "find-principals", "-f"])
+            .arg(allowed_signers)
+            .arg("-s")
+            .arg(signature_file.path());
+        let (found, principals) = run_with_input(&mut find, &[])?;
+        let Some(principal) = principals.lines().next().filter(|_| found) else {
+            return Ok(None);
+        };
+
+        let mut verify = Command::new(&self.config.ssh_program);
+        verify.args(["-Y", "verify", "-n", "git", "-f"])
+            .arg(allowed_signers)
+            .arg("-I")
+            .arg(principal)
+            .arg("-s")
+            .arg(signature_file.path());
+        let (verified, _) = run_with_input(&mut verify, data)?;
+        Ok(verified.then(|| principal.to_string()))
+    }
+}
+
+// The verifiers read signatures from a file; an unpredictable name keeps other users from
+// planting one, and it is removed when dropped
+fn signature_file(signature: &[u8]) -> Result<NamedTempFile> {
+    let mut file = tempfile::Builder::new()
+        .prefix("code-historian-")
+        .suffix(".sig")
+        .tempfile()?;
+    file.write_all(signature)?;
+    Ok(file)
+}
+
+fn run_with_input(command: &mut Command, input: &[u8]) -> Result<(bool, String)> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| HistorianError::Config(format!("Cannot run {}: {}", program, e)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input)?;
+    }
+    let output = child.wait_with_output()?;
+    Ok((output.status.success(), String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+/// Signing coverage per period and per author, counting each commit once.
+pub fn coverage(changes: &[Change], config: &SignaturesConfig) -> SigningCoverage {
+    let mut coverage = SigningCoverage::default();
+    let mut periods: BTreeMap<String, SigningStats> = BTreeMap::new();
+    let mut authors: HashMap<&str, SigningStats> = HashMap::new();
+    let mut seen = HashSet::new();
+
+    for change in changes {
+        if !seen.insert(change.commit_id.as_str()) {
+            continue;
+        }
+
+        let signature = change.signature.as_ref();
+        coverage.overall.record(signature);
+        periods.entry(config.period.key(&change.timestamp)).or_default().record(signature);
+        authors.entry(change.author.as_str()).or_default().record(signature);
+    }
+
+    coverage.periods = periods.into_iter()
+        .map(|(key, stats)| SigningStats { key, ..stats })
+        .collect();
+    coverage.authors = authors.into_iter()
+        .map(|(name, stats)| SigningStats { key: name.to_string(), ..stats })
+        .collect();
+    coverage.authors.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.key.cmp(&b.key)));
+    coverage
+}
+
+/// Commits in `range`, either `from..to` or everything reachable from a single rev.
+pub fn commits_in_range(repo: &Repository, range: &str) -> Result<Vec<Oid>> {
+    let mut revwalk = repo.revwalk()?;
+    if range.contains("..") {
+        revwalk.push_range(range)?;
+    } else {
+        revwalk.push(repo.revparse_single(range)?.peel_to_commit()?.id())?;
+    }
+    revwalk.map(|oid| oid.map_err(Into::into)).collect()
+}
+
+/// Check commits against the signing policy: unsigned commits always fail,
+/// and with `require_verified` so do signatures that do not verify.
+pub fn check_policy(
+    repo: &Repository,
+    verifier: &SignatureVerifier,
+    commits: &[Oid],
+    require_verified: bool,
+) -> Result<Vec<Violation>> {
+    let mut violations = Vec::new();
+
+    for &commit_id in commits {
+        let commit = repo.find_commit(commit_id)?;
+        let problem = match verifier.verify(repo, commit_id)? {
+            None => Some("is not signed"),
+            Some(signature) if require_verified && signature.status != SignatureStatus::Verified => {
+                Some("has a signature that could not be verified")
+            }
+            Some(_) => None,
+        };
+
+        if let Some(problem) = problem {
+            let id = commit_id.to_string();
+            violations.push(Violation {
+                file_path: None,
+                message: format!("commit {} ({}) {}", &id[..8], commit.summary().unwrap_or(""), problem),
+            });
+        }
+    }
+
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use tempfile::TempDir;
+
+    const PGP_SIGNATURE: &str = "-----BEGIN PGP SIGNATURE-----\n\niQEzBAABCAAdFiEE\n-----END PGP SIGNATURE-----";
+
+    fn commit(repo: &Repository, message: &str, signature: Option<&str>) -> Oid {
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let parents: Vec<git2::Commit> = repo.head().ok()
+            .and_then(|h| h.peel_to_commit().ok())
+            .into_iter()
+            .collect();
+        let parents: Vec<&git2::Commit> = parents.iter().collect();
+
+        let buffer = repo.commit_create_buffer(&sig, &sig, message, &tree, &parents).unwrap();
+        let oid = match signature {
+            Some(signature) => repo.commit_signed(buffer.as_str().unwrap(), signature, None).unwrap(),
+            None => repo.odb().unwrap().write(git2::ObjectType::Commit, &buffer).unwrap(),
+        };
+        repo.reference("refs/heads/main", oid, true, "test").unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+        oid
+    }
+
+    #[test]
+    fn test_extract_and_policy() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let config = SignaturesConfig::default();
+        let verifier = SignatureVerifier::new(&config, temp_dir.path());
+
+        let first = commit(&repo, "first", Some(PGP_SIGNATURE));
+        let second = commit(&repo, "second", None);
+
+        let signature = verifier.verify(&repo, first).unwrap().unwrap();
+        assert_eq!(signature.kind, Some(SignatureKind::Gpg));
+        assert_eq!(signature.status, SignatureStatus::Signed);
+        assert!(verifier.verify(&repo, second).unwrap().is_none());
+
+        let commits = commits_in_range(&repo, "main").unwrap();
+        assert_eq!(commits, vec![second, first]);
+        assert_eq!(commits_in_range(&repo, &format!("{}..main", first)).unwrap(), vec![second]);
+
+        let violations = check_policy(&repo, &verifier, &commits, false).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].message.contains("(second) is not signed"));
+        assert_eq!(check_policy(&repo, &verifier, &commits, true).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_coverage() {
+        let change = |commit_id: &str, author: &str, month: u32, status: Option<SignatureStatus>| Change {
+            commit_id: commit_id.to_string(),
+            author: author.to_string(),
+            timestamp: Utc.with_ymd_and_hms(2024, month, 1, 0, 0, 0).unwrap(),
+            signature: status.map(|status| CommitSignature { kind: Some(SignatureKind::Ssh), status, signer: None }),
+            ..Default::default()
+        };
+        let changes = vec![
+            change("a", "Ann", 1, Some(SignatureStatus::Verified)),
+            change("a", "Ann", 1, Some(SignatureStatus::Verified)),
+            change("b", "Ann", 1, None),
+            change("c", "Bob", 2, Some(SignatureStatus::Unverified)),
+        ];
+
+        let coverage = coverage(&changes, &SignaturesConfig::default());
+        assert_eq!(coverage.overall.commits, 3);
+        assert_eq!(coverage.overall.signed, 2);
+        assert_eq!(coverage.overall.verified, 1);
+        assert_eq!(coverage.periods[0].key, "2024-01");
+        assert_eq!(coverage.periods[0].signed_share(), 0.5);
+        assert_eq!(coverage.authors[0].key, "Ann");
+        assert_eq!(coverage.authors[1].signed, 1);
+    }
+}
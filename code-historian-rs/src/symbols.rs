@@ -0,0 +1,742 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use chrono::{DateTime, Utc};
+use git2::{Commit, DiffFindOptions, DiffOptions, Mailmap, ObjectType, Oid, Patch, Repository, Tree};
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+use crate::{Result, HistorianError, Category};
+use crate::analyzer::{self, Change};
+use crate::feedback::{self, Correction};
+use crate::ml::ChangeClassifier;
+
+// Files larger than this are not searched for definitions
+const MAX_SEARCH_BYTES: usize = 1024 * 1024;
+
+// How far a definition header may run before its opening brace
+const MAX_HEADER_LINES: usize = 10;
+
+// Share of lines a definition must keep to count as moved from another file rather than new
+const MIN_MOVE_SIMILARITY: f64 = 0.7;
+
+const DEFINITION_KEYWORDS: &str = "fn|def|function|func|class|struct|enum|union|trait|interface|type|mod|module|object";
+const CONTAINER_KEYWORDS: &str = "impl|class|struct|enum|trait|interface|mod|module|object|namespace";
+
+/// What to follow through history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", tag = "kind")]
+pub enum HistoryTarget {
+    // `analyzer::Analyzer::analyze`; the last segment is the symbol, the others its modules or types
+    Symbol { path: Vec<String> },
+    // `src/lib.rs:10-40`, 1-based and inclusive
+    Lines { file: PathBuf, start: usize, end: usize },
+}
+
+impl FromStr for HistoryTarget {
+    type Err = HistorianError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some((file, range)) = s.rsplit_once(':') {
+            if let Some((start, end)) = parse_range(range) {
+                if file.is_empty() || start == 0 || start > end {
+                    return Err(HistorianError::InvalidArgument(format!("Invalid line range '{}'", s)));
+                }
+                return Ok(HistoryTarget::Lines { file: PathBuf::from(file), start, end });
+            }
+        }
+
+        let separator = if s.contains("::") { "::" } else { "." };
+        let path: Vec<String> = s.split(separator).map(|segment| segment.trim().to_string()).collect();
+        // Qualifiers may also be directory names, which can contain hyphens
+        let valid = |c: char| c.is_alphanumeric() || matches!(c, '_' | '$' | '-');
+        if path.iter().any(|segment| segment.is_empty() || !segment.chars().all(valid)) {
+            return Err(HistorianError::InvalidArgument(format!(
+                "Invalid history target '{}'; expected a symbol such as 'module::Type::method' or 'path:start-end'",
+                s
+            )));
+        }
+        Ok(HistoryTarget::Symbol { path })
+    }
+}
+
+impl std::fmt::Display for HistoryTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HistoryTarget::Symbol { path } => write!(f, "{}", path.join("::")),
+            HistoryTarget::Lines { file, start, end } => write!(f, "{}:{}-{}", file.display(), start, end),
+        }
+    }
+}
+
+// `10-40`, `10,40` or a single line
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(['-', ',']) {
+        Some((start, end)) => Some((start.trim().parse().ok()?, end.trim().parse().ok()?)),
+        None => range.trim().parse().ok().map(|line| (line, line)),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RevisionKind {
+    Added,
+    Modified,
+    // Only its location changed
+    Moved,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolRevision {
+    pub commit_id: String,
+    pub author: String,
+    pub timestamp: DateTime<Utc>,
+    pub summary: String,
+    pub kind: RevisionKind,
+    pub file_path: PathBuf,
+    // Where it lived before a move or rename
+    pub previous_path: Option<PathBuf>,
+    pub start_line: usize,
+    pub end_line: usize,
+    // Unified diff of just the symbol or line range
+    pub diff: String,
+    pub categories: Vec<Category>,
+}
+
+/// Commits that touched a symbol or line range, newest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolHistory {
+    pub target: HistoryTarget,
+    pub revisions: Vec<SymbolRevision>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Location {
+    path: PathBuf,
+    // 1-based and inclusive
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+/// Follows a symbol or line range back through first-parent history, across file renames
+/// and moves between files, like `git log -L` with language-agnostic symbol lookup.
+pub struct SymbolTracker<'a> {
+    repo: &'a Repository,
+    classifier: &'a ChangeClassifier,
+    mailmap: Mailmap,
+    corrections: Vec<Correction>,
+    // Commit the walk stops before, with its commit time
+    since: Option<(Oid, i64)>,
+}
+
+impl<'a> SymbolTracker<'a> {
+    pub fn new(repo: &'a Repository, classifier: &'a ChangeClassifier) -> Result<Self> {
+        Ok(Self { repo, classifier, mailmap: repo.mailmap()?, corrections: Vec::new(), since: None })
+    }
+
+    /// Feedback corrections, which override the categories of the commits they cover.
+    pub fn with_corrections(mut self, corrections: Vec<Correction>) -> Self {
+        self.corrections = corrections;
+        self
+    }
+
+    /// Leaves out `commit` and what came before it. When `commit` is not on the first-parent
+    /// chain, the walk stops at the first commit committed before it.
+    pub fn since(mut self, commit: &Commit) -> Self {
+        self.since = Some((commit.id(), commit.time().seconds()));
+        self
+    }
+
+    pub fn history(&self, target: &HistoryTarget, rev: &str) -> Result<SymbolHistory> {
+        let mut commit = self.repo.revparse_single(rev)?.peel_to_commit()?;
+        let mut current = self.locate_at_start(target, &commit.tree()?)?;
+        let mut revisions = Vec::new();
+
+        loop {
+            if let Some((since, time)) = self.since {
+                if commit.id() == since || commit.time().seconds() < time {
+                    break;
+                }
+            }
+
+            let Ok(parent) = commit.parent(0) else {
+                revisions.push(self.revision(&commit, RevisionKind::Added, &current, None)?);
+                break;
+            };
+
+            let (tree, parent_tree) = (commit.tree()?, parent.tree()?);
+            if blob_id(&tree, &current.path) == blob_id(&parent_tree, &current.path) {
+                commit = parent;
+                continue;
+            }
+
+            let Some(previous) = self.locate_in_parent(target, &current, &commit, &parent_tree)? else {
+                revisions.push(self.revision(&commit, RevisionKind::Added, &current, None)?);
+                break;
+            };
+
+            let moved = previous.path != current.path;
+            if previous.text != current.text {
+                revisions.push(self.revision(&commit, RevisionKind::Modified, &current, Some(&previous))?);
+            } else if moved {
+                revisions.push(self.revision(&commit, RevisionKind::Moved, &current, Some(&previous))?);
+            }
+
+            current = previous;
+            commit = parent;
+        }
+
+        Ok(SymbolHistory { target: target.clone(), revisions })
+    }
+
+    fn locate_at_start(&self, target: &HistoryTarget, tree: &Tree) -> Result<Location> {
+        match target {
+            HistoryTarget::Lines { file, start, end } => {
+                let content = read_file(self.repo, tree, file)?
+                    .ok_or_else(|| HistorianError::InvalidArgument(format!("{} does not exist", file.display())))?;
+                let lines: Vec<&str> = content.lines().collect();
+                if *end > lines.len() {
+                    return Err(HistorianError::InvalidArgument(format!(
+                        "{} has only {} lines",
+                        file.display(),
+                        lines.len()
+                    )));
+                }
+                Ok(location(file, &lines, *start, *end))
+            }
+            HistoryTarget::Symbol { path } => {
+                let mut found = self.search_tree(tree, path)?;
+                match found.len() {
+                    0 => Err(HistorianError::InvalidArgument(format!("Symbol '{}' not found", target))),
+                    1 => Ok(found.remove(0)),
+                    _ => Err(HistorianError::InvalidArgument(format!(
+                        "Symbol '{}' is ambiguous; qualify it further: {}",
+                        target,
+                        found.iter()
+                            .take(5)
+                            .map(|l| format!("{}:{}", l.path.display(), l.start))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))),
+                }
+            }
+        }
+    }
+
+    // Where `current` was in the parent revision, or None if the commit introduced it
+    fn locate_in_parent(
+        &self,
+        target: &HistoryTarget,
+        current: &Location,
+        commit: &Commit,
+        parent_tree: &Tree,
+    ) -> Result<Option<Location>> {
+        let old_path = if blob_id(parent_tree, &current.path).is_some() {
+            Some(current.path.clone())
+        } else {
+            self.renamed_from(commit, parent_tree, &current.path)?
+        };
+
+        match target {
+            HistoryTarget::Lines { .. } => {
+                let Some(old_path) = old_path else {
+                    return Ok(None);
+                };
+                let old = read_file(self.repo, parent_tree, &old_path)?.unwrap_or_default();
+                let new = read_file(self.repo, &commit.tree()?, &current.path)?.unwrap_or_default();
+                let (start, end) = map_range(&old, &new, current.start, current.end)?;
+                let lines: Vec<&str> = old.lines().collect();
+                if start == 0 || start > end || start > lines.len() {
+                    return Ok(None);
+                }
+                Ok(Some(location(&old_path, &lines, start, end.min(lines.len()))))
+            }
+            HistoryTarget::Symbol { path } => {
+                if let Some(old_path) = &old_path {
+                    if let Some(content) = read_file(self.repo, parent_tree, old_path)? {
+                        if let Some(found) = find_definitions(&content, old_path, path).into_iter().next() {
+                            return Ok(Some(found));
+                        }
+                    }
+                }
+
+                // Moved from another file: only a like body from a file this commit changed, since
+                // common names such as `new` are defined all over the tree
+                let tree = commit.tree()?;
+                let moved_from = self.search_tree(parent_tree, path)?
+                    .into_iter()
+                    .filter(|l| blob_id(&tree, &l.path) != blob_id(parent_tree, &l.path))
+                    .map(|l| (similarity(&l.text, &current.text), l))
+                    .filter(|(similarity, _)| *similarity >= MIN_MOVE_SIMILARITY)
+                    .max_by(|(a, _), (b, _)| a.total_cmp(b));
+                Ok(moved_from.map(|(_, location)| location))
+            }
+        }
+    }
+
+    fn renamed_from(&self, commit: &Commit, parent_tree: &Tree, path: &Path) -> Result<Option<PathBuf>> {
+        let mut diff = self.repo.diff_tree_to_tree(Some(parent_tree), Some(&commit.tree()?), Some(&mut DiffOptions::new()))?;
+        diff.find_similar(Some(DiffFindOptions::new().renames(true).copies(true)))?;
+
+        Ok(diff.deltas()
+            .filter(|d| matches!(d.status(), git2::Delta::Renamed | git2::Delta::Copied))
+            .find(|d| d.new_file().path() == Some(path))
+            .and_then(|d| d.old_file().path().map(Path::to_path_buf)))
+    }
+
+    fn search_tree(&self, tree: &Tree, symbol: &[String]) -> Result<Vec<Location>> {
+        let name = &symbol[symbol.len() - 1];
+        let mut blobs: Vec<(PathBuf, Oid)> = Vec::new();
+        tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+            if entry.kind() == Some(ObjectType::Blob) {
+                if let Some(name) = entry.name() {
+                    blobs.push((Path::new(dir).join(name), entry.id()));
+                }
+            }
+            git2::TreeWalkResult::Ok
+        })?;
+
+        let mut found = Vec::new();
+        for (path, id) in blobs {
+            let blob = self.repo.find_blob(id)?;
+            if blob.is_binary() || blob.size() > MAX_SEARCH_BYTES {
+                continue;
+            }
+            let Ok(content) = std::str::from_utf8(blob.content()) else {
+                continue;
+            };
+            if content.contains(name.as_str()) {
+                found.extend(find_definitions(content, &path, symbol));
+            }
+        }
+        Ok(found)
+    }
+
+    fn revision(
+        &self,
+        commit: &Commit,
+        kind: RevisionKind,
+        current: &Location,
+        previous: Option<&Location>,
+    ) -> Result<SymbolRevision> {
+        let old_path = previous.map_or(current.path.as_path(), |p| p.path.as_path());
+        let mut patch = Patch::from_buffers(
+            previous.map_or("", |p| p.text.as_str()).as_bytes(),
+            Some(old_path),
+            current.text.as_bytes(),
+            Some(current.path.as_path()),
+            None,
+        )?;
+        let diff = String::from_utf8_lossy(&patch.to_buf()?).into_owned();
+
+        // Categorized as an analysis would the commit's change to the file, from just these lines
+        let mut change = Change {
+            commit_id: commit.id().to_string(),
+            file_path: current.path.clone(),
+            message: commit.message().unwrap_or("").to_string(),
+            diff: diff.clone(),
+            ..Default::default()
+        };
+        analyzer::categorize_change(self.classifier, &mut change)?;
+        feedback::apply(std::slice::from_mut(&mut change), &self.corrections);
+
+        let author = commit.author_with_mailmap(&self.mailmap)?;
+        Ok(SymbolRevision {
+            commit_id: commit.id().to_string(),
+            author: author.name().unwrap_or("unknown").to_string(),
+            timestamp: DateTime::from_timestamp(author.when().seconds(), 0).unwrap_or_else(Utc::now),
+            summary: commit.summary().unwrap_or("").to_string(),
+            kind,
+            file_path: current.path.clone(),
+            previous_path: previous.map(|p| p.path.clone()).filter(|p| *p != current.path),
+            start_line: current.start,
+            end_line: current.end,
+            categories: change.categories,
+            diff,
+        })
+    }
+}
+
+/// History of `target` up to `rev`, categorized with `classifier`.
+pub fn history(repo: &Repository, classifier: &ChangeClassifier, target: &HistoryTarget, rev: &str) -> Result<SymbolHistory> {
+    SymbolTracker::new(repo, classifier)?.history(target, rev)
+}
+
+fn blob_id(tree: &Tree, path: &Path) -> Option<Oid> {
+    tree.get_path(path).ok().map(|entry| entry.id())
+}
+
+fn read_file(repo: &Repository, tree: &Tree, path: &Path) -> Result<Option<String>> {
+    let Some(id) = blob_id(tree, path) else {
+        return Ok(None);
+    };
+    let blob = repo.find_blob(id)?;
+    Ok(Some(String::from_utf8_lossy(blob.content()).into_owned()))
+}
+
+fn location(path: &Path, lines: &[&str], start: usize, end: usize) -> Location {
+    Location {
+        path: path.to_path_buf(),
+        start,
+        end,
+        text: lines[start - 1..end].iter().map(|line| format!("{}\n", line)).collect(),
+    }
+}
+
+// Maps new lines `start..=end` to the parent's numbering; an empty result means they were all added
+fn map_range(old: &str, new: &str, start: usize, end: usize) -> Result<(usize, usize)> {
+    let mut opts = DiffOptions::new();
+    opts.context_lines(0);
+    let patch = Patch::from_buffers(old.as_bytes(), None, new.as_bytes(), None, Some(&mut opts))?;
+
+    let mut hunks = Vec::new();
+    for idx in 0..patch.num_hunks() {
+        let (hunk, _) = patch.hunk(idx)?;
+        hunks.push((
+            hunk.old_start() as usize,
+            hunk.old_lines() as usize,
+            hunk.new_start() as usize,
+            hunk.new_lines() as usize,
+        ));
+    }
+
+    let map = |line: usize, is_start: bool| {
+        let mut shifted = line;
+        for &(old_start, old_lines, new_start, new_lines) in &hunks {
+            // A side without lines is positioned after its start line
+            let (new_first, new_end) = if new_lines > 0 { (new_start, new_start + new_lines) } else { (new_start + 1, new_start + 1) };
+            let old_end = if old_lines > 0 { old_start + old_lines } else { old_start + 1 };
+            if line < new_first {
+                break;
+            }
+            if line < new_end {
+                return match (is_start, old_lines > 0) {
+                    (true, true) => old_start,
+                    (true, false) => old_start + 1,
+                    (false, true) => old_start + old_lines - 1,
+                    (false, false) => old_start,
+                };
+            }
+            shifted = line + old_end - new_end;
+        }
+        shifted
+    };
+
+    Ok((map(start, true), map(end, false)))
+}
+
+// Definitions of the symbol's last segment whose other segments match the file path or an
+// enclosing type or module
+fn find_definitions(content: &str, path: &Path, symbol: &[String]) -> Vec<Location> {
+    let (name, qualifiers) = symbol.split_last().expect("symbol paths are never empty");
+    let Ok(definition) = Regex::new(&format!(
+        r#"^\s*(?:[\w()"]+\s+)*?(?:{})\s+(?:\([^)]*\)\s*)?{}\b"#,
+        DEFINITION_KEYWORDS,
+        regex::escape(name)
+    )) else {
+        return Vec::new();
+    };
+
+    let lines: Vec<&str> = content.lines().collect();
+    let path_segments: Vec<String> = path.iter()
+        .map(|segment| Path::new(segment).file_stem().unwrap_or(segment).to_string_lossy().into_owned())
+        .collect();
+
+    (0..lines.len())
+        .filter(|&i| definition.is_match(lines[i]))
+        .filter(|&i| {
+            qualifiers.iter()
+                .filter(|q| !matches!(q.as_str(), "crate" | "self" | "super"))
+                .all(|q| path_segments.contains(q) || is_enclosed_by(&lines, i, q))
+        })
+        .map(|i| {
+            let start = leading_annotations(&lines, i);
+            location(path, &lines, start + 1, block_end(&lines, i) + 1)
+        })
+        .collect()
+}
+
+fn is_enclosed_by(lines: &[&str], line: usize, container: &str) -> bool {
+    let Ok(header) = Regex::new(&format!(
+        r#"^\s*(?:[\w()"]+\s+)*?(?:{})\b[^{{;]*\b{}\b"#,
+        CONTAINER_KEYWORDS,
+        regex::escape(container)
+    )) else {
+        return false;
+    };
+
+    (0..line).any(|i| header.is_match(lines[i]) && block_end(lines, i) >= line)
+}
+
+// Doc comments, attributes and decorators directly above a definition belong to it
+fn leading_annotations(lines: &[&str], line: usize) -> usize {
+    let mut start = line;
+    while start > 0 {
+        let previous = lines[start - 1].trim_start();
+        if previous.starts_with("///") || previous.starts_with("#[") || previous.starts_with('@') {
+            start -= 1;
+        } else {
+            break;
+        }
+    }
+    start
+}
+
+// Last line of the block opened at `line`: brace-delimited, or indented as in Python
+fn block_end(lines: &[&str], line: usize) -> usize {
+    let header = lines[line].trim_end();
+    if header.ends_with(':') && !header.contains('{') {
+        let indent = indentation(lines[line]);
+        let mut end = line;
+        for (i, text) in lines.iter().enumerate().skip(line + 1) {
+            if text.trim().is_empty() {
+                continue;
+            }
+            if indentation(text) <= indent {
+                break;
+            }
+            end = i;
+        }
+        return end;
+    }
+
+    let mut depth = 0usize;
+    let mut opened = false;
+    for (i, text) in lines.iter().enumerate().skip(line) {
+        let mut in_string = false;
+        let mut previous = ' ';
+        let chars: Vec<char> = text.chars().collect();
+        let mut j = 0;
+        while j < chars.len() {
+            let c = chars[j];
+            j += 1;
+            match c {
+                '"' if previous != '\\' => in_string = !in_string,
+                '\'' if !in_string => {
+                    j += char_literal_len(&chars[j - 1..]).saturating_sub(1);
+                }
+                '/' if !in_string && previous == '/' => break,
+                '{' if !in_string => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' if !in_string && depth > 0 => {
+                    depth -= 1;
+                    if opened && depth == 0 {
+                        return i;
+                    }
+                }
+                ';' if !in_string && !opened => return i,
+                _ => {}
+            }
+            previous = c;
+        }
+        if !opened && i >= line + MAX_HEADER_LINES {
+            return line;
+        }
+    }
+    if opened { lines.len() - 1 } else { line }
+}
+
+// Share of lines two bodies have in common, ignoring indentation; 1.0 when identical
+fn similarity(a: &str, b: &str) -> f64 {
+    let mut remaining: HashMap<&str, usize> = HashMap::new();
+    for line in a.lines() {
+        *remaining.entry(line.trim()).or_insert(0) += 1;
+    }
+    let mut common = 0;
+    for line in b.lines() {
+        if let Some(count) = remaining.get_mut(line.trim()).filter(|count| **count > 0) {
+            *count -= 1;
+            common += 1;
+        }
+    }
+    let total = a.lines().count() + b.lines().count();
+    if total == 0 { 1.0 } else { 2.0 * common as f64 / total as f64 }
+}
+
+// Length of the character literal at the start of `chars`, such as `'{'` or `'\''`; 0 for a lifetime
+fn char_literal_len(chars: &[char]) -> usize {
+    match chars.get(1) {
+        Some('\\') => chars.iter().skip(3).position(|c| *c == '\'').map_or(0, |p| p + 4),
+        Some(_) if chars.get(2) == Some(&'\'') => 3,
+        _ => 0,
+    }
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn commit(repo: &Repository, files: &[(&str, Option<&str>)], message: &str) -> Oid {
+        let mut index = repo.index().unwrap();
+        for (path, content) in files {
+            let full = repo.workdir().unwrap().join(path);
+            match content {
+                Some(content) => {
+                    std::fs::create_dir_all(full.parent().unwrap()).unwrap();
+                    std::fs::write(&full, content).unwrap();
+                    index.add_path(Path::new(path)).unwrap();
+                }
+                None => {
+                    std::fs::remove_file(&full).unwrap();
+                    index.remove_path(Path::new(path)).unwrap();
+                }
+            }
+        }
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let parents: Vec<Commit> = repo.head().ok()
+            .and_then(|h| h.peel_to_commit().ok())
+            .into_iter()
+            .collect();
+        let parents: Vec<&Commit> = parents.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap()
+    }
+
+    const V1: &str = "struct Analyzer;\n\nimpl Analyzer {\n    fn analyze(&self) {\n        run();\n    }\n}\n";
+    const V2: &str = "use std::fs;\n\nstruct Analyzer;\n\nimpl Analyzer {\n    /// Runs it\n    fn analyze(&self) {\n        run();\n    }\n\n    fn other(&self) {}\n}\n";
+    const V3: &str = "use std::fs;\n\nstruct Analyzer;\n\nimpl Analyzer {\n    /// Runs it\n    fn analyze(&self) -> Result<()> {\n        run()?;\n        Ok(())\n    }\n\n    fn other(&self) {}\n}\n";
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            "analyzer::Analyzer::analyze".parse::<HistoryTarget>().unwrap(),
+            HistoryTarget::Symbol { path: vec!["analyzer".into(), "Analyzer".into(), "analyze".into()] }
+        );
+        assert_eq!(
+            "pkg.Model.save".parse::<HistoryTarget>().unwrap(),
+            HistoryTarget::Symbol { path: vec!["pkg".into(), "Model".into(), "save".into()] }
+        );
+        assert_eq!(
+            "src/lib.rs:10-40".parse::<HistoryTarget>().unwrap(),
+            HistoryTarget::Lines { file: "src/lib.rs".into(), start: 10, end: 40 }
+        );
+        assert_eq!(
+            "src/lib.rs:7".parse::<HistoryTarget>().unwrap(),
+            HistoryTarget::Lines { file: "src/lib.rs".into(), start: 7, end: 7 }
+        );
+        assert!("src/lib.rs:40-10".parse::<HistoryTarget>().is_err());
+        assert!("a::::b".parse::<HistoryTarget>().is_err());
+        assert!("a::b c".parse::<HistoryTarget>().is_err());
+    }
+
+    #[test]
+    fn test_find_definitions() {
+        let path = Path::new("src/analyzer.rs");
+        let symbol = |s: &str| s.split("::").map(String::from).collect::<Vec<_>>();
+
+        let found = find_definitions(V2, path, &symbol("Analyzer::analyze"));
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].start, found[0].end), (6, 9));
+        assert!(found[0].text.starts_with("    /// Runs it\n"));
+
+        assert_eq!(find_definitions(V2, path, &symbol("analyzer::analyze")).len(), 1);
+        assert!(find_definitions(V2, path, &symbol("Report::analyze")).is_empty());
+        assert_eq!(find_definitions(V2, path, &symbol("Analyzer"))[0].end, 3);
+
+        let python = "class Model:\n    def save(self):\n        write()\n\n        flush()\n    def load(self):\n        pass\n";
+        let found = find_definitions(python, Path::new("models.py"), &symbol("Model::save"));
+        assert_eq!((found[0].start, found[0].end), (2, 5));
+
+        // Braces in character literals do not open or close blocks
+        let rust = "fn open(c: char) -> bool {\n    c == '{' || c == '\\''\n}\nfn next<'a>(s: &'a str) {}\n";
+        let found = find_definitions(rust, Path::new("lex.rs"), &symbol("open"));
+        assert_eq!((found[0].start, found[0].end), (1, 3));
+        assert_eq!(find_definitions(rust, Path::new("lex.rs"), &symbol("next"))[0].end, 4);
+    }
+
+    #[test]
+    fn test_map_range() {
+        let old = "a\nb\nc\nd\ne\n";
+        assert_eq!(map_range(old, "x\na\nb\nc\nd\ne\n", 4, 5).unwrap(), (3, 4));
+        assert_eq!(map_range(old, "a\nc\nd\ne\n", 2, 3).unwrap(), (3, 4));
+        assert_eq!(map_range(old, "a\nb\nC\nd\ne\n", 2, 4).unwrap(), (2, 4));
+        // Lines that did not exist before
+        let (start, end) = map_range(old, "a\nb\nnew\nc\nd\ne\n", 3, 3).unwrap();
+        assert!(start > end);
+    }
+
+    #[test]
+    fn test_symbol_history_follows_moves() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+
+        let added = commit(&repo, &[("src/analyzer.rs", Some(V1))], "Add analyzer");
+        let documented = commit(&repo, &[("src/analyzer.rs", Some(V2))], "Document analyze");
+        commit(&repo, &[("README.md", Some("docs\n"))], "Unrelated");
+        let moved = commit(&repo, &[("src/analyzer.rs", None), ("src/core/engine.rs", Some(V2))], "Move analyzer");
+        let changed = commit(&repo, &[("src/core/engine.rs", Some(V3))], "Return errors from analyze");
+
+        let target: HistoryTarget = "Analyzer::analyze".parse().unwrap();
+        let history = history(&repo, &ChangeClassifier::default(), &target, "HEAD").unwrap();
+        let commits: Vec<(&str, RevisionKind)> = history.revisions.iter()
+            .map(|r| (r.commit_id.as_str(), r.kind))
+            .collect();
+        let (added, documented, moved, changed) = (added.to_string(), documented.to_string(), moved.to_string(), changed.to_string());
+        assert_eq!(commits, vec![
+            (changed.as_str(), RevisionKind::Modified),
+            (moved.as_str(), RevisionKind::Moved),
+            (documented.as_str(), RevisionKind::Modified),
+            (added.as_str(), RevisionKind::Added),
+        ]);
+
+        let latest = &history.revisions[0];
+        assert_eq!(latest.file_path, Path::new("src/core/engine.rs"));
+        assert_eq!((latest.start_line, latest.end_line), (6, 10));
+        assert!(latest.diff.contains("+        Ok(())"));
+        assert!(latest.categories.contains(&Category::ErrorHandling));
+        assert_eq!(history.revisions[1].previous_path.as_deref(), Some(Path::new("src/analyzer.rs")));
+
+        let missing: HistoryTarget = "Analyzer::missing".parse().unwrap();
+        assert!(matches!(super::history(&repo, &ChangeClassifier::default(), &missing, "HEAD"), Err(HistorianError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_new_symbol_is_added() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+
+        commit(&repo, &[("src/a.rs", Some("fn run() {\n    a();\n}\n"))], "Add a");
+        let added = commit(&repo, &[("src/b.rs", Some("fn run() {\n    b();\n}\n"))], "Add b");
+        commit(&repo, &[("src/a.rs", None)], "Remove a");
+
+        // An unrelated definition elsewhere with the same name is not where it came from
+        let history = history(&repo, &ChangeClassifier::default(), &"run".parse().unwrap(), "HEAD").unwrap();
+        assert_eq!(history.revisions.len(), 1);
+        assert_eq!(history.revisions[0].commit_id, added.to_string());
+        assert_eq!(history.revisions[0].kind, RevisionKind::Added);
+
+        let corrected = SymbolTracker::new(&repo, &ChangeClassifier::default()).unwrap()
+            .with_corrections(vec![Correction::new(added.to_string(), vec![Category::Logging])])
+            .history(&"run".parse().unwrap(), "HEAD")
+            .unwrap();
+        assert_eq!(corrected.revisions[0].categories, vec![Category::Logging]);
+        assert!(similarity("a\nb\n", "  a\n  b\n") == 1.0 && similarity("a\n", "b\n") == 0.0);
+    }
+
+    #[test]
+    fn test_line_history_follows_renames() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+
+        let added = commit(&repo, &[("lib.rs", Some("a\nb\nc\nd\ne\nf\ng\nh\n"))], "Add");
+        let edited = commit(&repo, &[("lib.rs", Some("a\nb\nc\nD\ne\nf\ng\nh\n"))], "Edit d");
+        commit(&repo, &[("lib.rs", Some("a\nb\nc\nD\ne\nf\ng\nH\n"))], "Edit h");
+        let renamed = commit(&repo, &[("lib.rs", None), ("src/lib.rs", Some("top\na\nb\nc\nD\ne\nf\ng\nH\n"))], "Rename");
+
+        let target: HistoryTarget = "src/lib.rs:4-6".parse().unwrap();
+        let history = history(&repo, &ChangeClassifier::default(), &target, "HEAD").unwrap();
+        let commits: Vec<String> = history.revisions.iter().map(|r| r.commit_id.clone()).collect();
+        assert_eq!(commits, vec![renamed.to_string(), edited.to_string(), added.to_string()]);
+        assert_eq!(history.revisions[0].kind, RevisionKind::Moved);
+        assert!(history.revisions[1].diff.contains("-d\n+D"));
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use git2::{BlameOptions, DiffOptions, Oid, Patch, Repository};
+use serde::{Serialize, Deserialize};
+use crate::{Result, Category};
+use crate::analyzer::Change;
+use crate::config::DefectConfig;
+use crate::lexer::{Lexer, TokenContext};
+use crate::message::{self, ConventionalCommit};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DefectReport {
+    pub fix_commits: Vec<String>,
+    pub bug_introducing: Vec<BugIntroduction>,
+    pub file_density: Vec<FileDefectDensity>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BugIntroduction {
+    pub commit_id: String,
+    pub fixed_by: Vec<String>,
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDefectDensity {
+    pub file_path: PathBuf,
+    pub defects: usize,
+    pub changes: usize,
+    pub density: f64,
+}
+
+/// SZZ-style identification of bug-introducing commits: the lines removed or
+/// modified by a fix are blamed back to the commits that last touched them.
+pub struct SzzAnalyzer<'a> {
+    repo: &'a Repository,
+    config: &'a DefectConfig,
+}
+
+impl<'a> SzzAnalyzer<'a> {
+    pub fn new(repo: &'a Repository, config: &'a DefectConfig) -> Self {
+        Self { repo, config }
+    }
+
+    pub fn is_fix_message(&self, message: &str) -> bool {
+        if let Some(commit) = ConventionalCommit::parse(message) {
+            if self.config.fix_types.iter().any(|t| t.eq_ignore_ascii_case(&commit.kind)) {
+                return true;
+            }
+        }
+
+        message::has_label(message, &self.config.fix_labels)
+    }
+
+    fn is_fix_commit(&self, changes: &[&Change]) -> bool {
+        let Some(first) = changes.first() else {
+            return false;
+        };
+
+        if self.is_fix_message(&first.message) {
+            return true;
+        }
+
+        self.config.use_categories && changes.iter()
+            .any(|c| c.categories.contains(&Category::ErrorHandling))
+    }
+
+    pub fn analyze(&self, changes: &mut [Change]) -> Result<DefectReport> {
+        // Group the analyzed files by commit
+        let mut by_commit: HashMap<&str, Vec<&Change>> = HashMap::new();
+        for change in changes.iter() {
+            by_commit.entry(change.commit_id.as_str()).or_default().push(change);
+        }
+
+        let mut fix_commits: Vec<String> = by_commit.iter()
+            .filter(|(_, changes)| self.is_fix_commit(changes))
+            .map(|(id, _)| id.to_string())
+            .collect();
+        fix_commits.sort();
+
+        // Blame each fix back to the commits that introduced the lines it touched
+        let mut inducing: HashMap<String, (HashSet<String>, HashSet<PathBuf>)> = HashMap::new();
+        for fix in &fix_commits {
+            let files: HashSet<PathBuf> = by_commit[fix.as_str()].iter()
+                .map(|c| c.file_path.clone())
+                .collect();
+
+            for (commit_id, path) in self.blame_fix(Oid::from_str(fix)?, &files)? {
+                let entry = inducing.entry(commit_id).or_default();
+                entry.0.insert(fix.clone());
+                entry.1.insert(path);
+            }
+        }
+
+        // Flag the inducing changes and compute per-file defect density
+        let mut file_stats: HashMap<PathBuf, (usize, usize)> = HashMap::new();
+        for change in changes.iter_mut() {
+            let stats = file_stats.entry(change.file_path.clone()).or_insert((0, 0));
+            stats.1 += 1;
+
+            if let Some((_, files)) = inducing.get(&change.commit_id) {
+                if files.contains(&change.file_path) {
+                    change.fix_inducing = true;
+                    stats.0 += 1;
+                }
+            }
+        }
+
+        for change in changes.iter_mut() {
+            if let Some((defects, total)) = file_stats.get(&change.file_path) {
+                change.metrics.insert("defect_density".to_string(), *defects as f64 / *total as f64);
+            }
+        }
+
+        let mut file_density: Vec<FileDefectDensity> = file_stats.into_iter()
+            .filter(|(_, (defects, _))| *defects > 0)
+            .map(|(file_path, (defects, changes))| FileDefectDensity {
+                file_path,
+                defects,
+                changes,
+                density: defects as f64 / changes as f64,
+            })
+            .collect();
+        file_density.sort_by(|a, b| {
+            b.density.partial_cmp(&a.density).unwrap()
+                .then(b.defects.cmp(&a.defects))
+        });
+
+        let mut bug_introducing: Vec<BugIntroduction> = inducing.into_iter()
+            .map(|(commit_id, (fixes, files))| {
+                let mut fixed_by: Vec<String> = fixes.into_iter().collect();
+                fixed_by.sort();
+                let mut files: Vec<PathBuf> = files.into_iter().collect();
+                files.sort();
+                BugIntroduction { commit_id, fixed_by, files }
+            })
+            .collect();
+        bug_introducing.sort_by(|a, b| a.commit_id.cmp(&b.commit_id));
+
+        Ok(DefectReport {
+            fix_commits,
+            bug_introducing,
+            file_density,
+        })
+    }
+
+    fn blame_fix(&self, fix: Oid, files: &HashSet<PathBuf>) -> Result<Vec<(String, PathBuf)>> {
+        let commit = self.repo.find_commit(fix)?;
+        let Ok(parent) = commit.parent(0) else {
+            return Ok(Vec::new());
+        };
+
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.context_lines(0);
+        let diff = self.repo.diff_tree_to_tree(
+            Some(&parent.tree()?),
+            Some(&commit.tree()?),
+            Some(&mut diff_opts),
+        )?;
+
+        let mut results = Vec::new();
+        for idx in 0..diff.deltas().len() {
+            let Some(patch) = Patch::from_diff(&diff, idx)? else {
+                continue;
+            };
+
+            let delta = patch.delta();
+            let (Some(old_path), Some(new_path)) = (delta.old_file().path(), delta.new_file().path()) else {
+                continue;
+            };
+            if delta.status() == git2::Delta::Added || !files.contains(new_path) {
+                continue;
+            }
+
+            // Collect the removed or modified lines that carry code
+            let old_content = self.repo.find_blob(delta.old_file().id())?;
+            let significant = significant_lines(old_path, &String::from_utf8_lossy(old_content.content()));
+            let mut old_lines = Vec::new();
+            for hunk in 0..patch.num_hunks() {
+                for line_idx in 0..patch.num_lines_in_hunk(hunk)? {
+                    let line = patch.line_in_hunk(hunk, line_idx)?;
+                    if line.origin() != '-' {
+                        continue;
+                    }
+                    if let Some(lineno) = line.old_lineno() {
+                        if significant.get(lineno as usize - 1).copied().unwrap_or(true) {
+                            old_lines.push(lineno as usize);
+                        }
+                    }
+                }
+            }
+
+            if old_lines.is_empty() {
+                continue;
+            }
+
+            let mut blame_opts = BlameOptions::new();
+            blame_opts.newest_commit(parent.id());
+            let blame = self.repo.blame_file(old_path, Some(&mut blame_opts))?;
+
+            let mut seen = HashSet::new();
+            for lineno in old_lines {
+                if let Some(hunk) = blame.get_line(lineno) {
+                    let inducing = hunk.final_commit_id();
+                    if seen.insert(inducing) {
+                        results.push((inducing.to_string(), new_path.to_path_buf()));
+                    }
+                }
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+// Blank lines and comments are ignored, as in the original SZZ refinements; what counts as a
+// comment depends on the language, so `#include` or `#[derive]` stay significant. The whole file
+// is lexed so that lines inside a block comment opened above a hunk are known as comments.
+fn significant_lines(path: &Path, content: &str) -> Vec<bool> {
+    let mut lexer = Lexer::for_path(Some(path));
+    content.lines().map(|line| is_significant_line(&mut lexer, line)).collect()
+}
+
+fn is_significant_line(lexer: &mut Lexer, line: &str) -> bool {
+    lexer.line(line)
+        .iter()
+        .any(|token| token.context != TokenContext::Comment && !line[token.range.clone()].trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn commit_file(repo: &Repository, path: &str, content: &str, message: &str) -> Oid {
+        std::fs::write(repo.workdir().unwrap().join(path), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let parents: Vec<git2::Commit> = repo.head().ok()
+            .and_then(|h| h.peel_to_commit().ok())
+            .into_iter()
+            .collect();
+        let parents: Vec<&git2::Commit> = parents.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap()
+    }
+
+    #[test]
+    fn test_fix_detection() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let config = DefectConfig::default();
+        let szz = SzzAnalyzer::new(&repo, &config);
+
+        assert!(szz.is_fix_message("fix(parser): handle empty input"));
+        assert!(szz.is_fix_message("[bug] wrong total"));
+        assert!(!szz.is_fix_message("feat: add parser"));
+    }
+
+    #[test]
+    fn test_blame_fix() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+
+        commit_file(&repo, "lib.rs", "fn a() {}\n", "feat: add a");
+        let inducing = commit_file(&repo, "lib.rs", "fn a() {}\nfn b() { 1 / 0 }\n", "feat: add b");
+        let fix = commit_file(&repo, "lib.rs", "fn a() {}\nfn b() { 1 }\n", "fix: avoid division by zero");
+
+        let config = DefectConfig::default();
+        let szz = SzzAnalyzer::new(&repo, &config);
+        let files: HashSet<PathBuf> = [PathBuf::from("lib.rs")].into_iter().collect();
+        let results = szz.blame_fix(fix, &files).unwrap();
+
+        assert_eq!(results, vec![(inducing.to_string(), PathBuf::from("lib.rs"))]);
+    }
+
+    #[test]
+    fn test_significant_lines() {
+        let mut rust = Lexer::for_path(Some(Path::new("lib.rs")));
+        assert!(is_significant_line(&mut rust, "let x = 1;"));
+        assert!(!is_significant_line(&mut rust, "   "));
+        assert!(!is_significant_line(&mut rust, "// comment"));
+        assert!(is_significant_line(&mut rust, "#[derive(Debug)]"));
+        assert!(!is_significant_line(&mut rust, "/* open"));
+        assert!(!is_significant_line(&mut rust, "still a comment */"));
+
+        let mut c = Lexer::for_path(Some(Path::new("main.c")));
+        assert!(is_significant_line(&mut c, "#include <stdio.h>"));
+        assert!(is_significant_line(&mut c, "#define MAX 10"));
+
+        assert!(is_significant_line(&mut c, "*ptr = value;"));
+
+        let mut python = Lexer::for_path(Some(Path::new("app.py")));
+        assert!(!is_significant_line(&mut python, "# comment"));
+
+        let lines = significant_lines(Path::new("main.c"), "/*\n * note\n */\n*count += 1;\n");
+        assert_eq!(lines, vec![false, false, false, true]);
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use crate::analyzer::{Change, Period};
+use crate::diff::{self, DiffLine};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum CodeKind {
+    Test,
+    #[default]
+    Production,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TestEvolution {
+    pub periods: Vec<TestPeriodMetrics>,
+    pub untested_hotspots: Vec<UntestedHotspot>,
+    pub test_commit_share: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TestPeriodMetrics {
+    pub period: String,
+    pub test_lines_added: usize,
+    pub production_lines_added: usize,
+    pub commits: usize,
+    pub commits_touching_tests: usize,
+    pub test_commit_share: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UntestedHotspot {
+    pub file_path: PathBuf,
+    pub changes: usize,
+}
+
+const TEST_DIRS: &[&str] = &["test", "tests", "__tests__", "spec", "specs", "testdata", "fixtures"];
+
+const TEST_MARKERS: &[&str] = &[
+    "#[test]",
+    "#[cfg(test)]",
+    "#[tokio::test]",
+    "@Test",
+    "@pytest",
+    "def test_",
+    "func Test",
+    "describe(",
+    "it(",
+    "test(",
+];
+
+pub fn is_test_path(path: &Path) -> bool {
+    let in_test_dir = path.parent()
+        .map(|parent| parent.components().any(|c| {
+            TEST_DIRS.contains(&c.as_os_str().to_string_lossy().to_lowercase().as_str())
+        }))
+        .unwrap_or(false);
+    if in_test_dir {
+        return true;
+    }
+
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let stem = name.split('.').next().unwrap_or(name);
+
+    stem.ends_with("_test")
+        || stem.ends_with("_tests")
+        || stem.starts_with("test_")
+        || stem.ends_with("Test")
+        || stem.ends_with("Tests")
+        || name.contains(".spec.")
+        || name.contains(".test.")
+}
+
+fn is_test_marker(line: &str) -> bool {
+    let line = line.trim_start();
+    TEST_MARKERS.iter().any(|marker| line.starts_with(marker))
+}
+
+/// Whether every changed line sits in test code.
+///
+/// Within a hunk, code after a test marker (changed or context) is test code, as is the whole
+/// hunk when its header names an enclosing test function or module.
+pub fn changes_only_tests(diff: &str) -> bool {
+    let mut in_test = false;
+    let mut changed = false;
+    for line in diff::lines(diff) {
+        let content = match line {
+            DiffLine::Hunk { context } => {
+                in_test = is_test_marker(context) || context.starts_with("mod tests");
+                continue;
+            }
+            DiffLine::Added(content) | DiffLine::Removed(content) | DiffLine::Context(content) => content,
+            DiffLine::Header(_) => continue,
+        };
+        in_test |= is_test_marker(content);
+        if line.changed().is_some() {
+            changed = true;
+            if !in_test {
+                return false;
+            }
+        }
+    }
+    changed
+}
+
+pub fn classify_change(path: &Path, diff: &str) -> CodeKind {
+    if is_test_path(path) || changes_only_tests(diff) {
+        CodeKind::Test
+    } else {
+        CodeKind::Production
+    }
+}
+
+pub fn compute_test_evolution(changes: &[Change], period: Period, hotspot_min_changes: usize) -> TestEvolution {
+    let mut periods: BTreeMap<String, TestPeriodMetrics> = BTreeMap::new();
+    let mut period_commits: HashMap<String, HashSet<&str>> = HashMap::new();
+    let mut test_commits: HashSet<&str> = HashSet::new();
+    let mut all_commits: HashSet<&str> = HashSet::new();
+
+    for change in changes {
+        let key = period.key(&change.timestamp);
+        let metrics = periods.entry(key.clone()).or_insert_with(|| TestPeriodMetrics {
+            period: key.clone(),
+            ..Default::default()
+        });
+
+        let (added, _) = change.line_counts();
+        match change.code_kind {
+            CodeKind::Test => {
+                metrics.test_lines_added += added;
+                test_commits.insert(&change.commit_id);
+            }
+            CodeKind::Production => metrics.production_lines_added += added,
+        }
+
+        period_commits.entry(key).or_default().insert(&change.commit_id);
+        all_commits.insert(&change.commit_id);
+    }
+
+    for (key, metrics) in periods.iter_mut() {
+        let commits = &period_commits[key];
+        metrics.commits = commits.len();
+        metrics.commits_touching_tests = commits.iter().filter(|c| test_commits.contains(*c)).count();
+        metrics.test_commit_share = metrics.commits_touching_tests as f64 / metrics.commits as f64;
+    }
+
+    // Production files that keep changing without any accompanying test change
+    let mut file_changes: HashMap<&Path, (usize, bool)> = HashMap::new();
+    for change in changes.iter().filter(|c| c.code_kind == CodeKind::Production) {
+        let entry = file_changes.entry(change.file_path.as_path()).or_insert((0, false));
+        entry.0 += 1;
+        entry.1 |= test_commits.contains(change.commit_id.as_str());
+    }
+
+    let mut untested_hotspots: Vec<UntestedHotspot> = file_changes.into_iter()
+        .filter(|(_, (count, tested))| !tested && *count >= hotspot_min_changes)
+        .map(|(path, (changes, _))| UntestedHotspot {
+            file_path: path.to_path_buf(),
+            changes,
+        })
+        .collect();
+    untested_hotspots.sort_by(|a, b| b.changes.cmp(&a.changes).then(a.file_path.cmp(&b.file_path)));
+
+    TestEvolution {
+        periods: periods.into_values().collect(),
+        untested_hotspots,
+        test_commit_share: if all_commits.is_empty() {
+            0.0
+        } else {
+            test_commits.len() as f64 / all_commits.len() as f64
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn change(commit_id: &str, path: &str, diff: &str, month: u32) -> Change {
+        Change {
+            commit_id: commit_id.to_string(),
+            timestamp: Utc.with_ymd_and_hms(2024, month, 1, 0, 0, 0).unwrap(),
+            file_path: PathBuf::from(path),
+            diff: diff.to_string(),
+            code_kind: classify_change(Path::new(path), diff),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_path_conventions() {
+        assert!(is_test_path(Path::new("tests/integration.rs")));
+        assert!(is_test_path(Path::new("pkg/server/handler_test.go")));
+        assert!(is_test_path(Path::new("web/src/app.spec.ts")));
+        assert!(is_test_path(Path::new("src/test/java/FooTest.java")));
+        assert!(is_test_path(Path::new("test_models.py")));
+        assert!(!is_test_path(Path::new("src/attest.rs")));
+        assert!(!is_test_path(Path::new("src/main.rs")));
+    }
+
+    #[test]
+    fn test_markers() {
+        assert_eq!(classify_change(Path::new("src/lib.rs"), "+    #[test]\n+    fn works() {}\n"), CodeKind::Test);
+        assert_eq!(classify_change(Path::new("src/lib.rs"), "+    let value = result.expect(\"value\");\n"), CodeKind::Production);
+
+        // A production edit above the test module stays production
+        let diff = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,4 +1,4 @@\n-fn a() -> u32 { 1 }\n+fn a() -> u32 { 2 }\n \n #[cfg(test)]\n mod tests {\n";
+        assert_eq!(classify_change(Path::new("src/lib.rs"), diff), CodeKind::Production);
+        let diff = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -8,2 +8,3 @@ mod tests {\n     fn works() {\n+        assert!(true);\n     }\n";
+        assert_eq!(classify_change(Path::new("src/lib.rs"), diff), CodeKind::Test);
+        let mixed = format!("{}@@ -20,1 +21,2 @@\n #[test]\n+fn more() {{}}\n", diff.replace("mod tests {", ""));
+        assert_eq!(classify_change(Path::new("src/lib.rs"), &mixed), CodeKind::Production);
+    }
+
+    #[test]
+    fn test_compute_test_evolution() {
+        let changes = vec![
+            change("a", "src/lib.rs", "+fn a() {}\n+fn b() {}\n", 1),
+            change("a", "tests/lib.rs", "+fn t() {}\n", 1),
+            change("b", "src/parser.rs", "+fn p() {}\n", 1),
+            change("c", "src/parser.rs", "+fn q() {}\n", 2),
+        ];
+
+        let evolution = compute_test_evolution(&changes, Period::Month, 2);
+        assert_eq!(evolution.periods.len(), 2);
+        assert_eq!(evolution.periods[0].period, "2024-01");
+        assert_eq!(evolution.periods[0].test_lines_added, 1);
+        assert_eq!(evolution.periods[0].production_lines_added, 3);
+        assert_eq!(evolution.periods[0].commits, 2);
+        assert_eq!(evolution.periods[0].commits_touching_tests, 1);
+        assert!((evolution.test_commit_share - 1.0 / 3.0).abs() < f64::EPSILON);
+
+        assert_eq!(evolution.untested_hotspots.len(), 1);
+        assert_eq!(evolution.untested_hotspots[0].file_path, PathBuf::from("src/parser.rs"));
+    }
+}
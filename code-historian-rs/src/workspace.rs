@@ -0,0 +1,348 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use crate::{Result, HistorianError};
+use crate::analyzer::{Analysis, Metrics};
+
+/// A set of repositories analyzed together, loaded from a TOML workspace file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workspace {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub repositories: Vec<WorkspaceRepository>,
+    #[serde(default)]
+    pub identities: Vec<Identity>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceRepository {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+impl WorkspaceRepository {
+    pub fn display_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            self.path.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| self.path.display().to_string())
+        })
+    }
+}
+
+/// One person, with every name and address they have committed under.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Identity {
+    pub name: String,
+    #[serde(default)]
+    pub emails: Vec<String>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+impl Workspace {
+    /// Load a workspace file; repository paths are relative to the file's directory.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let mut workspace: Workspace = toml::from_str(&content)
+            .map_err(|e| HistorianError::Config(format!("Invalid workspace file {}: {}", path.display(), e)))?;
+
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+        for repo in &mut workspace.repositories {
+            if repo.path.is_relative() {
+                repo.path = base.join(&repo.path);
+            }
+        }
+
+        workspace.validate()?;
+        Ok(workspace)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.repositories.is_empty() {
+            return Err(HistorianError::Config("Workspace lists no repositories".to_string()));
+        }
+
+        let mut names = HashSet::new();
+        for repo in &self.repositories {
+            let name = repo.display_name();
+            if !names.insert(name.clone()) {
+                return Err(HistorianError::Config(format!(
+                    "Duplicate repository name '{}' in workspace; set `name` to tell them apart",
+                    name
+                )));
+            }
+            if !repo.path.exists() {
+                return Err(HistorianError::Config(format!(
+                    "Repository '{}' not found at {}",
+                    name,
+                    repo.path.display()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Maps author names and emails onto canonical identities.
+#[derive(Debug, Clone, Default)]
+pub struct IdentityResolver {
+    by_email: HashMap<String, String>,
+    by_name: HashMap<String, String>,
+}
+
+impl IdentityResolver {
+    pub fn new(identities: &[Identity]) -> Self {
+        let mut resolver = Self::default();
+        for identity in identities {
+            for email in &identity.emails {
+                resolver.by_email.insert(email.to_lowercase(), identity.name.clone());
+            }
+            for alias in identity.aliases.iter().chain(std::iter::once(&identity.name)) {
+                resolver.by_name.insert(alias.to_lowercase(), identity.name.clone());
+            }
+        }
+        resolver
+    }
+
+    pub fn canonical(&self, name: &str, email: &str) -> String {
+        self.by_email.get(&email.to_lowercase())
+            .or_else(|| self.by_name.get(&name.to_lowercase()))
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepositoryAnalysis {
+    pub name: String,
+    pub path: PathBuf,
+    pub analysis: Analysis,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthorSummary {
+    pub name: String,
+    pub commits: usize,
+    pub changes: usize,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    pub repositories: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceAnalysis {
+    pub name: Option<String>,
+    pub repositories: Vec<RepositoryAnalysis>,
+    pub metrics: Metrics,
+    pub authors: Vec<AuthorSummary>,
+}
+
+impl WorkspaceAnalysis {
+    /// Merge every repository into one `Analysis`, prefixing file paths with the repository name.
+    pub fn combined(&self) -> Analysis {
+        let mut combined = Analysis {
+            metrics: self.metrics.clone(),
+            ..Default::default()
+        };
+
+        for repo in &self.repositories {
+            combined.changes.extend(repo.analysis.changes.iter().cloned().map(|mut change| {
+                change.file_path = Path::new(&repo.name).join(&change.file_path);
+                change
+            }));
+            combined.patterns.extend(repo.analysis.patterns.iter().cloned());
+        }
+
+        combined.changes.sort_by_key(|c| Reverse(c.timestamp));
+        combined
+    }
+}
+
+/// Run `analyze` over every repository and aggregate the results.
+pub fn analyze_workspace<F>(workspace: &Workspace, mut analyze: F) -> Result<WorkspaceAnalysis>
+where
+    F: FnMut(&WorkspaceRepository) -> Result<Analysis>,
+{
+    let mut results = Vec::new();
+    for repo in &workspace.repositories {
+        tracing::info!("Analyzing {} at {}", repo.display_name(), repo.path.display());
+        results.push((repo.clone(), analyze(repo)?));
+    }
+    Ok(aggregate(workspace, results))
+}
+
+pub fn aggregate(workspace: &Workspace, results: Vec<(WorkspaceRepository, Analysis)>) -> WorkspaceAnalysis {
+    let identities = IdentityResolver::new(&workspace.identities);
+    let mut metrics = Metrics::default();
+    let mut authors: BTreeMap<String, (AuthorSummary, BTreeSet<String>, HashSet<String>)> = BTreeMap::new();
+    let mut repositories = Vec::new();
+
+    for (repo, mut analysis) in results {
+        let name = repo.display_name();
+
+        for change in &mut analysis.changes {
+            change.author = identities.canonical(&change.author, &change.author_email);
+
+            let (summary, repos, commits) = authors.entry(change.author.clone()).or_default();
+            summary.changes += 1;
+            let (added, removed) = change.line_counts();
+            summary.lines_added += added;
+            summary.lines_removed += removed;
+            repos.insert(name.clone());
+            commits.insert(format!("{}:{}", name, change.commit_id));
+        }
+
+        merge_metrics(&mut metrics, &analysis.metrics, &name);
+        repositories.push(RepositoryAnalysis {
+            name,
+            path: repo.path,
+            analysis,
+        });
+    }
+
+    let mut authors: Vec<AuthorSummary> = authors.into_iter()
+        .map(|(name, (summary, repos, commits))| AuthorSummary {
+            name,
+            commits: commits.len(),
+            repositories: repos.into_iter().collect(),
+            ..summary
+        })
+        .collect();
+    authors.sort_by(|a, b| b.commits.cmp(&a.commits).then(a.name.cmp(&b.name)));
+
+    WorkspaceAnalysis {
+        name: workspace.name.clone(),
+        repositories,
+        metrics,
+        authors,
+    }
+}
+
+fn merge_metrics(total: &mut Metrics, other: &Metrics, repo_name: &str) {
+    total.total_commits += other.total_commits;
+    total.total_changes += other.total_changes;
+    total.lines_added += other.lines_added;
+    total.lines_removed += other.lines_removed;
+    total.mechanical_changes += other.mechanical_changes;
+
+    for (category, count) in &other.category_distribution {
+        *total.category_distribution.entry(category.clone()).or_insert(0) += count;
+    }
+    for (file, impact) in &other.impact_distribution {
+        total.impact_distribution.insert(format!("{}/{}", repo_name, file), *impact);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::Change;
+    use crate::Category;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn change(commit_id: &str, author: &str, email: &str, diff: &str) -> Change {
+        Change {
+            commit_id: commit_id.to_string(),
+            author: author.to_string(),
+            author_email: email.to_string(),
+            file_path: PathBuf::from("src/lib.rs"),
+            diff: diff.to_string(),
+            categories: vec![Category::Logic],
+            impact_score: 1.0,
+            ..Default::default()
+        }
+    }
+
+    fn analysis(changes: Vec<Change>) -> Analysis {
+        let mut metrics = Metrics {
+            total_commits: changes.len(),
+            total_changes: changes.len(),
+            ..Default::default()
+        };
+        metrics.category_distribution.insert(Category::Logic, changes.len());
+        metrics.impact_distribution.insert("src/lib.rs".to_string(), 1.0);
+        Analysis {
+            changes,
+            metrics,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_load_workspace() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("api")).unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("web")).unwrap();
+        let file = temp_dir.path().join("workspace.toml");
+
+        std::fs::write(&file, r#"
+name = "acme"
+
+[[repositories]]
+path = "api"
+
+[[repositories]]
+path = "web"
+name = "frontend"
+
+[[identities]]
+name = "Jane Doe"
+emails = ["jane@acme.dev", "jdoe@old-domain.com"]
+aliases = ["jdoe"]
+"#).unwrap();
+
+        let workspace = Workspace::load(&file).unwrap();
+        assert_eq!(workspace.repositories[0].path, temp_dir.path().join("api"));
+        assert_eq!(workspace.repositories[0].display_name(), "api");
+        assert_eq!(workspace.repositories[1].display_name(), "frontend");
+
+        std::fs::write(&file, "[[repositories]]\npath = \"missing\"\n").unwrap();
+        assert!(Workspace::load(&file).is_err());
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let workspace = Workspace {
+            name: Some("acme".to_string()),
+            repositories: Vec::new(),
+            identities: vec![Identity {
+                name: "Jane Doe".to_string(),
+                emails: vec!["jane@acme.dev".to_string()],
+                aliases: vec!["jdoe".to_string()],
+            }],
+        };
+        let repo = |name: &str| WorkspaceRepository {
+            path: PathBuf::from(name),
+            name: None,
+        };
+
+        let result = aggregate(&workspace, vec![
+            (repo("api"), analysis(vec![
+                change("a1", "Jane", "JANE@acme.dev", "+fn a() {}\n"),
+                change("a2", "Bob", "bob@acme.dev", "+fn b() {}\n"),
+            ])),
+            (repo("web"), analysis(vec![
+                change("w1", "jdoe", "jane@home.net", "+x\n-y\n"),
+            ])),
+        ]);
+
+        assert_eq!(result.metrics.total_commits, 3);
+        assert_eq!(result.metrics.category_distribution[&Category::Logic], 3);
+        assert!(result.metrics.impact_distribution.contains_key("web/src/lib.rs"));
+
+        assert_eq!(result.authors[0].name, "Jane Doe");
+        assert_eq!(result.authors[0].commits, 2);
+        assert_eq!(result.authors[0].lines_added, 2);
+        assert_eq!(result.authors[0].repositories, vec!["api".to_string(), "web".to_string()]);
+        assert_eq!(result.repositories[1].analysis.changes[0].author, "Jane Doe");
+
+        let combined = result.combined();
+        assert_eq!(combined.changes.len(), 3);
+        assert!(combined.changes.iter().any(|c| c.file_path == Path::new("web/src/lib.rs")));
+    }
+}
@@ -0,0 +1,84 @@
+package io.codehistorian.intellij.actions
+
+import com.intellij.openapi.actionSystem.AnAction
+import com.intellij.openapi.actionSystem.AnActionEvent
+import com.intellij.openapi.actionSystem.CommonDataKeys
+import com.intellij.openapi.project.Project
+import com.intellij.openapi.ui.popup.JBPopupFactory
+import io.codehistorian.intellij.service.HistorianApiService
+import javax.swing.ListSelectionModel
+
+class CorrectCategoriesAction : AnAction() {
+    override fun actionPerformed(e: AnActionEvent) {
+        val project = e.project ?: return
+        val editor = e.getData(CommonDataKeys.EDITOR) ?: return
+        val file = e.getData(CommonDataKeys.VIRTUAL_FILE) ?: return
+        val apiService = HistorianApiService.getInstance()
+        val line = editor.caretModel.logicalPosition.line + 1
+
+        try {
+            val provenance = apiService.getLineProvenance(file.path, line)
+
+            JBPopupFactory.getInstance()
+                .createPopupChooserBuilder(CATEGORIES)
+                .setSelectionMode(ListSelectionModel.MULTIPLE_INTERVAL_SELECTION)
+                .setSelectedValue(provenance.categories.firstOrNull(), true)
+                .setTitle("Categories of ${provenance.commitId.take(8)}: ${provenance.summary}")
+                .setItemsChosenCallback { chosen ->
+                    submit(project, apiService, provenance, chosen.toList())
+                }
+                .createPopup()
+                .showInBestPositionFor(editor)
+
+        } catch (ex: Exception) {
+            showMessage(project, "Failed to look up the change: ${ex.message}")
+        }
+    }
+
+    override fun update(e: AnActionEvent) {
+        val project = e.project
+        val editor = e.getData(CommonDataKeys.EDITOR)
+        val file = e.getData(CommonDataKeys.VIRTUAL_FILE)
+        e.presentation.isEnabled = project != null && editor != null && file != null
+    }
+
+    private fun submit(
+        project: Project,
+        apiService: HistorianApiService,
+        provenance: HistorianApiService.LineProvenance,
+        categories: List<String>
+    ) {
+        try {
+            apiService.submitFeedback(provenance.commitId, provenance.originalPath, categories)
+            showMessage(project, "Recorded ${categories.joinToString(", ").ifEmpty { "no categories" }} for ${provenance.commitId.take(8)}")
+        } catch (ex: Exception) {
+            showMessage(project, "Failed to record the correction: ${ex.message}")
+        }
+    }
+
+    private fun showMessage(project: Project, message: String) {
+        JBPopupFactory.getInstance()
+            .createMessage(message)
+            .showCenteredInCurrentWindow(project)
+    }
+
+    companion object {
+        private val CATEGORIES = listOf(
+            "Architecture",
+            "Api",
+            "Logic",
+            "Data",
+            "ErrorHandling",
+            "Logging",
+            "Documentation",
+            "Testing",
+            "Performance",
+            "Security",
+            "Refactoring",
+            "Dependencies",
+            "Configuration",
+            "UiUx",
+            "Accessibility"
+        )
+    }
+}
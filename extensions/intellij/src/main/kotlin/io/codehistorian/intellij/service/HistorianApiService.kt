@@ -1,6 +1,7 @@
 package io.codehistorian.intellij.service
 
 import com.google.gson.Gson
+import com.google.gson.annotations.SerializedName
 import com.intellij.openapi.components.Service
 import com.intellij.openapi.components.service
 import com.intellij.openapi.diagnostic.Logger
@@ -9,6 +10,7 @@ import okhttp3.OkHttpClient
 import okhttp3.Request
 import okhttp3.RequestBody.Companion.toRequestBody
 import java.io.IOException
+import java.net.URLEncoder
 import java.util.concurrent.TimeUnit
 
 @Service
@@ -108,6 +110,54 @@ class HistorianApiService {
         }
     }
 
+    fun getLineProvenance(filePath: String, line: Int): LineProvenance {
+        val path = URLEncoder.encode(filePath, Charsets.UTF_8)
+        val request = Request.Builder()
+            .url("${settings.serverUrl}/api/provenance?path=$path&line=$line")
+            .get()
+            .addHeader("X-API-Key", settings.apiKey)
+            .build()
+
+        client.newCall(request).execute().use { response ->
+            if (!response.isSuccessful) {
+                throw IOException("Unexpected response ${response.code}")
+            }
+
+            return gson.fromJson(
+                response.body?.string() ?: throw IOException("Empty response body"),
+                LineProvenance::class.java
+            )
+        }
+    }
+
+    fun submitFeedback(commitId: String, filePath: String?, categories: List<String>): FeedbackResponse {
+        val request = Request.Builder()
+            .url("${settings.serverUrl}/api/feedback")
+            .post(
+                gson.toJson(
+                    mapOf(
+                        "commit" to commitId,
+                        "file_path" to filePath,
+                        "categories" to categories,
+                        "author" to System.getProperty("user.name")
+                    )
+                ).toRequestBody(jsonMediaType)
+            )
+            .addHeader("X-API-Key", settings.apiKey)
+            .build()
+
+        client.newCall(request).execute().use { response ->
+            if (!response.isSuccessful) {
+                throw IOException("Unexpected response ${response.code}")
+            }
+
+            return gson.fromJson(
+                response.body?.string() ?: throw IOException("Empty response body"),
+                FeedbackResponse::class.java
+            )
+        }
+    }
+
     data class AnalysisResponse(
         val id: String,
         val status: String,
@@ -130,6 +180,18 @@ class HistorianApiService {
         val authors: Int
     )
 
+    data class LineProvenance(
+        @SerializedName("commit_id") val commitId: String,
+        val author: String,
+        val summary: String,
+        @SerializedName("original_path") val originalPath: String,
+        val categories: List<String>
+    )
+
+    data class FeedbackResponse(
+        val changes: Int
+    )
+
     companion object {
         fun getInstance(): HistorianApiService = service()
     }